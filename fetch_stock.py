@@ -3,13 +3,16 @@ import yfinance as yf
 import matplotlib.pyplot as plt
 import matplotlib.dates as mdates
 import matplotlib.ticker as mtick
+from matplotlib.lines import Line2D
 import json
 import io
+import math
 import base64
 import importlib
 from datetime import datetime, time, timedelta
 import pandas as pd
 import numpy as np
+from indicators import heikin_ashi
 
 def get_extended_period(period, interval):
     periods = ["1d", "5d", "1mo", "3mo", "6mo", "1y", "2y", "5y", "10y", "max"]
@@ -63,20 +66,571 @@ def get_period_timedelta(period):
     if period == "10y": return timedelta(days=365*10)
     return None
 
-def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=None, time_format="24h", chart_mode="default", period="1d", interval="1m", graph_type="line"):
+DEFAULT_PALETTE = {
+    "up": "#98c379",
+    "down": "#e06c75",
+    "line": "#4674d7",
+    "baseline": "#ABB2BF",
+    "grid": "#43454c",
+    "text": "#ABB2BF",
+}
+
+# Minor-unit currency codes mapped to (major currency, divisor)
+MINOR_CURRENCY_UNITS = {
+    "GBp": ("GBP", 100),
+    "GBX": ("GBP", 100),
+    "ZAc": ("ZAR", 100),
+    "ILA": ("ILS", 100),
+}
+
+# From this price up, compact prices drop the cents, as in the TUI
+WHOLE_PRICE_FROM = 10_000
+
+def round_price(value):
+    # Keep enough precision for sub-dollar and crypto prices; the TUI decides
+    # how many decimals to display.
+    if abs(value) >= 1:
+        return round(float(value), 4)
+    return float(f"{value:.6g}")
+
+def format_price(value, decimals=None, thousands=True, compact=True):
+    # Mirrors Formatter::price in src/format.rs: fixed decimals when the
+    # config sets them, otherwise whole numbers from 10,000 up when compact,
+    # 2 from 1.00 up, 4 from 0.01, then three significant figures.
+    if decimals is None:
+        magnitude = abs(value)
+        if compact and magnitude >= WHOLE_PRICE_FROM:
+            decimals = 0
+        elif magnitude >= 1 or magnitude == 0:
+            decimals = 2
+        elif magnitude >= 0.01:
+            decimals = 4
+        else:
+            decimals = min(int(np.floor(-np.log10(magnitude))) + 3, 10)
+    return f"{value:{',' if thousands else ''}.{decimals}f}"
+
+def nice_ticks(lo, hi, max_ticks, percent=False):
+    # Round values inside [lo, hi] for y-axis labels and gridlines, at most
+    # max_ticks of them. Steps are 1, 2 or 5 (prices also 2.5) times a power of
+    # ten; percentages step by at least 0.1 to suit their one-decimal labels.
+    if not (math.isfinite(lo) and math.isfinite(hi)):
+        return []
+    if hi <= lo:
+        return [lo]
+    max_ticks = max(max_ticks, 2)
+    mantissas = (1, 2, 5) if percent else (1, 2, 2.5, 5)
+    exponent = math.floor(math.log10((hi - lo) / max_ticks))
+    while True:
+        for m in mantissas:
+            step = m * 10.0 ** exponent
+            if percent and step < 0.1 - 1e-9:
+                continue
+            first, last = math.ceil(lo / step), math.floor(hi / step)
+            if last - first + 1 <= max_ticks:
+                return [round(k * step, 10) for k in range(first, last + 1)]
+        exponent += 1
+
+def log_ticks(lo, hi, max_ticks):
+    # Labels for a log price axis: 1, 2 and 5 times powers of ten inside
+    # [lo, hi], so equal gaps mean equal ratios, thinned to at most
+    # max_ticks. A range too narrow for three of them (less than a few x)
+    # falls back to evenly stepped round prices.
+    if not (np.isfinite(lo) and np.isfinite(hi)) or lo <= 0 or hi <= lo:
+        return nice_ticks(lo, hi, max_ticks)
+    max_ticks = max(max_ticks, 2)
+    ticks = []
+    for exponent in range(int(np.floor(np.log10(lo))), int(np.ceil(np.log10(hi))) + 1):
+        for m in (1, 2, 5):
+            tick = m * 10.0 ** exponent
+            if lo <= tick <= hi:
+                ticks.append(round(tick, 10))
+    if len(ticks) < 3:
+        return nice_ticks(lo, hi, max_ticks)
+    stride = int(np.ceil(len(ticks) / max_ticks))
+    return ticks[::stride]
+
+# Regular session of each exchange in its own local time, keyed by Yahoo's
+# exchangeTimezoneName. Sessions are placed on each day in the exchange's
+# time, so they stay right while its DST dates differ from New York's
+# (London's and New York's are three weeks apart in March).
+EXCHANGE_HOURS = {
+    "America/New_York": (time(9, 30), time(16, 0)),
+    "America/Toronto": (time(9, 30), time(16, 0)),
+    "Europe/London": (time(8, 0), time(16, 30)),
+    "Europe/Berlin": (time(9, 0), time(17, 30)),
+    "Europe/Paris": (time(9, 0), time(17, 30)),
+    "Europe/Amsterdam": (time(9, 0), time(17, 30)),
+    "Europe/Zurich": (time(9, 0), time(17, 30)),
+    "Asia/Tokyo": (time(9, 0), time(15, 30)),
+    "Asia/Hong_Kong": (time(9, 30), time(16, 0)),
+    "Australia/Sydney": (time(10, 0), time(16, 0)),
+}
+US_HOURS = ("America/New_York", time(9, 30), time(16, 0))
+# Instruments with a regular session, and those that also trade before and
+# after it. Indices follow their stocks' session without extended hours;
+# futures, currencies and crypto trade (nearly) around the clock.
+SESSION_TYPES = {"EQUITY", "ETF", "MUTUALFUND", "INDEX"}
+EXTENDED_HOURS_TYPES = {"EQUITY", "ETF"}
+# Opacity of pre-market and after-hours stretches of the line
+EXTENDED_ALPHA = 0.45
+# Percent moves under which the price line is drawn faint, and over which
+# bold, unless the TUI passes its own "change_grades"
+DEFAULT_CHANGE_GRADES = {"faint_below": 0.5, "bold_above": 2.0}
+# How the faint and bold price lines differ from a normal one: a
+# (width factor, opacity factor) pair each
+FAINT_LINE = (0.75, 0.6)
+BOLD_LINE = (1.5, 1.0)
+# Percent by which the chart endpoint's live price may differ from the last
+# bar's close before the 1-day chart marks it
+LIVE_PRICE_GAP_PCT = 0.05
+# Room left after the latest bar by the 1-day chart's "elapsed" time axis
+ELAPSED_HEADROOM = timedelta(minutes=15)
+
+def quote_info(ticker):
+    # Yahoo's quote summary (currency, previous close, market state, ...).
+    # It comes from a different endpoint than the bars and breaks on its own
+    # now and then; every field read from it has a fallback, so an empty
+    # summary costs some header figures rather than the whole chart.
+    try:
+        return ticker.info or {}
+    except Exception as e:
+        sys.stderr.write(f"quote summary unavailable: {e}\n")
+        return {}
+
+def instrument_type(ticker):
+    # Yahoo's instrumentType: EQUITY, ETF, INDEX, FUTURE, CURRENCY,
+    # CRYPTOCURRENCY, ...; empty when unknown, which is treated like a stock
+    try:
+        return ticker.get_history_metadata().get('instrumentType') or ""
+    except Exception:
+        return ""
+
+def meta_price(ticker):
+    # The chart endpoint's own latest price (regularMarketPrice in the
+    # metadata that comes with the bars). In a fast market it runs ahead of
+    # the last bar's close; a large gap can also mean a stale bar array.
+    try:
+        price = ticker.get_history_metadata().get('regularMarketPrice')
+        return float(price) if price else None
+    except Exception:
+        return None
+
+def exchange_hours(ticker, instrument):
+    # (timezone, open, close) of the symbol's exchange, open and close in its
+    # local time. Stocks, funds and indices on exchanges in EXCHANGE_HOURS use
+    # those hours. Anything else (futures trade nearly around the clock on
+    # exchanges listed there too) uses Yahoo's current regular period. When
+    # even that is missing: New York's hours for session types, otherwise
+    # round the clock.
+    fallback = US_HOURS if not instrument or instrument in SESSION_TYPES else ("America/New_York", time(), time())
+    try:
+        meta = ticker.get_history_metadata()
+        tz = meta['exchangeTimezoneName']
+    except Exception:
+        return fallback
+    if tz in EXCHANGE_HOURS and (not instrument or instrument in SESSION_TYPES):
+        return (tz, *EXCHANGE_HOURS[tz])
+    try:
+        regular = meta['currentTradingPeriod']['regular']
+        open_at, close_at = (pd.Timestamp(regular[k], unit='s', tz='UTC').tz_convert(tz).time()
+                             for k in ('start', 'end'))
+    except Exception:
+        return fallback
+    return tz, open_at, close_at
+
+def session_bounds(day, hours):
+    # The regular session opening on `day` at the exchange, as naive New York
+    # (open, close). A time in a skipped DST hour moves forward and one in a
+    # repeated hour is taken in standard time. A close at or before the open
+    # (round-the-clock markets) is the next day's.
+    tz, open_at, close_at = hours
+    close_day = day + timedelta(days=1) if close_at <= open_at else day
+    def new_york(d, t):
+        local = pd.Timestamp(datetime.combine(d, t)).tz_localize(tz, ambiguous=False, nonexistent='shift_forward')
+        return local.tz_convert('America/New_York').tz_localize(None).to_pydatetime()
+    return new_york(day, open_at), new_york(close_day, close_at)
+
+def session_mask(index, hours):
+    # True for New York timestamps (naive or aware) inside any day's regular
+    # session. Sessions of far-off exchanges straddle New York midnight, so
+    # each date's next day is checked as well.
+    times = index.tz_localize(None) if index.tz is not None else index
+    mask = np.zeros(len(times), dtype=bool)
+    for day in {d + timedelta(days=k) for d in set(times.date) for k in (0, 1)}:
+        start, end = session_bounds(day, hours)
+        mask |= (times >= start) & (times < end)
+    return mask
+
+def naive_times(index):
+    # Aware New York timestamps as naive ones for plotting: wall-clock time,
+    # except on the day DST ends. Its 25 hours are fitted into 24 by squeezing
+    # midnight up to the repeat of 01:00 into midnight to 01:00, so the
+    # repeated hour isn't drawn twice over. The hour skipped when DST starts
+    # is just a gap. No bar is dropped either way.
+    naive = index.tz_localize(None)
+    offsets = [t.utcoffset() for t in index]
+    fall_back = {index[i].date() for i in range(1, len(index)) if offsets[i] < offsets[i - 1]}
+    if not fall_back:
+        return naive
+    values = list(naive)
+    for day in fall_back:
+        midnight = pd.Timestamp(datetime.combine(day, time())).tz_localize(index.tz)
+        repeat = pd.Timestamp(datetime.combine(day, time(1))).tz_localize(index.tz, ambiguous=False)
+        scale = (repeat.tz_localize(None) - midnight.tz_localize(None)) / (repeat - midnight)
+        for i, t in enumerate(index):
+            if midnight <= t < repeat:
+                values[i] = midnight.tz_localize(None) + (t - midnight) * scale
+    return pd.DatetimeIndex(values)
+
+def regular_session(bars, hours):
+    # Bars from the regular open up to (not including) the close, leaving out
+    # pre-market and after-hours prints
+    return bars[session_mask(bars.index, hours)]
+
+def session_open(day_bars, prepost, hours):
+    # The open is the first bar of the regular session, not the latest one at
+    # or after the opening bell. A pre-market bar is only used before the
+    # session starts.
+    if prepost:
+        regular = regular_session(day_bars, hours)
+        if not regular.empty:
+            return regular.iloc[0]['Open']
+    return day_bars.iloc[0]['Open'] if not day_bars.empty else 0
+
+def previous_day_path(hist, last_date, prepost, vs_open, hours):
+    # The trading day before last_date as a percent path from its own previous
+    # close (or its open, with vs_open), re-timed onto last_date so the two
+    # days line up by time of day. hist is indexed by naive New York times,
+    # which the date split relies on. None without that day in hist.
+    dates = sorted({d for d in hist.index.date if d < last_date})
+    if not dates:
+        return None
+    day = dates[-1]
+    bars = hist[hist.index.date == day]
+    if vs_open:
+        baseline = session_open(bars, prepost, hours)
+    else:
+        before = hist[hist.index.date < day]
+        regular = regular_session(before, hours)
+        closes = (regular if not regular.empty else before)['Close']
+        baseline = closes.iloc[-1] if not closes.empty else bars['Open'].iloc[0]
+    if not baseline:
+        return None
+    path = (bars['Close'] - baseline) / baseline * 100
+    path.index = path.index + (datetime.combine(last_date, time()) - datetime.combine(day, time()))
+    return day, path
+
+def trading_period(ticker, last_date, name):
+    # Yahoo's 'pre', 'regular' or 'post' period for the day as naive New York
+    # (start, end). It knows about early closes (13:00 on half days). None when
+    # it's missing or describes a different day, so the caller keeps the usual
+    # hours.
+    try:
+        period = ticker.get_history_metadata()['currentTradingPeriod'][name]
+        start, end = (pd.Timestamp(period[k], unit='s', tz='UTC').tz_convert('America/New_York')
+                      for k in ('start', 'end'))
+    except Exception:
+        return None
+    if end.date() != last_date:
+        return None
+    return start.tz_localize(None).to_pydatetime(), end.tz_localize(None).to_pydatetime()
+
+def regular_hours_on(ticker, last_date, hours):
+    # last_date's regular (open, close) as naive New York times: Yahoo's, which
+    # know about early closes, or else the exchange's usual hours
+    return trading_period(ticker, last_date, 'regular') or session_bounds(last_date, hours)
+
+def session_elapsed(ticker, last_date, hours):
+    # Fraction of last_date's regular session that has passed, from 0 before
+    # the open to 1 after the close (or for an earlier day)
+    now = pd.Timestamp.now(tz='America/New_York').tz_localize(None).to_pydatetime()
+    if now.date() != last_date:
+        return 1.0
+    open_time, close_time = regular_hours_on(ticker, last_date, hours)
+    return min(max((now - open_time) / (close_time - open_time), 0.0), 1.0)
+
+def session_end(ticker, last_date, prepost):
+    period = trading_period(ticker, last_date, 'post' if prepost else 'regular')
+    return period[1] if period else None
+
+def regular_mask(real_index, regular_hours, hours):
+    # True for bars inside the regular session. regular_hours, Yahoo's
+    # (open, close) for the latest day, overrides the exchange's usual hours
+    # on that day.
+    mask = session_mask(real_index, hours)
+    if regular_hours is not None:
+        open_time, close_time = regular_hours
+        latest = real_index.date == open_time.date()
+        mask[latest] = ((real_index >= open_time) & (real_index < close_time))[latest]
+    return mask
+
+def session_runs(segments, regular):
+    # Split each segment where it crosses into or out of the regular session,
+    # tagging runs with whether they are regular. Runs share their boundary
+    # bar so the line stays joined.
+    if regular is None:
+        return [(start, end, True) for start, end in segments]
+    runs = []
+    for seg_start, seg_end in segments:
+        start = seg_start
+        for i in range(seg_start + 1, seg_end + 1):
+            if i == seg_end or regular[i] != regular[start]:
+                runs.append((start, min(i + 1, seg_end), bool(regular[start])))
+                start = i
+    return runs
+
+def is_intraday(interval):
+    return interval.endswith('m') or interval.endswith('h')
+
+def interval_to_timedelta(interval):
+    if interval.endswith('m'):
+        return timedelta(minutes=int(interval[:-1]))
+    if interval.endswith('h'):
+        return timedelta(hours=int(interval[:-1]))
+    return timedelta(days=1)
+
+def compress_time_gaps(df, interval):
+    # Re-index the bars onto an evenly spaced timeline so overnight and weekend
+    # closures take up no horizontal space. Indicators keep working because the
+    # index is still a DatetimeIndex with a constant bar spacing.
+    step = interval_to_timedelta(interval)
+    base = df.index[0]
+    out = df.copy()
+    out.index = pd.DatetimeIndex([base + step * i for i in range(len(df))])
+    return out
+
+def direction_runs(values, start, end, max_runs=40):
+    # Split points [start, end) into runs of rising or falling moves, as
+    # (run_start, run_end, rising) sharing their boundary points. On long,
+    # choppy lines each move takes the direction of the trend around it and
+    # runs shorter than that window are folded into the run before them, so
+    # there are at most a few dozen artists to draw.
+    window = max(1, (end - start) // max_runs)
+    runs = []
+    for i in range(start, end - 1):
+        rising = values[min(i + window, end - 1)] >= values[max(i + 1 - window, start)]
+        if runs and (runs[-1][2] == rising or runs[-1][1] - runs[-1][0] - 1 < window):
+            runs[-1][1] = i + 2
+        else:
+            runs.append([i, i + 2, rising])
+    return [tuple(run) for run in runs] or [(start, end, True)]
+
+def line_colors(values, start, end, direction_colors, palette):
+    # The pieces to draw points [start, end) in: one in the line color, or
+    # rising and falling runs in the up and down colors.
+    if not direction_colors or end - start < 2:
+        return [(start, end, palette['line'])]
+    return [(a, b, palette['up'] if rising else palette['down'])
+            for a, b, rising in direction_runs(values, start, end)]
+
+def fill_missing_prices(hist):
+    # Yahoo leaves out the open, high or low of some international symbols'
+    # bars, or sends 0, while the close is there. Each falls back to the
+    # bar's close so the markers and candles stay real prices; the day's
+    # range leaves those bars out (see day_range). Returns the patched bars
+    # and how many there were.
+    columns = ["Open", "High", "Low"]
+    missing = ~(hist[columns] > 0)
+    filled = hist.assign(**{c: hist[c].where(~missing[c], hist['Close']) for c in columns})
+    return filled, int(missing.any(axis=1).sum())
+
+def day_range(highs, lows, regular):
+    # The day's high and low, and the positions of the bars they printed in,
+    # from the bars that reported them: a high or low Yahoo left out (NaN or
+    # 0) says nothing about the range, and its close would understate it.
+    # Only regular-session bars count once the session has any (regular is
+    # one flag per bar, or None when all are); before the open the
+    # pre-market's do. Each half is None when no bar reported it.
+    positions = range(len(highs))
+    if regular is not None and any(regular):
+        positions = [i for i in positions if regular[i]]
+    valid_highs = [i for i in positions if highs[i] > 0]
+    valid_lows = [i for i in positions if lows[i] > 0]
+    high = max(valid_highs, key=lambda i: highs[i], default=None)
+    low = min(valid_lows, key=lambda i: lows[i], default=None)
+    return (
+        (highs[high], high) if high is not None else None,
+        (lows[low], low) if low is not None else None,
+    )
+
+def rolling_window(stamps, closes, hours=24):
+    # Where the trailing `hours` of bars start, as the position of the first
+    # bar after the cutoff, and the close they're measured against: that of
+    # the last bar at or before it. stamps are the bars' times in seconds,
+    # oldest first. None when no bar is that old, as for a pair that hasn't
+    # traded so long.
+    cutoff = stamps[-1] - hours * 3600
+    before = [i for i, stamp in enumerate(stamps) if stamp <= cutoff]
+    if not before:
+        return None
+    return before[-1] + 1, closes[before[-1]]
+
+def line_grade(pct_change, grades):
+    # Width and opacity factors for the price line, by the size of the day's
+    # move: a quiet day draws faint, a big one bold. A move of exactly either
+    # threshold is normal, as in the TUI.
+    size = abs(pct_change)
+    if size < grades["faint_below"]:
+        return FAINT_LINE
+    if size > grades["bold_above"]:
+        return BOLD_LINE
+    return (1.0, 1.0)
+
+def smooth(values, segments, window):
+    # Centered moving average over `window` bars, within each segment so
+    # closed hours aren't averaged across. Near a segment's ends the window
+    # shrinks evenly instead of dropping bars, so the first and last points
+    # stay the raw prices.
+    values = np.asarray(values, dtype=float)
+    if window <= 1:
+        return values
+    half = window // 2
+    out = values.copy()
+    for start, end in segments:
+        for i in range(start, end):
+            k = min(half, i - start, end - 1 - i)
+            out[i] = np.nanmean(values[i - k:i + k + 1]) if k > 0 else values[i]
+    return out
+
+def minmax_downsample(x, y, bucket):
+    # Thin a run to each bucket's lowest and highest bar, in time order, so
+    # spikes survive however many bars share a pixel column. Runs short
+    # enough to draw as they are come back unchanged.
+    values = np.asarray(y, dtype=float)
+    if bucket <= 1 or len(values) <= 2 * bucket:
+        return x, y
+    keep = {0, len(values) - 1}
+    for start in range(0, len(values), bucket):
+        chunk = values[start:start + bucket]
+        if np.isnan(chunk).all():
+            continue
+        keep.add(start + int(np.nanargmin(chunk)))
+        keep.add(start + int(np.nanargmax(chunk)))
+    keep = sorted(keep)
+    return x[keep], values[keep]
+
+def plot_run(ax, x, y, color, linewidth, alpha, line_style, label=None, bucket=1):
+    # One stretch of the price line: a line, a dot per bar, or both. Dots are
+    # filled in the line's own color, a mid tone that reads on dark and
+    # light terminal backgrounds alike. With more bars than pixel columns,
+    # only each bucket's extremes are drawn.
+    x, y = minmax_downsample(x, y, bucket)
+    if line_style != "points":
+        ax.plot(x, y, color=color, linewidth=linewidth, alpha=alpha, label=label, zorder=2)
+        label = None
+    if line_style != "line":
+        ax.plot(x, y, linestyle='none', marker='o', markersize=linewidth * 1.8, markeredgewidth=0,
+                color=color, alpha=alpha, label=label, zorder=2)
+
+def session_segments(real_index, interval, gap_factor=3):
+    # Split bar positions into [start, end) runs wherever the gap between
+    # consecutive timestamps exceeds gap_factor bar intervals, so closed hours
+    # and long data holes aren't bridged by a straight line.
+    n = len(real_index)
+    if n < 2 or not is_intraday(interval):
+        return [(0, n)]
+    max_gap = interval_to_timedelta(interval) * gap_factor
+    breaks = [i for i in range(1, n) if real_index[i] - real_index[i - 1] > max_gap]
+    bounds = [0] + breaks + [n]
+    return list(zip(bounds[:-1], bounds[1:]))
+
+def format_multi_day_axis(axes, synthetic_index, real_index, view_start, view_end, color, max_labels=8):
+    # Faint separators at each trading-day boundary plus "Mon 17" style labels.
+    # Called after y-scaling so the separator lines don't affect the limits.
+    dates = real_index.date
+    day_starts = [0] + [i for i in range(1, len(dates)) if dates[i] != dates[i - 1]]
+    half_step = (synthetic_index[1] - synthetic_index[0]) / 2
+
+    for i in day_starts[1:]:
+        x = synthetic_index[i] - half_step
+        if view_start <= x <= view_end:
+            for ax in axes:
+                ax.axvline(x, color=color, linewidth=1.0, alpha=0.5, zorder=1)
+
+    visible = [i for i in day_starts if view_start <= synthetic_index[i] <= view_end]
+    first_visible = synthetic_index.searchsorted(view_start)
+    if not visible or visible[0] != first_visible:
+        visible.insert(0, first_visible)
+    stride = max(1, -(-len(visible) // max_labels))
+    visible = visible[::stride]
+
+    positions = [mdates.date2num(synthetic_index[i]) for i in visible]
+    labels = [real_index[i].strftime('%a %d') for i in visible]
+    axes[-1].xaxis.set_major_locator(mtick.FixedLocator(positions))
+    axes[-1].xaxis.set_major_formatter(mtick.FixedFormatter(labels))
+
+def no_trades_stats(ticker, ticker_symbol, dropped):
+    # The quote for a symbol whose bars all lack a close: the last price Yahoo
+    # has (or the previous close), unchanged, and nothing to chart
+    info = quote_info(ticker)
+    currency = info.get('currency') or "USD"
+    currency, price_divisor = MINOR_CURRENCY_UNITS.get(currency, (currency, 1))
+    price = (info.get('regularMarketPrice') or info.get('previousClose') or 0) / price_divisor
+    return {
+        "symbol": ticker_symbol.upper(),
+        "price": round_price(price),
+        "open": 0,
+        "high": 0,
+        "low": 0,
+        "volume": 0,
+        "change": 0,
+        "pct_change": 0,
+        "currency": currency,
+        "dropped": dropped,
+        "market_state": info.get('marketState') or "",
+        "utc_offset": int(pd.Timestamp.now(tz='America/New_York').utcoffset().total_seconds()),
+        "avg_volume": int(info.get('averageDailyVolume3Month') or info.get('averageVolume') or 0),
+        "no_trades": True,
+    }
+
+def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=None, time_format="24h", chart_mode="default", period="1d", interval="1m", graph_type="line", options=None):
     if active_indicators is None:
         active_indicators = []
+    if options is None:
+        options = {}
+    palette = {**DEFAULT_PALETTE, **options.get("palette", {})}
+    prepost = bool(options.get("prepost", False))
+    vs_open = options.get("baseline") == "open"
+    direction_colors = bool(options.get("direction_colors", False))
+    line_style = options.get("line_style", "line")
+    smoothing = int(options.get("smoothing", 0))
+    log_scale = bool(options.get("log_scale", False))
+    change_grades = {**DEFAULT_CHANGE_GRADES, **(options.get("change_grades") or {})}
+    # A past day to chart instead of the latest one (1d only)
+    day = pd.Timestamp(options["date"]) if options.get("date") else None
 
     try:
         # Fetch data with extended period
         fetch_period = get_extended_period(period, interval)
         ticker = yf.Ticker(ticker_symbol)
-        hist = ticker.history(period=fetch_period, interval=interval)
+        if day is not None:
+            # The day and the few before it, for the previous-day overlay.
+            # The TUI picks an interval Yahoo still has for the whole span.
+            hist = ticker.history(start=day - pd.Timedelta(days=4), end=day + pd.Timedelta(days=1),
+                                  interval=interval, prepost=prepost)
+        else:
+            hist = ticker.history(period=fetch_period, interval=interval, prepost=prepost)
         
         if hist.empty:
-            print(json.dumps({"error": "No data found"}))
+            print(json.dumps({"error": "No data found", "not_found": day is None, "no_trading": day is not None}))
             return
 
+        # Yahoo sometimes pads the history with bars that have no prices
+        missing = hist['Close'].isna()
+        dropped = int(missing.sum())
+        if dropped:
+            sys.stderr.write(f"Dropped {dropped} of {len(hist)} bars with no close price\n")
+            hist = hist[~missing]
+            if hist.empty:
+                # Bars but no prices at all: halted, or nothing traded yet
+                print(json.dumps(no_trades_stats(ticker, ticker_symbol, dropped)))
+                return
+        # As sent, for the day's range
+        reported_highs, reported_lows = hist['High'].tolist(), hist['Low'].tolist()
+        hist, patched = fill_missing_prices(hist)
+        if patched:
+            sys.stderr.write(f"Used the close for {patched} bars with no open, high or low\n")
+
         if hist.index.tz is not None:
             # Convert to NY if possible
             hist.index = hist.index.tz_convert('America/New_York')
@@ -87,7 +641,33 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
             else:
                 pass
 
-        prev_close = ticker.info.get('previousClose')
+        if day is not None and hist.index[-1].date() != day.date():
+            print(json.dumps({"error": f"No trading on {day.strftime('%a %b %d %Y')}", "no_trading": True}))
+            return
+
+        info = quote_info(ticker)
+        instrument = instrument_type(ticker)
+        hours = exchange_hours(ticker, instrument)
+        # Pre- and post-market only exist for stocks and ETFs
+        prepost = prepost and (not instrument or instrument in EXTENDED_HOURS_TYPES)
+
+        # Some exchanges quote in minor units (London in pence as "GBp").
+        # Convert everything to the major unit up front so the header, chart
+        # and alerts all agree; percentages are unaffected.
+        currency = info.get('currency') or "USD"
+        currency, price_divisor = MINOR_CURRENCY_UNITS.get(currency, (currency, 1))
+        if price_divisor != 1:
+            hist[['Open', 'High', 'Low', 'Close']] = hist[['Open', 'High', 'Low', 'Close']] / price_divisor
+
+        if day is not None:
+            # The quote summary's previous close is today's; use the daily
+            # close before the day, so the change is close to close
+            daily = ticker.history(start=day - pd.Timedelta(days=10), end=day, interval='1d')
+            prev_close = float(daily['Close'].iloc[-1]) if not daily.empty else None
+        else:
+            prev_close = info.get('previousClose')
+        if prev_close is not None:
+            prev_close = prev_close / price_divisor
         if prev_close is None:
             if len(hist) > 1:
                 if interval.endswith('m') or interval.endswith('h'):
@@ -108,7 +688,7 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
         latest = hist.iloc[-1]
         current_price = latest['Close']
         change = current_price - prev_close
-        pct_change = (change / prev_close) * 100
+        pct_change = (change / prev_close) * 100 if prev_close else 0.0
         
         last_date = hist.index[-1].date()
         if interval.endswith('m') or interval.endswith('h'):
@@ -116,19 +696,97 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
         else:
             today_data_stats = hist.iloc[[-1]] 
 
+        # With extended hours the day's first bar is pre-market
+        day_open = session_open(today_data_stats, prepost, hours)
+
+        # Crypto trades around the clock, so New York's midnight is an
+        # arbitrary place to start its day: the header's change, high, low
+        # and volume cover the trailing 24 hours instead, against the price
+        # 24 hours ago. The history fetched spans several days, so there is
+        # always a bar from before the window once the pair has traded that
+        # long.
+        rolling_start = None
+        if instrument == "CRYPTOCURRENCY" and period == "1d" and is_intraday(interval) and day is None:
+            window = rolling_window([stamp.timestamp() for stamp in hist.index], list(hist['Close']))
+            if window is not None:
+                first, prev_close = window
+                rolling_start = hist.index[-1] - pd.Timedelta(hours=24)
+                today_data_stats = hist.iloc[first:]
+                change = current_price - prev_close
+                pct_change = (change / prev_close) * 100 if prev_close else 0.0
+                day_open = today_data_stats['Open'].iloc[0]
+
+        # The day's high and low, and where they printed as positions in hist.
+        # Only the bars fetched count, so pre-market extremes show only with
+        # prepost, and then only until the session opens.
+        first_today = len(hist) - len(today_data_stats)
+        regular = session_mask(today_data_stats.index, hours) if prepost and is_intraday(interval) else None
+        day_high, day_low = day_range(reported_highs[first_today:], reported_lows[first_today:], regular)
+        extremes = None
+        if is_intraday(interval) and day_high is not None and day_low is not None:
+            extremes = (first_today + day_high[1], first_today + day_low[1])
+
         stats = {
             "symbol": ticker_symbol.upper(),
-            "price": round(current_price, 2),
-            "open": round(today_data_stats.iloc[0]['Open'], 2) if not today_data_stats.empty else 0,
-            "high": round(today_data_stats['High'].max(), 2) if not today_data_stats.empty else 0,
-            "low": round(today_data_stats['Low'].min(), 2) if not today_data_stats.empty else 0,
+            "price": round_price(current_price),
+            "open": round_price(day_open),
+            "high": round_price(day_high[0]) if day_high is not None else 0,
+            "low": round_price(day_low[0]) if day_low is not None else 0,
             "volume": int(today_data_stats['Volume'].sum()) if not today_data_stats.empty else 0,
-            "change": round(change, 2),
-            "pct_change": round(pct_change, 2)
+            "change": round_price(change),
+            "pct_change": round(pct_change, 2),
+            "currency": currency,
+            # Time of the latest bar: intraday bars are in New York time
+            "as_of": hist.index[-1].strftime('%H:%M ET' if is_intraday(interval) else '%Y-%m-%d'),
+            "dropped": dropped,
+            # Staleness is judged against the newest bar, and only while trading
+            "last_bar": int(hist.index[-1].timestamp()),
+            "market_state": "CLOSED" if day is not None else info.get('marketState') or "",
+            "instrument_type": instrument,
+            # For the TUI's clock, which shows New York time like the bars do
+            "utc_offset": int(pd.Timestamp.now(tz='America/New_York').utcoffset().total_seconds()),
+            # For relative volume: a typical day's volume, and how much of
+            # today's session it should be compared against
+            "avg_volume": int(info.get('averageDailyVolume3Month') or info.get('averageVolume') or 0),
+            "session_elapsed": round(session_elapsed(ticker, last_date, hours), 4),
+            "rolling_24h": rolling_start is not None,
         }
+        if extremes is not None:
+            stats["high_at"] = hist.index[extremes[0]].strftime('%H:%M')
+            stats["low_at"] = hist.index[extremes[1]].strftime('%H:%M')
+        if is_intraday(interval):
+            # For the footer's count of bars received against those expected
+            open_time, close_time = regular_hours_on(ticker, last_date, hours)
+            stats["session_minutes"] = round((close_time - open_time).total_seconds() / 60)
+            stats["regular_bars"] = len(regular_session(today_data_stats, hours))
+
+        # Bid/ask are only meaningful while the regular session is open; outside
+        # of it Yahoo reports stale or zeroed quotes, so omit them entirely.
+        if info.get('marketState') == 'REGULAR':
+            bid = (info.get('bid') or 0) / price_divisor
+            ask = (info.get('ask') or 0) / price_divisor
+            if bid > 0 and ask > 0:
+                stats["bid"] = round_price(bid)
+                stats["ask"] = round_price(ask)
+                stats["bid_size"] = int(info.get('bidSize') or 0)
+                stats["ask_size"] = int(info.get('askSize') or 0)
+
+        # Outside regular hours the live price is the regular close, while the
+        # bars may run into pre- or post-market, so they only compare during it
+        live_price = None
+        if day is None and is_intraday(interval) and info.get('marketState') == 'REGULAR':
+            live_price = meta_price(ticker)
+            if live_price is not None:
+                live_price /= price_divisor
+                stats["live_price"] = round_price(live_price)
+
+        if not options.get("chart", True):
+            print(json.dumps(stats))
+            return
 
         # Prepare Plot Data
-        hist.index = hist.index.tz_localize(None)
+        bar_times = hist.index  # timezone-aware, for the CSV export
+        hist.index = naive_times(hist.index)
         plot_data = hist
         
         # Calculate View Limits (Naive times)
@@ -152,6 +810,26 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
         if view_start < hist.index[0]:
             view_start = hist.index[0]
 
+        # Intraday bars over several days are plotted against a gap-free
+        # timeline; real_index keeps the actual timestamps for labelling.
+        real_index = None
+        if period != "1d" and is_intraday(interval) and len(hist) > 1:
+            real_index = hist.index
+            hist = compress_time_gaps(hist, interval)
+            plot_data = hist
+            view_start = hist.index[real_index.searchsorted(view_start)]
+            view_end = hist.index[-1]
+
+        segments = session_segments(real_index if real_index is not None else hist.index, interval)
+
+        # With extended hours, pre- and post-market stretches of the line are dimmed
+        regular = None
+        regular_hours = None
+        if prepost and is_intraday(interval):
+            regular_hours = trading_period(ticker, last_date, 'regular')
+            regular = regular_mask(real_index if real_index is not None else hist.index, regular_hours, hours)
+        runs = session_runs(segments, regular)
+
         loaded_indicators = []
         separate_plots = 0
         for name in active_indicators:
@@ -182,7 +860,7 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
         main_ax = axes[0]
 
         plot_price = False
-        if chart_mode == "price" or graph_type == "candle":
+        if chart_mode == "price" or graph_type in ("candle", "heikin-ashi"):
             plot_price = True
         elif chart_mode == "percent":
             plot_price = False
@@ -194,8 +872,11 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
             ax.patch.set_alpha(0.0)
         
         # Calculate chart baseline based on view_start
+        baseline_label = 'Prev Close'
         if period == "1d":
             chart_baseline = prev_close
+            if vs_open and day_open:
+                chart_baseline, baseline_label = day_open, 'Open'
         else:
             # Find closest price to view_start
             # Filter plot_data for >= view_start
@@ -205,42 +886,144 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
             else:
                 chart_baseline = prev_close
 
+        # Bars in view, with the same baseline as the percent chart, for CSV export
+        if rolling_start is not None:
+            in_view = bar_times > rolling_start
+        elif period == "1d":
+            in_view = plot_data.index.date == last_date
+        else:
+            in_view = plot_data.index >= view_start
+        view_bars = plot_data[in_view]
+        view_times = bar_times[in_view]
+        utc_times = view_times.tz_convert('UTC') if view_times.tz is not None else view_times.tz_localize('UTC')
+        stats["series"] = {
+            "utc": [t.strftime('%Y-%m-%dT%H:%M:%SZ') for t in utc_times],
+            "et": [t.strftime('%Y-%m-%d %H:%M') for t in view_times],
+            "price": [round_price(p) for p in view_bars['Close']],
+            "pct_change": [round(float(p), 4) for p in (view_bars['Close'] - chart_baseline) / chart_baseline * 100],
+            "volume": [int(v) if v == v else 0 for v in view_bars['Volume']],
+        }
+
+        # Dense series (a week of 1m bars, years of daily ones) draw only each
+        # pixel column's extremes; the series above keeps every bar
+        plot_px = int(w_in * 80)
+        bucket = len(view_bars) // plot_px if len(view_bars) > 2 * plot_px else 1
+
         # Calculate dynamic width for candles and volume
         if len(plot_data) > 1:
             width = (plot_data.index[1] - plot_data.index[0]).total_seconds() / 86400.0 * 0.8
         else:
             width = 0.0005
 
-        if graph_type == "candle":
-            up = plot_data[plot_data.Close >= plot_data.Open]
-            down = plot_data[plot_data.Close < plot_data.Open]
+        if graph_type in ("candle", "heikin-ashi"):
+            candles = heikin_ashi(plot_data) if graph_type == "heikin-ashi" else plot_data
+            up = candles[candles.Close >= candles.Open]
+            down = candles[candles.Close < candles.Open]
 
             # Up candles (Green)
-            main_ax.bar(up.index, up.Close - up.Open, bottom=up.Open, color='#98c379', width=width, zorder=2)
-            main_ax.vlines(up.index, up.Low, up.High, color='#98c379', linewidth=1, zorder=2)
+            main_ax.bar(up.index, up.Close - up.Open, bottom=up.Open, color=palette['up'], width=width, zorder=2)
+            main_ax.vlines(up.index, up.Low, up.High, color=palette['up'], linewidth=1, zorder=2)
 
             # Down candles (Red)
             # height is negative if Close < Open, which is fine, or we can normalize
-            main_ax.bar(down.index, down.Close - down.Open, bottom=down.Open, color='#e06c75', width=width, zorder=2)
-            main_ax.vlines(down.index, down.Low, down.High, color='#e06c75', linewidth=1, zorder=2)
+            main_ax.bar(down.index, down.Close - down.Open, bottom=down.Open, color=palette['down'], width=width, zorder=2)
+            main_ax.vlines(down.index, down.Low, down.High, color=palette['down'], linewidth=1, zorder=2)
             
             if period == "1d":
-                main_ax.axhline(prev_close, color='#ABB2BF', linestyle='--', linewidth=1.0, alpha=0.5, label='Prev Close', zorder=2)
+                main_ax.axhline(chart_baseline, color=palette['baseline'], linestyle='--', linewidth=1.0, alpha=0.5, label=baseline_label, zorder=2)
 
         elif plot_price:
-            main_ax.plot(plot_data.index, plot_data['Close'], color='#4674d7', linewidth=2.0, label='Price', zorder=2)
+            width_factor, alpha_factor = line_grade(pct_change, change_grades)
+            closes = smooth(plot_data['Close'].values, segments, smoothing)
+            for n, (seg_start, seg_end, in_session) in enumerate(runs):
+                for m, (start, end, color) in enumerate(line_colors(closes, seg_start, seg_end, direction_colors, palette)):
+                    plot_run(main_ax, plot_data.index[start:end], closes[start:end], color, 2.0 * width_factor,
+                             (1.0 if in_session else EXTENDED_ALPHA) * alpha_factor, line_style,
+                             label='Price' if n == 0 and m == 0 else None, bucket=bucket)
             if period == "1d":
-                main_ax.axhline(prev_close, color='#ABB2BF', linestyle='--', linewidth=1.0, alpha=0.5, label='Prev Close', zorder=2)
+                main_ax.axhline(chart_baseline, color=palette['baseline'], linestyle='--', linewidth=1.0, alpha=0.5, label=baseline_label, zorder=2)
         else:
-            pct_series = ((plot_data['Close'] - chart_baseline) / chart_baseline) * 100
-            main_ax.plot(plot_data.index, pct_series, color='#4674d7', linewidth=2.5, zorder=2)
-            main_ax.axhline(0, color='#ABB2BF', linestyle='--', linewidth=2.0, zorder=2)
+            width_factor, alpha_factor = line_grade(pct_change, change_grades)
+            closes = smooth(plot_data['Close'].values, segments, smoothing)
+            pct_series = pd.Series((closes - chart_baseline) / chart_baseline * 100, index=plot_data.index)
+            for seg_start, seg_end, in_session in runs:
+                for start, end, color in line_colors(pct_series.values, seg_start, seg_end, direction_colors, palette):
+                    seg = pct_series.iloc[start:end]
+                    plot_run(main_ax, seg.index, seg, color, 2.5 * width_factor,
+                             (1.0 if in_session else EXTENDED_ALPHA) * alpha_factor, line_style, bucket=bucket)
+            main_ax.axhline(0, color=palette['baseline'], linestyle='--', linewidth=2.0, zorder=2)
             main_ax.yaxis.set_major_formatter(mtick.PercentFormatter(decimals=1))
 
+        # The previous trading day behind today's line, for comparing shapes.
+        # The 1-day fetch already spans several days, so this costs no request.
+        # A rolling 24 hours has no calendar day to line the previous one up with.
+        if period == "1d" and is_intraday(interval) and options.get("prev_day", False) and rolling_start is None:
+            previous = previous_day_path(plot_data, last_date, prepost, vs_open, hours)
+            if previous is not None:
+                prev_date, prev_path = previous
+                if plot_price:
+                    prev_path = chart_baseline * (1 + prev_path / 100)
+                main_ax.plot(prev_path.index, prev_path.values, color=palette['baseline'], linewidth=1.5, alpha=0.6, zorder=1)
+                today_color = palette['up' if pct_change >= 0 else 'down'] if graph_type in ("candle", "heikin-ashi") else palette['line']
+                main_ax.legend(handles=[Line2D([], [], color=today_color, linewidth=2.0, label='Today'),
+                                        Line2D([], [], color=palette['baseline'], linewidth=1.5, alpha=0.6, label=prev_date.strftime('%a %b %d'))],
+                               loc='upper left', fontsize='small', frameon=False, labelcolor=palette['text'])
+
+        # The price each fetch saw, stepped from one fetch to the next, over
+        # the bars. Moves inside a bar that were watched live survive here
+        # after Yahoo settles the bar.
+        observed = options.get("observed") or []
+        if period == "1d" and is_intraday(interval) and observed:
+            seen_at = naive_times(pd.to_datetime([t for t, _ in observed], unit='s', utc=True).tz_convert('America/New_York'))
+            seen = np.array([p for _, p in observed], dtype=float)
+            today = seen_at.date == last_date
+            if not plot_price:
+                seen = (seen - chart_baseline) / chart_baseline * 100
+            if today.sum() > 1:
+                main_ax.plot(seen_at[today], seen[today], drawstyle='steps-post', color=palette['text'],
+                             linewidth=1.0, alpha=0.9, zorder=3)
+
+        # Mark the day's high and low
+        if extremes is not None:
+            high_pos, low_pos = extremes
+            high, low = plot_data['High'].iloc[high_pos], plot_data['Low'].iloc[low_pos]
+            if not plot_price:
+                high = (high - chart_baseline) / chart_baseline * 100
+                low = (low - chart_baseline) / chart_baseline * 100
+            main_ax.plot([plot_data.index[high_pos]], [high], marker='^', color=palette['up'], markersize=7, linestyle='none', zorder=3)
+            main_ax.plot([plot_data.index[low_pos]], [low], marker='v', color=palette['down'], markersize=7, linestyle='none', zorder=3)
+
+        if plot_price:
+            decimals, thousands = options.get("decimals"), options.get("thousands", True)
+            compact = options.get("compact_prices", True)
+            main_ax.yaxis.set_major_formatter(mtick.FuncFormatter(lambda v, _: format_price(v, decimals, thousands, compact)))
+
         # Date Formatting and Limits
-        if period == "1d":
-            start_time = datetime.combine(last_date, time(9, 30))
-            end_time = datetime.combine(last_date, time(16, 0))
+        if rolling_start is not None:
+            # The same trailing 24 hours as the header
+            main_ax.set_xlim(rolling_start.tz_localize(None), plot_data.index[-1])
+            time_fmt_str = '%H:%M' if time_format == '24h' else '%I:%M %p'
+            axes[-1].xaxis.set_major_formatter(mdates.DateFormatter(time_fmt_str))
+        elif period == "1d":
+            # Extended hours are New York's. A session spanning New York
+            # midnight (Asia, round-the-clock markets) shows the whole day.
+            open_time, close_time = session_bounds(last_date, hours)
+            around_the_clock = open_time.date() != close_time.date()
+            if around_the_clock:
+                open_time, close_time = datetime.combine(last_date, time()), datetime.combine(last_date + timedelta(days=1), time())
+            start_time = datetime.combine(last_date, time(4, 0)) if prepost else open_time
+            end_time = None if around_the_clock else session_end(ticker, last_date, prepost)
+            if end_time is None:
+                end_time = datetime.combine(last_date, time(20, 0)) if prepost else close_time
+            # Narrower than the session: up to just past the latest bar, or
+            # its last few minutes. Never past the session's own bounds.
+            x_axis = options.get("x_axis", "session")
+            latest_bar = plot_data.index[-1]
+            if x_axis == "elapsed" and latest_bar >= start_time:
+                end_time = min(end_time, latest_bar + ELAPSED_HEADROOM)
+            elif x_axis == "sliding" and latest_bar > start_time:
+                end_time = min(end_time, latest_bar)
+                start_time = max(start_time, end_time - timedelta(minutes=int(options.get("sliding_minutes", 60))))
             main_ax.set_xlim(start_time, end_time)
             
             time_fmt_str = '%H:%M' if time_format == '24h' else '%I:%M %p'
@@ -265,8 +1048,13 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
                  axes[-1].xaxis.set_major_formatter(mdates.DateFormatter('%Y-%m-%d'))
 
         # Style Main Axis
-        main_ax.tick_params(axis='both', colors='#ABB2BF', labelsize=16, width=0, length=0)
-        main_ax.grid(True, color='#43454c', linewidth=0.5)
+        # Small chart areas get fewer, smaller labels so they don't collide
+        compact = options.get("compact", False)
+        main_ax.tick_params(axis='both', colors=palette['text'], labelsize=11 if compact else 16, width=0, length=0)
+        if compact and real_index is None:
+            axes[-1].xaxis.set_major_locator(mdates.AutoDateLocator(minticks=2, maxticks=4))
+        if options.get("gridlines", True):
+            main_ax.grid(True, color=palette['grid'], linewidth=0.5)
         for spine in main_ax.spines.values():
             spine.set_visible(False)
         
@@ -283,12 +1071,39 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
             except Exception as e:
                 sys.stderr.write(f"Error running indicator: {e}\n")
 
+        # The live price as a pointer at the right edge, apart from the last
+        # bar's point, when the two disagree. Drawn on the edge itself so the
+        # y-scaling below keeps it in view.
+        if period == "1d" and live_price is not None and current_price:
+            gap_pct = (live_price - current_price) / current_price * 100
+            if abs(gap_pct) > LIVE_PRICE_GAP_PCT:
+                live_y = live_price if plot_price else (live_price - chart_baseline) / chart_baseline * 100
+                main_ax.plot([main_ax.get_xlim()[1]], [live_y], marker='<', markersize=8, color=palette['text'],
+                             linestyle='none', clip_on=False, zorder=4)
+
         # Handle Y-Scaling
         # Use the manual visible calculation for all periods to ensure scaling matches view
         # main_ax.get_xlim() returns floats (dates converted to numbers), no need for date2num
         x_min_num = main_ax.get_xlim()[0]
         x_max_num = main_ax.get_xlim()[1]
 
+        # A log axis only makes sense for prices: equal gaps become equal
+        # percentage moves. Non-positive values are masked rather than NaN'd.
+        log_y = log_scale and plot_price
+        if log_y:
+            # Setting the scale resets the axis formatter, so the real-price
+            # labels go back on
+            formatter = main_ax.yaxis.get_major_formatter()
+            main_ax.set_yscale('log', nonpositive='mask')
+            main_ax.yaxis.set_major_formatter(formatter)
+            main_ax.yaxis.set_minor_locator(mtick.NullLocator())
+
+        anchor = None
+        if options.get("y_scale") == "anchored":
+            if not plot_price:
+                anchor = 0.0
+            elif period == "1d":
+                anchor = chart_baseline
         for ax in axes:
             visible_y_values = []
             
@@ -345,12 +1160,70 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
             if visible_y_values:
                 y_min = np.nanmin(visible_y_values)
                 y_max = np.nanmax(visible_y_values)
+                # Anchored scaling keeps the baseline in range; tight leaves it
+                # out, and the dashed line is simply clipped when off-scale.
+                # Baselines are drawn in axes coordinates, so the scan above
+                # never sees them.
+                if ax is main_ax and anchor is not None:
+                    y_min, y_max = min(y_min, anchor), max(y_max, anchor)
+                if ax is main_ax and log_y:
+                    positive = [v for v in (y_min, y_max, *visible_y_values) if v > 0]
+                    if positive:
+                        # Padding by a ratio, so it looks the same at both ends
+                        y_min, y_max = min(positive), max(positive)
+                        ratio = (y_max / y_min) ** 0.05 if y_max > y_min else 1.02
+                        ax.set_ylim(y_min / ratio, y_max * ratio)
+                    continue
                 y_range = y_max - y_min
                 if y_range == 0:
                     y_range = abs(y_max) * 0.02 if y_max != 0 else 1.0
                 pad = y_range * 0.05
                 ax.set_ylim(y_min - pad, y_max + pad) 
 
+        # Y labels (and gridlines) at round values, as many as the axis height
+        # fits without the labels crowding each other
+        label_px = (11 if compact else 16) * 80 / 72 * 2.5
+        axis_px = main_ax.get_position().height * h_in * 80
+        max_ticks = min(10, int(axis_px // label_px))
+        y_lo, y_hi = main_ax.get_ylim()
+        if log_y:
+            main_ax.yaxis.set_major_locator(mtick.FixedLocator(log_ticks(y_lo, y_hi, max_ticks)))
+        else:
+            main_ax.yaxis.set_major_locator(mtick.FixedLocator(nice_ticks(y_lo, y_hi, max_ticks, percent=not plot_price)))
+
+        # Thin lines at the regular open and close. Drawn after y-scaling,
+        # whose line scan would otherwise read their axes-relative y values.
+        if period == "1d" and regular is not None:
+            open_time, close_time = regular_hours or session_bounds(last_date, hours)
+            for x in (open_time, close_time):
+                main_ax.axvline(x, color=palette['baseline'], linewidth=1.0, alpha=0.5, zorder=1)
+
+        # Marked price levels as dashed lines labelled at the right edge, in
+        # percent of the baseline on the percent chart. Also drawn after
+        # y-scaling, and only inside the bounds, so a far-off level neither
+        # stretches the axis nor draws off it.
+        levels = options.get("levels") or []
+        if levels and chart_baseline:
+            decimals, thousands = options.get("decimals"), options.get("thousands", True)
+            compact = options.get("compact_prices", True)
+            y_lo, y_hi = main_ax.get_ylim()
+            for level in levels:
+                price = level["price"]
+                y = price if plot_price else (price - chart_baseline) / chart_baseline * 100
+                if not y_lo <= y <= y_hi:
+                    continue
+                main_ax.axhline(y, color=palette['text'], linestyle=(0, (4, 3)), linewidth=1.0, alpha=0.6, zorder=2)
+                text = format_price(price, decimals, thousands, compact)
+                if level.get("label"):
+                    text = f"{level['label']} {text}"
+                main_ax.text(1.0, y, text + " ", transform=main_ax.get_yaxis_transform(), ha='right', va='bottom',
+                             color=palette['text'], alpha=0.8, fontsize=11 if compact else 14, zorder=3)
+            main_ax.set_ylim(y_lo, y_hi)
+
+        if real_index is not None:
+            format_multi_day_axis(axes, hist.index, real_index, view_start, view_end, palette['baseline'],
+                                  max_labels=4 if compact else 8)
+
         for ax in axes[:-1]:
             plt.setp(ax.get_xticklabels(), visible=False)
         
@@ -372,7 +1245,130 @@ def fetch_and_plot(ticker_symbol, width=None, height=None, active_indicators=Non
         traceback.print_exc(file=sys.stderr)
         print(json.dumps({"error": str(e)}))
 
+def fetch_quotes(symbols, with_currency=False):
+    # One batched download for the index strip and the portfolio; the last
+    # two daily closes give the price and the change on the day. The
+    # portfolio also needs each quote's currency, which costs a request per
+    # symbol, so the strip goes without.
+    try:
+        data = yf.download(symbols, period="5d", interval="1d", group_by="ticker",
+                           auto_adjust=False, progress=False, threads=False)
+        quotes = []
+        for symbol in symbols:
+            try:
+                closes = data[symbol]['Close'].dropna()
+            except KeyError:
+                continue
+            if closes.empty:
+                continue
+            price = float(closes.iloc[-1])
+            prev = float(closes.iloc[-2]) if len(closes) > 1 else price
+            quote = {
+                "symbol": symbol,
+                "price": round_price(price),
+                "pct_change": round((price - prev) / prev * 100, 2) if prev else 0.0,
+            }
+            if with_currency:
+                try:
+                    quote["currency"] = yf.Ticker(symbol).fast_info['currency'] or ""
+                except Exception:
+                    quote["currency"] = ""
+            quotes.append(quote)
+        print(json.dumps({"quotes": quotes}))
+    except Exception as e:
+        print(json.dumps({"quotes": [], "error": str(e)}))
+
+def fetch_calendar(symbol):
+    # Next earnings and ex-dividend dates from the calendarEvents module, as
+    # the epoch second of that day's New York midnight, and the trailing
+    # dividend yield in percent. Anything a symbol doesn't have is null.
+    def day_start(d):
+        return int(pd.Timestamp(d).tz_localize('America/New_York').timestamp())
+
+    result = {"next_earnings": None, "ex_dividend": None, "dividend_yield": None}
+    try:
+        ticker = yf.Ticker(symbol)
+        calendar = ticker.calendar or {}
+        today = pd.Timestamp.now(tz='America/New_York').date()
+        upcoming = sorted(d for d in calendar.get('Earnings Date', []) if d >= today)
+        if upcoming:
+            result["next_earnings"] = day_start(upcoming[0])
+        ex_dividend = calendar.get('Ex-Dividend Date')
+        if ex_dividend and ex_dividend >= today:
+            result["ex_dividend"] = day_start(ex_dividend)
+        dividend_yield = ticker.info.get('trailingAnnualDividendYield')
+        if dividend_yield:
+            result["dividend_yield"] = round(float(dividend_yield) * 100, 2)
+        print(json.dumps(result))
+    except Exception as e:
+        result["error"] = str(e)
+        print(json.dumps(result))
+
+def fetch_news(symbol, limit=10):
+    # The latest headlines for a symbol from Yahoo's search endpoint, newest
+    # first. Older yfinance releases return flat items; newer ones nest them
+    # under 'content' with an ISO publish date.
+    result = {"headlines": []}
+    try:
+        headlines = []
+        for item in yf.Search(symbol, max_results=0, news_count=limit).news:
+            content = item.get('content') or item
+            title = content.get('title')
+            if not title:
+                continue
+            provider = content.get('provider') or {}
+            link = (content.get('canonicalUrl') or content.get('clickThroughUrl') or {}).get('url') or content.get('link') or ""
+            published = content.get('providerPublishTime')
+            if published is None and content.get('pubDate'):
+                published = int(pd.Timestamp(content['pubDate']).timestamp())
+            headlines.append({
+                "title": title,
+                "publisher": provider.get('displayName') or content.get('publisher') or "",
+                "link": link,
+                "published": published,
+            })
+        headlines.sort(key=lambda h: h["published"] or 0, reverse=True)
+        result["headlines"] = headlines[:limit]
+        print(json.dumps(result))
+    except Exception as e:
+        result["error"] = str(e)
+        print(json.dumps(result))
+
+def search_symbols(query, limit=5):
+    try:
+        results = []
+        for quote in yf.Search(query, max_results=limit, news_count=0).quotes[:limit]:
+            symbol = quote.get('symbol')
+            if not symbol:
+                continue
+            results.append({
+                "Ticker": symbol,
+                "Name": quote.get('shortname') or quote.get('longname') or "",
+                "Type": quote.get('quoteType', "").title(),
+                "Exchange": quote.get('exchDisp') or quote.get('exchange') or "",
+            })
+        print(json.dumps({"results": results}))
+    except Exception as e:
+        print(json.dumps({"results": [], "error": str(e)}))
+
 if __name__ == "__main__":
+    if len(sys.argv) > 2 and sys.argv[1] == "--search":
+        search_symbols(sys.argv[2])
+        sys.exit(0)
+
+    if len(sys.argv) > 2 and sys.argv[1] == "--calendar":
+        fetch_calendar(sys.argv[2])
+        sys.exit(0)
+
+    if len(sys.argv) > 2 and sys.argv[1] == "--news":
+        fetch_news(sys.argv[2])
+        sys.exit(0)
+
+    if len(sys.argv) > 2 and sys.argv[1] == "--quotes":
+        with_currency = len(sys.argv) > 3 and sys.argv[3] == "--currency"
+        fetch_quotes([s for s in sys.argv[2].split(',') if s], with_currency)
+        sys.exit(0)
+
     symbol = sys.argv[1] if len(sys.argv) > 1 else "AAPL"
     w = int(sys.argv[2]) if len(sys.argv) > 2 else None
     h = int(sys.argv[3]) if len(sys.argv) > 3 else None
@@ -389,5 +1385,6 @@ if __name__ == "__main__":
     period = sys.argv[7] if len(sys.argv) > 7 else "1d"
     interval = sys.argv[8] if len(sys.argv) > 8 else "1m"
     graph_type = sys.argv[9] if len(sys.argv) > 9 else "line"
+    options = json.loads(sys.argv[10]) if len(sys.argv) > 10 else {}
 
-    fetch_and_plot(symbol, w, h, indicators, time_format, chart_mode, period, interval, graph_type)
+    fetch_and_plot(symbol, w, h, indicators, time_format, chart_mode, period, interval, graph_type, options)
@@ -0,0 +1,31 @@
+import pandas as pd
+
+
+def ema(series, span):
+    """Exponential moving average seeded with the first value, as charting
+    platforms compute it (no bias correction for the early bars)."""
+    return series.ewm(span=span, adjust=False).mean()
+
+
+def heikin_ashi(df):
+    """Heikin-Ashi bars for an OHLC frame, on the same index.
+
+    Close is the bar's average price. Open is the midpoint of the previous
+    HA bar, so each bar depends on every one before it; the first bar (and
+    any after a missing one) opens at the midpoint of its own open and close.
+    High and low stretch to cover the HA open and close. These are smoothed
+    values, not traded prices.
+    """
+    ha_close = (df['Open'] + df['High'] + df['Low'] + df['Close']) / 4
+    seeds = ((df['Open'] + df['Close']) / 2).values
+    closes = ha_close.values
+    ha_open = list(seeds)
+    for i in range(1, len(df)):
+        previous = (ha_open[i - 1] + closes[i - 1]) / 2
+        # A missing bar restarts the chain rather than blanking the rest
+        if previous == previous:
+            ha_open[i] = previous
+    ha = pd.DataFrame({'Open': ha_open, 'Close': ha_close.values}, index=df.index)
+    ha['High'] = pd.concat([df['High'], ha['Open'], ha['Close']], axis=1).max(axis=1)
+    ha['Low'] = pd.concat([df['Low'], ha['Open'], ha['Close']], axis=1).min(axis=1)
+    return ha
@@ -1,16 +1,20 @@
 import pandas as pd
 import matplotlib.pyplot as plt
 
+from indicators import ema
+
 PLOT_TYPE = "separate"
 REQUIRES_PRICE = False
 
+def macd_lines(closes):
+    # MACD(12,26,9): the MACD line, its signal line and the histogram between
+    # them, from the whole history so the newest bar's values stay current
+    macd = ema(closes, 12) - ema(closes, 26)
+    signal = ema(macd, 9)
+    return macd, signal, macd - signal
+
 def run(ax, df):
-    # Calculate MACD
-    exp1 = df['Close'].ewm(span=12, adjust=False).mean()
-    exp2 = df['Close'].ewm(span=26, adjust=False).mean()
-    macd = exp1 - exp2
-    signal = macd.ewm(span=9, adjust=False).mean()
-    histogram = macd - signal
+    macd, signal, histogram = macd_lines(df['Close'])
     
     # Plot
     # We ignore x-axis labels for the sub-chart usually, or share x-axis
@@ -0,0 +1,353 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    format::{format_pct, Formatter},
+    logging::state_dir,
+    model::StockStats,
+};
+
+mod parse;
+
+pub use parse::RuleError;
+
+/// Which side of a price level or move an alert watches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    Above,
+    Below,
+}
+
+/// What an alert watches for, from `--alert` or `:alert`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertKind {
+    /// A fixed price, e.g. "above 200" or "below 180"
+    Level { condition: Condition, level: f64 },
+    /// A percent move from the price when the alert was set, e.g. "down 2%"
+    Move { condition: Condition, pct: f64 },
+    /// The day's percent change reaching a level, e.g. "change < -3%"
+    DayChange { condition: Condition, pct: f64 },
+    /// A percent drop from the highest price fetched since the alert was
+    /// set, e.g. "trail 1.5%"
+    TrailingStop { pct: f64 },
+    /// The day's volume reaching a multiple of the average day's, e.g.
+    /// "volume 3x"
+    Volume { multiple: f64 },
+}
+
+impl fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let price = |level| Formatter::default().price(level);
+        match *self {
+            AlertKind::Level { condition: Condition::Above, level } => write!(f, "above {}", price(level)),
+            AlertKind::Level { condition: Condition::Below, level } => write!(f, "below {}", price(level)),
+            AlertKind::Move { condition: Condition::Above, pct } => write!(f, "up {}%", pct),
+            AlertKind::Move { condition: Condition::Below, pct } => write!(f, "down {}%", pct),
+            AlertKind::DayChange { condition: Condition::Above, pct } => write!(f, "change above {}%", pct),
+            AlertKind::DayChange { condition: Condition::Below, pct } => write!(f, "change below {}%", pct),
+            AlertKind::TrailingStop { pct } => write!(f, "trailing stop {}%", pct),
+            AlertKind::Volume { multiple } => write!(f, "volume {}x average", multiple),
+        }
+    }
+}
+
+/// An alert kind and whether it fires more than once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlertRule {
+    pub kind: AlertKind,
+    /// Re-arm once the condition has cleared by the hysteresis margin,
+    /// rather than staying triggered
+    pub repeat: bool,
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind, if self.repeat { " (repeating)" } else { "" })
+    }
+}
+
+/// An alert watched for one symbol. It triggers when its condition holds,
+/// including on the first check. A one-shot alert then stays triggered; a
+/// repeating one re-arms once the value has moved back past the threshold by
+/// `hysteresis` percent, so noise around the level doesn't fire it again.
+#[derive(Clone, Debug)]
+pub struct Alert {
+    pub symbol: String,
+    pub rule: AlertRule,
+    hysteresis: f64,
+    triggered: bool,
+    /// For `Move`, the price when the alert was set; for `TrailingStop`, the
+    /// highest price fetched since. The first check seeds it if unset.
+    anchor: Option<f64>,
+}
+
+impl Alert {
+    pub fn new(symbol: &str, rule: AlertRule, hysteresis: f64) -> Alert {
+        Alert { symbol: symbol.to_string(), rule, hysteresis, triggered: false, anchor: None }
+    }
+
+    /// Measures moves and the trailing high from `price`, the quote showing
+    /// when the alert was set, rather than from the next fetch.
+    pub fn starting_at(mut self, price: f64) -> Alert {
+        if price > 0.0 && matches!(self.rule.kind, AlertKind::Move { .. } | AlertKind::TrailingStop { .. }) {
+            self.anchor = Some(price);
+        }
+        self
+    }
+
+    /// The watched value, the threshold it's compared with and on which side
+    /// the alert triggers. None until a move or trailing stop is anchored.
+    fn measure(&self, stats: &StockStats) -> Option<(f64, f64, Condition)> {
+        let price = stats.price;
+        match self.rule.kind {
+            AlertKind::Level { condition, level } => Some((price, level, condition)),
+            AlertKind::Move { condition: Condition::Above, pct } => {
+                Some((price, self.anchor? * (1.0 + pct / 100.0), Condition::Above))
+            }
+            AlertKind::Move { condition: Condition::Below, pct } | AlertKind::TrailingStop { pct } => {
+                Some((price, self.anchor? * (1.0 - pct / 100.0), Condition::Below))
+            }
+            // As a price, so the hysteresis margin means the same as for a level
+            AlertKind::DayChange { condition, pct } if stats.price - stats.change > 0.0 => {
+                Some((price, (stats.price - stats.change) * (1.0 + pct / 100.0), condition))
+            }
+            AlertKind::DayChange { .. } => None,
+            AlertKind::Volume { multiple } if stats.avg_volume > 0 => {
+                Some((stats.volume as f64, stats.avg_volume as f64 * multiple, Condition::Above))
+            }
+            AlertKind::Volume { .. } => None,
+        }
+    }
+
+    /// Feeds a new quote; true when the alert triggers. Quotes for other
+    /// symbols are ignored. The trailing high only ever rises, so a gap down
+    /// between fetches is measured from the high before the gap.
+    pub fn check(&mut self, stats: &StockStats) -> bool {
+        if stats.symbol != self.symbol || stats.price <= 0.0 {
+            return false;
+        }
+        match self.rule.kind {
+            AlertKind::Move { .. } => {
+                self.anchor.get_or_insert(stats.price);
+            }
+            AlertKind::TrailingStop { .. } => {
+                self.anchor = Some(self.anchor.map_or(stats.price, |high| high.max(stats.price)));
+            }
+            AlertKind::Level { .. } | AlertKind::DayChange { .. } | AlertKind::Volume { .. } => {}
+        }
+        let Some((value, threshold, condition)) = self.measure(stats) else { return false };
+        let margin = threshold * self.hysteresis / 100.0;
+        if !self.triggered {
+            self.triggered = match condition {
+                Condition::Above => value >= threshold,
+                Condition::Below => value <= threshold,
+            };
+            return self.triggered;
+        }
+        if self.rule.repeat {
+            self.triggered = match condition {
+                Condition::Above => value >= threshold - margin,
+                Condition::Below => value <= threshold + margin,
+            };
+        }
+        false
+    }
+
+    /// How a trigger is reported: the kind and what set it off, e.g.
+    /// "AAPL $196.00: down 2% (from $200.00)".
+    pub fn message(&self, stats: &StockStats, fmt: &Formatter) -> String {
+        let money = |value| fmt.money(value, &stats.currency);
+        let detail = match (self.rule.kind, self.anchor) {
+            (AlertKind::Move { .. }, Some(from)) => format!(" (from {})", money(from)),
+            (AlertKind::TrailingStop { .. }, Some(high)) => {
+                let stop = self.measure(stats).map_or(high, |(_, stop, _)| stop);
+                format!(" (high {}, stop {})", money(high), money(stop))
+            }
+            (AlertKind::DayChange { .. }, _) => format!(" (day {})", format_pct(stats.pct_change)),
+            (AlertKind::Volume { .. }, _) => format!(
+                " (now {:.1}x: {} vs {})",
+                stats.volume as f64 / stats.avg_volume.max(1) as f64,
+                fmt.volume(stats.volume),
+                fmt.volume(stats.avg_volume)
+            ),
+            _ => String::new(),
+        };
+        format!("{} {}: {}{}", stats.symbol, money(stats.price), self.rule.kind, detail)
+    }
+}
+
+/// One fired alert, as kept in the alert log.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trigger {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    /// New York's offset from UTC at the time, for showing and grouping by day
+    #[serde(default)]
+    pub utc_offset: Option<i32>,
+    pub symbol: String,
+    pub rule: String,
+    pub price: f64,
+    pub message: String,
+}
+
+/// Feeds a quote to every alert and returns those that triggered. The TUI
+/// and `--daemon` both evaluate alerts through this.
+pub fn check_all(alerts: &mut [Alert], stats: &StockStats, fmt: &Formatter) -> Vec<Trigger> {
+    let at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let mut triggered = Vec::new();
+    for alert in alerts.iter_mut() {
+        if alert.check(stats) {
+            triggered.push(Trigger {
+                at,
+                utc_offset: stats.utc_offset,
+                symbol: stats.symbol.clone(),
+                rule: alert.rule.to_string(),
+                price: stats.price,
+                message: alert.message(stats, fmt),
+            });
+        }
+    }
+    triggered
+}
+
+/// `alerts.jsonl` in the state directory, next to the debug logs.
+pub fn log_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("alerts.jsonl"))
+}
+
+/// Appends a trigger to the alert log.
+pub fn append_log(trigger: &Trigger) -> Result<(), Box<dyn Error>> {
+    let path = log_path().ok_or("can't locate the alert log: HOME is not set")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    writeln!(file, "{}", serde_json::to_string(trigger)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Triggers logged on the current New York day, newest first. Lines that
+/// don't parse are skipped; a missing log is just empty.
+pub fn read_today() -> Result<Vec<Trigger>, Box<dyn Error>> {
+    let Some(path) = log_path() else { return Ok(Vec::new()) };
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+    let day = |secs: i64, offset: Option<i32>| (secs + i64::from(offset.unwrap_or(0))).div_euclid(86_400);
+    let mut today = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("{}: {}", path.display(), e))?;
+        if let Ok(trigger) = serde_json::from_str::<Trigger>(&line)
+            && day(trigger.at as i64, trigger.utc_offset) == day(now, trigger.utc_offset)
+        {
+            today.push(trigger);
+        }
+    }
+    today.reverse();
+    Ok(today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(price: f64) -> StockStats {
+        StockStats { symbol: "AAPL".to_string(), price, currency: "USD".to_string(), ..StockStats::default() }
+    }
+
+    fn alert(kind: AlertKind, repeat: bool) -> Alert {
+        Alert::new("AAPL", AlertRule { kind, repeat }, 0.5)
+    }
+
+    /// Positions in `prices` at which `alert` fired.
+    fn fires(alert: &mut Alert, prices: &[f64]) -> Vec<usize> {
+        prices.iter().enumerate().filter(|(_, price)| alert.check(&quote(**price))).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn level_fires_once_unless_repeating() {
+        let above = AlertKind::Level { condition: Condition::Above, level: 200.0 };
+        let prices = [199.0, 201.0, 202.0, 199.5, 198.0, 201.0];
+        assert_eq!(fires(&mut alert(above, false), &prices), vec![1]);
+        // Dipping within the 0.5% margin isn't enough to re-arm
+        assert_eq!(fires(&mut alert(above, true), &prices), vec![1, 5]);
+    }
+
+    #[test]
+    fn move_is_measured_from_the_price_when_set() {
+        let down = AlertKind::Move { condition: Condition::Below, pct: 2.0 };
+        let mut set_at_200 = alert(down, false).starting_at(200.0);
+        assert_eq!(fires(&mut set_at_200, &[197.0, 196.0]), vec![1]);
+        assert_eq!(set_at_200.message(&quote(196.0), &Formatter::default()), "AAPL $196.00: down 2% (from $200.00)");
+
+        // Without a quote showing, the first fetch is the starting point
+        let up = AlertKind::Move { condition: Condition::Above, pct: 4.0 };
+        assert_eq!(fires(&mut alert(up, false), &[100.0, 103.0, 104.0]), vec![2]);
+    }
+
+    #[test]
+    fn trailing_stop_follows_the_high() {
+        let trail = AlertKind::TrailingStop { pct: 5.0 };
+        let mut stop = alert(trail, false);
+        // The high rises to 110, so the stop moves from 95 to 104.50
+        assert_eq!(fires(&mut stop, &[100.0, 110.0, 106.0, 105.0, 104.0]), vec![4]);
+        assert_eq!(stop.message(&quote(104.0), &Formatter::default()), "AAPL $104.00: trailing stop 5% (high $110.00, stop $104.50)");
+    }
+
+    #[test]
+    fn trailing_high_survives_gaps() {
+        let trail = AlertKind::TrailingStop { pct: 5.0 };
+        // A gap up between fetches raises the high in one step
+        assert_eq!(fires(&mut alert(trail, false), &[100.0, 130.0, 124.0, 123.0]), vec![3]);
+        // A gap down is measured from the high before it, not from the low
+        // it opened at
+        let mut stop = alert(trail, true);
+        assert_eq!(fires(&mut stop, &[100.0, 110.0, 90.0, 91.0]), vec![2]);
+        assert_eq!(stop.anchor, Some(110.0));
+    }
+
+    #[test]
+    fn volume_spike_needs_an_average() {
+        let spike = AlertKind::Volume { multiple: 3.0 };
+        let mut volume = alert(spike, false);
+        let day = |volume, avg_volume| StockStats { volume, avg_volume, ..quote(100.0) };
+        assert!(!volume.check(&day(2_900_000, 1_000_000)));
+        assert!(volume.check(&day(3_100_000, 1_000_000)));
+        assert_eq!(
+            volume.message(&day(3_100_000, 1_000_000), &Formatter::default()),
+            "AAPL $100.00: volume 3x average (now 3.1x: 3.10M vs 1.00M)"
+        );
+        assert!(!alert(spike, false).check(&day(9_000_000, 0)));
+    }
+
+    #[test]
+    fn day_change_is_measured_from_the_previous_close() {
+        let down = AlertKind::DayChange { condition: Condition::Below, pct: -3.0 };
+        let mut change = alert(down, false);
+        assert!(!change.check(&StockStats { change: -2.5, ..quote(97.5) }));
+        assert!(change.check(&StockStats { change: -3.0, ..quote(97.0) }));
+    }
+
+    #[test]
+    fn other_symbols_and_empty_quotes_are_ignored() {
+        let mut level = alert(AlertKind::Level { condition: Condition::Below, level: 200.0 }, false);
+        assert!(!level.check(&StockStats { symbol: "MSFT".to_string(), ..quote(150.0) }));
+        assert!(!level.check(&quote(0.0)));
+        assert!(level.check(&quote(150.0)));
+    }
+}
@@ -0,0 +1,304 @@
+//! The rule grammar shared by `--alert` and `:alert`: an optional subject
+//! ("price", "change", "volume"), a comparison and a value, e.g.
+//! "price > 200", "change < -3%" or "volume > 2x avg", plus the shorter
+//! "above 200", "down 2%" and "trail 1.5%". Any rule may end in "repeat".
+//! There's no ">=" or "<=": alerts fire once the level is reached, so ">"
+//! and "<" already include it.
+
+use std::{error::Error, fmt, ops::Range};
+
+use super::{AlertKind, AlertRule, Condition};
+
+const EXAMPLES: &str = "e.g. \"price > 200\", \"change < -3%\", \"volume > 2x avg\", \"down 2%\" or \"trail 1.5%\"";
+
+/// A rule that doesn't parse, and the part of it that's wrong.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleError {
+    pub rule: String,
+    /// Byte range of the offending word in `rule`; empty at the end when
+    /// something is missing
+    pub span: Range<usize>,
+    pub reason: String,
+}
+
+impl RuleError {
+    /// The reason, then the rule with the offending part underlined, for
+    /// errors on the command line.
+    pub fn highlighted(&self) -> String {
+        let pad = self.rule[..self.span.start].chars().count();
+        let width = self.rule[self.span.clone()].chars().count().max(1);
+        format!("{}\n  {}\n  {}{}", self.reason, self.rule, " ".repeat(pad), "^".repeat(width))
+    }
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}': {}", self.rule.trim(), self.reason)
+    }
+}
+
+impl Error for RuleError {}
+
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+}
+
+impl Token<'_> {
+    fn is(&self, words: &[&str]) -> bool {
+        words.iter().any(|word| self.text.eq_ignore_ascii_case(word))
+    }
+}
+
+/// Splits a rule at whitespace. "<" and ">", with an optional "=", are words
+/// of their own even without spaces around them, so "price>200" works.
+fn tokenize(rule: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = rule.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        if c == '<' || c == '>' {
+            if let Some(&(at, '=')) = chars.peek() {
+                chars.next();
+                end = at + 1;
+            }
+        } else {
+            while let Some(&(at, next)) = chars.peek() {
+                if next.is_whitespace() || next == '<' || next == '>' {
+                    break;
+                }
+                chars.next();
+                end = at + next.len_utf8();
+            }
+        }
+        tokens.push(Token { text: &rule[start..end], start });
+    }
+    tokens
+}
+
+fn comparison(token: &Token) -> Option<Condition> {
+    if token.is(&[">", "above"]) {
+        Some(Condition::Above)
+    } else if token.is(&["<", "below"]) {
+        Some(Condition::Below)
+    } else {
+        None
+    }
+}
+
+struct Parser<'a> {
+    rule: &'a str,
+    tokens: Vec<Token<'a>>,
+    at: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.at).copied()
+    }
+
+    fn error(&self, token: Option<Token>, reason: String) -> RuleError {
+        let span = match token {
+            Some(token) => token.start..token.start + token.text.len(),
+            None => self.rule.len()..self.rule.len(),
+        };
+        RuleError { rule: self.rule.to_string(), span, reason }
+    }
+
+    /// The next word, or an error saying `what` is missing.
+    fn expect(&mut self, what: &str) -> Result<Token<'a>, RuleError> {
+        let token = self.peek().ok_or_else(|| self.error(None, format!("expected {}", what)))?;
+        self.at += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next word if it's one of `words`.
+    fn skip(&mut self, words: &[&str]) {
+        if self.peek().is_some_and(|token| token.is(words)) {
+            self.at += 1;
+        }
+    }
+
+    /// Rejects ">=" and "<=": alerts already fire once the level is reached,
+    /// so they'd silently mean the same as ">" and "<".
+    fn reject_inclusive(&self, token: Token) -> Result<(), RuleError> {
+        if token.is(&[">=", "<="]) {
+            let strict = &token.text[..1];
+            return Err(self.error(Some(token), format!("alerts fire once the level is reached, use \"{}\"", strict)));
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self) -> Result<Condition, RuleError> {
+        let token = self.expect("a comparison, \">\" or \"<\"")?;
+        self.reject_inclusive(token)?;
+        comparison(&token).ok_or_else(|| self.error(Some(token), "expected a comparison, \">\" or \"<\"".to_string()))
+    }
+
+    /// A number of `what`, after stripping `prefix` and `suffix` (each
+    /// optional), that passes `valid`.
+    fn number(
+        &mut self,
+        what: &str,
+        prefix: &[char],
+        suffix: &[char],
+        valid: impl Fn(f64) -> bool,
+    ) -> Result<f64, RuleError> {
+        let token = self.expect(what)?;
+        let digits = token.text.trim_start_matches(prefix).trim_end_matches(suffix);
+        digits
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite() && valid(*n))
+            .ok_or_else(|| self.error(Some(token), format!("expected {}", what)))
+    }
+
+    fn price(&mut self) -> Result<f64, RuleError> {
+        self.number("a price, e.g. 200", &['$'], &[], |n| n > 0.0)
+    }
+
+    /// A move from a reference price, which can't reach 100% down.
+    fn pct(&mut self) -> Result<f64, RuleError> {
+        self.number("a percentage under 100, e.g. 2%", &[], &['%'], |n| n > 0.0 && n < 100.0)
+    }
+
+    /// The day's change, up or down.
+    fn signed_pct(&mut self) -> Result<f64, RuleError> {
+        self.number("a percentage, e.g. -3% or 5%", &[], &['%'], |n| n > -100.0)
+    }
+
+    fn multiple(&mut self) -> Result<f64, RuleError> {
+        self.number("a multiple of the average volume, e.g. 2x", &[], &['x', '×'], |n| n > 0.0)
+    }
+
+    fn kind(&mut self) -> Result<AlertKind, RuleError> {
+        let first = self.expect(&format!("a rule, {}", EXAMPLES))?;
+        self.reject_inclusive(first)?;
+        let kind = if first.is(&["price"]) {
+            AlertKind::Level { condition: self.condition()?, level: self.price()? }
+        } else if first.is(&["change", "chg"]) {
+            AlertKind::DayChange { condition: self.condition()?, pct: self.signed_pct()? }
+        } else if first.is(&["volume", "vol"]) {
+            // The comparison is optional: "volume 3x" reads fine too
+            if let Some(token) = self.peek() {
+                self.reject_inclusive(token)?;
+            }
+            if let Some(token) = self.peek()
+                && let Some(condition) = comparison(&token)
+            {
+                if condition == Condition::Below {
+                    return Err(self.error(Some(token), "volume alerts watch for a spike, e.g. \"volume > 2x avg\"".to_string()));
+                }
+                self.at += 1;
+            }
+            let multiple = self.multiple()?;
+            self.skip(&["avg", "average"]);
+            AlertKind::Volume { multiple }
+        } else if first.is(&["up"]) {
+            AlertKind::Move { condition: Condition::Above, pct: self.pct()? }
+        } else if first.is(&["down"]) {
+            AlertKind::Move { condition: Condition::Below, pct: self.pct()? }
+        } else if first.is(&["trail", "trailing"]) {
+            self.skip(&["stop"]);
+            AlertKind::TrailingStop { pct: self.pct()? }
+        } else if let Some(condition) = comparison(&first) {
+            AlertKind::Level { condition, level: self.price()? }
+        } else {
+            return Err(self.error(Some(first), format!("unknown rule, expected {}", EXAMPLES)));
+        };
+        Ok(kind)
+    }
+}
+
+impl AlertRule {
+    /// Parses a rule in the grammar above, optionally followed by "repeat".
+    pub fn parse(s: &str) -> Result<AlertRule, RuleError> {
+        let mut parser = Parser { rule: s, tokens: tokenize(s), at: 0 };
+        let kind = parser.kind()?;
+        let repeat = parser.peek().is_some_and(|token| token.is(&["repeat"]));
+        if repeat {
+            parser.at += 1;
+        }
+        if let Some(extra) = parser.peek() {
+            return Err(parser.error(Some(extra), format!("unexpected '{}'", extra.text)));
+        }
+        Ok(AlertRule { kind, repeat })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: AlertKind, repeat: bool) -> AlertRule {
+        AlertRule { kind, repeat }
+    }
+
+    #[test]
+    fn parses_each_form() {
+        let cases = [
+            ("price > 200", rule(AlertKind::Level { condition: Condition::Above, level: 200.0 }, false)),
+            ("price>200", rule(AlertKind::Level { condition: Condition::Above, level: 200.0 }, false)),
+            ("PRICE < $180.5", rule(AlertKind::Level { condition: Condition::Below, level: 180.5 }, false)),
+            ("above 200", rule(AlertKind::Level { condition: Condition::Above, level: 200.0 }, false)),
+            ("change < -3%", rule(AlertKind::DayChange { condition: Condition::Below, pct: -3.0 }, false)),
+            ("chg > 5%", rule(AlertKind::DayChange { condition: Condition::Above, pct: 5.0 }, false)),
+            ("volume 3x avg", rule(AlertKind::Volume { multiple: 3.0 }, false)),
+            ("vol > 2x", rule(AlertKind::Volume { multiple: 2.0 }, false)),
+            ("trail stop 1.5%", rule(AlertKind::TrailingStop { pct: 1.5 }, false)),
+            ("trailing 2%", rule(AlertKind::TrailingStop { pct: 2.0 }, false)),
+            ("down 2%", rule(AlertKind::Move { condition: Condition::Below, pct: 2.0 }, false)),
+            ("up 4% repeat", rule(AlertKind::Move { condition: Condition::Above, pct: 4.0 }, true)),
+            ("price > 200 repeat", rule(AlertKind::Level { condition: Condition::Above, level: 200.0 }, true)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(AlertRule::parse(input), Ok(expected), "{}", input);
+        }
+    }
+
+    /// The error's span, as the text it covers.
+    fn error_at(input: &str) -> (String, &str) {
+        let e = AlertRule::parse(input).unwrap_err();
+        (e.reason.clone(), &input[e.span])
+    }
+
+    #[test]
+    fn missing_value_points_past_the_end() {
+        let e = AlertRule::parse("price >").unwrap_err();
+        assert_eq!(e.span, 7..7);
+        assert!(e.reason.starts_with("expected a price"), "{}", e.reason);
+    }
+
+    #[test]
+    fn volume_below_points_at_the_comparison() {
+        let (reason, at) = error_at("volume < 2x");
+        assert_eq!(at, "<");
+        assert!(reason.contains("spike"), "{}", reason);
+    }
+
+    #[test]
+    fn trailing_word_points_at_the_word() {
+        let (reason, at) = error_at("price > 200 please");
+        assert_eq!(at, "please");
+        assert_eq!(reason, "unexpected 'please'");
+    }
+
+    #[test]
+    fn rejects_inclusive_comparisons() {
+        for (input, op) in [("price >= 200", ">="), ("price<=200", "<="), (">= 200", ">="), ("volume >= 2x", ">=")] {
+            let (reason, at) = error_at(input);
+            assert_eq!(at, op, "{}", input);
+            assert!(reason.contains(&format!("use \"{}\"", &op[..1])), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn highlights_the_span() {
+        let e = AlertRule::parse("price > abc").unwrap_err();
+        assert_eq!(e.highlighted().lines().last(), Some("          ^^^"));
+    }
+}
@@ -0,0 +1,3 @@
+//! Data providers. Only Yahoo, through the Python fetcher, for now.
+
+pub mod yahoo;
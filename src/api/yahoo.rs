@@ -0,0 +1,185 @@
+//! Responses from the Yahoo fetcher script and how its output is parsed.
+
+use std::{error::Error, fmt, io, process::Command};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{cli::Provider, model::StockStats};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TickerRecord {
+    #[serde(rename = "Ticker")]
+    pub ticker: String,
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Type")]
+    pub kind: String,
+    #[serde(rename = "Exchange", default)]
+    pub exchange: String,
+}
+
+/// A market index in the strip above the header, or a portfolio symbol.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MiniQuote {
+    pub symbol: String,
+    pub price: f64,
+    pub pct_change: f64,
+    /// Only looked up for the portfolio
+    #[serde(default)]
+    pub currency: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct QuotesResponse {
+    #[serde(default)]
+    pub quotes: Vec<MiniQuote>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Upcoming corporate events for a symbol. Dates are epoch seconds of that
+/// day's New York midnight.
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+pub struct Calendar {
+    #[serde(default)]
+    pub next_earnings: Option<i64>,
+    #[serde(default)]
+    pub ex_dividend: Option<i64>,
+    /// Trailing yield in percent; absent for symbols paying no dividend
+    #[serde(default)]
+    pub dividend_yield: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct CalendarResponse {
+    #[serde(flatten)]
+    pub calendar: Calendar,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A news story about a symbol.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Headline {
+    pub title: String,
+    #[serde(default)]
+    pub publisher: String,
+    #[serde(default)]
+    pub link: String,
+    /// Epoch seconds; absent when the feed doesn't say
+    #[serde(default)]
+    pub published: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct NewsResponse {
+    #[serde(default)]
+    pub headlines: Vec<Headline>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SearchResponse {
+    #[serde(default)]
+    pub results: Vec<TickerRecord>,
+}
+
+/// Why a fetch produced no quote at all. A symbol Yahoo doesn't know still
+/// comes back as a quote, with its `error` set.
+#[derive(Debug)]
+pub enum FetchError {
+    /// python3 couldn't be started
+    Launch(io::Error),
+    /// The script exited with an error; holds the last line of its stderr
+    Script(String),
+    /// The script's output wasn't a quote
+    Output(serde_json::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Launch(e) => write!(f, "couldn't run python3: {}", e),
+            FetchError::Script(reason) => write!(f, "the fetcher failed: {}", reason),
+            FetchError::Output(e) => write!(f, "unreadable fetcher output: {}", e),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+/// The fetcher's result, skipping anything it printed before the JSON.
+pub fn parse_fetch_output(success: bool, stdout: &str, stderr: &str) -> Result<StockStats, FetchError> {
+    parse_script_output(success, stdout, stderr)
+}
+
+/// A script's JSON response, skipping anything it printed before it.
+fn parse_script_output<T: DeserializeOwned>(success: bool, stdout: &str, stderr: &str) -> Result<T, FetchError> {
+    if !success {
+        // A traceback ends with the exception, which is the useful part
+        let reason = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+        return Err(FetchError::Script(reason.trim().to_string()));
+    }
+
+    let json_start = stdout.find('{').unwrap_or(0);
+    let json_str = &stdout[json_start..];
+
+    serde_json::from_str(json_str).map_err(FetchError::Output)
+}
+
+/// Runs `provider`'s script with `args`, for the lookups beside the main
+/// fetch (search, quotes, calendar, news), and parses its JSON response.
+pub fn run_script<T: DeserializeOwned>(provider: Provider, args: &[&str]) -> Result<T, FetchError> {
+    let output = Command::new("python3")
+        .arg(provider.script())
+        .args(args)
+        .output()
+        .map_err(FetchError::Launch)?;
+    parse_script_output(
+        output.status.success(),
+        &String::from_utf8_lossy(&output.stdout),
+        &String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_output_skips_leading_noise() {
+        let response: SearchResponse =
+            parse_script_output(true, "warming up\n{\"results\": [{\"Ticker\": \"BRK-B\", \"Name\": \"Berkshire\", \"Type\": \"EQUITY\"}]}", "").unwrap();
+        assert_eq!(response.results[0].ticker, "BRK-B");
+    }
+
+    #[test]
+    fn failed_script_reports_last_stderr_line() {
+        let err = parse_script_output::<SearchResponse>(false, "", "Traceback\n  line 3\nValueError: bad symbol\n\n").unwrap_err();
+        assert_eq!(err.to_string(), "the fetcher failed: ValueError: bad symbol");
+    }
+
+    #[test]
+    fn fetch_output_with_a_short_column_parses() {
+        // Mid-session, Yahoo can send one more timestamp than closes
+        let stdout = r#"{"symbol": "AAPL", "price": 231.5, "series": {
+            "utc": ["2026-03-02T14:30:00Z", "2026-03-02T14:31:00Z"], "et": ["2026-03-02 09:30", "2026-03-02 09:31"],
+            "price": [231.5], "pct_change": [0.1, 0.2], "volume": [100, 200]}}"#;
+        let stats = parse_fetch_output(true, stdout, "").unwrap();
+        let series = stats.series.unwrap();
+        assert_eq!((series.len(), series.unmatched()), (1, 1));
+    }
+
+    #[test]
+    fn stripped_down_payloads_still_parse() {
+        // Only a price, next to a field under a name this doesn't know
+        let stats = parse_fetch_output(true, r#"{"symbol": "AAPL", "price": 231.5, "regularMarketDayHigh": 232.0}"#, "").unwrap();
+        assert_eq!((stats.symbol.as_str(), stats.price, stats.high), ("AAPL", 231.5, 0.0));
+        assert!(stats.currency.is_empty() && stats.series.is_none() && stats.error.is_none());
+
+        // The fetcher's own errors carry nothing else
+        let failed = parse_fetch_output(true, r#"{"error": "No data found, symbol may be delisted"}"#, "").unwrap();
+        assert_eq!(failed.error.as_deref(), Some("No data found, symbol may be delisted"));
+    }
+}
@@ -0,0 +1,905 @@
+//! What keys and `:` commands do.
+
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{quote_summary, App, InputMode, ToastLevel, SMOOTHING_WINDOWS};
+use crate::{
+    alert::{self, Alert},
+    cli::{PastDay, INTERVALS},
+    command::{self, Command as CommandLine},
+    config::XAxis,
+    export,
+    fetch::{interval_allowed, DataSource},
+    keymap::{self, Action},
+    levels::Level,
+    portfolio::Portfolio,
+    snapshot,
+    symbol::normalize_symbol,
+};
+
+/// Runs a main-screen action. Returns `true` when the app should quit.
+fn apply_action(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::Quit => return true,
+        Action::OpenTicker => {
+            app.input_mode = InputMode::Editing;
+            app.input.clear();
+            app.character_index = 0;
+            app.on_search_input_changed();
+        }
+        Action::OpenSettings => {
+            app.input_mode = InputMode::SettingsMain;
+            app.settings_main_state.select(Some(0));
+        }
+        Action::OpenCommandLine => {
+            app.input_mode = InputMode::Command;
+            app.command_input.clear();
+        }
+        Action::HistoryBack | Action::HistoryForward => {
+            let symbol = match action {
+                Action::HistoryBack => app.history.back(),
+                _ => app.history.forward(),
+            };
+            match symbol.map(str::to_string) {
+                Some(symbol) => {
+                    app.show_ticker(symbol);
+                }
+                None => app.toast(ToastLevel::Info, "No more symbols in the history".to_string()),
+            }
+        }
+        Action::PreviousDay => {
+            let earlier = match &app.date {
+                Some(day) => day.step(-1),
+                None => PastDay::latest(),
+            };
+            match earlier {
+                Some(day) => {
+                    app.day_step = -1;
+                    app.show_day(Some(day));
+                }
+                None => app.toast(ToastLevel::Info, "Yahoo keeps intraday bars for about two years".to_string()),
+            }
+        }
+        Action::NextDay => match &app.date {
+            Some(day) => {
+                let later = day.step(1);
+                app.day_step = 1;
+                app.show_day(later);
+            }
+            None => app.toast(ToastLevel::Info, "Already showing today".to_string()),
+        },
+        Action::ShowHistory => {
+            app.input_mode = InputMode::History;
+            app.history_state.select(Some(0));
+        }
+        Action::ShowAlertLog => match alert::read_today() {
+            Ok(triggers) => {
+                app.alert_log = triggers;
+                app.alert_log_state.select(if app.alert_log.is_empty() { None } else { Some(0) });
+                app.input_mode = InputMode::AlertLog;
+            }
+            Err(e) => app.toast(ToastLevel::Error, format!("Alert log: {}", e)),
+        },
+        Action::ShowNews => {
+            if !matches!(app.source, DataSource::Live { .. }) {
+                app.toast(ToastLevel::Info, "News is only fetched live".to_string());
+                return false;
+            }
+            let len = app.news.get(&app.ticker).map_or(0, |known| known.headlines.len());
+            app.news_state.select(if len == 0 { None } else { Some(0) });
+            app.input_mode = InputMode::News;
+        }
+        Action::ShowPortfolio => {
+            if app.portfolio.is_empty() {
+                let path = Portfolio::default_path().map_or("portfolio.toml".to_string(), |p| p.display().to_string());
+                app.toast(ToastLevel::Info, format!("No holdings: add them to {}", path));
+                return false;
+            }
+            if !matches!(app.source, DataSource::Live { .. }) {
+                app.toast(ToastLevel::Info, "The portfolio is only quoted live".to_string());
+                return false;
+            }
+            app.portfolio_state.select(Some(0));
+            app.input_mode = InputMode::Portfolio;
+        }
+        Action::ShowLevels => {
+            let len = app.levels.get(&app.ticker).len();
+            app.levels_state.select(if len == 0 { None } else { Some(0) });
+            app.input_mode = InputMode::Levels;
+        }
+        Action::ShowGrid => {
+            if app.grid.is_some() {
+                app.grid_view = true;
+            } else {
+                app.toast(ToastLevel::Info, "Only available with --grid".to_string());
+            }
+        }
+        Action::ShowHelp => {
+            app.input_mode = InputMode::Help;
+            app.help_scroll = 0;
+        }
+        Action::ToggleFullscreen => {
+            app.fullscreen = !app.fullscreen;
+        }
+        Action::CycleLineStyle => {
+            app.line_style = app.line_style.next();
+            app.toast(ToastLevel::Info, format!("Line style: {}", app.line_style.label()));
+            app.request_refresh();
+        }
+        Action::CycleSmoothing => {
+            let current = SMOOTHING_WINDOWS.iter().position(|&n| n == app.smoothing).unwrap_or(0);
+            app.smoothing = SMOOTHING_WINDOWS[(current + 1) % SMOOTHING_WINDOWS.len()];
+            let message = match (app.smoothing, app.chart_type.as_str()) {
+                (0, _) => "Smoothing off".to_string(),
+                (n, "line") => format!("Smoothing: {}-bar average", n),
+                (n, _) => format!("Smoothing: {}-bar average, shows on the line chart", n),
+            };
+            app.toast(ToastLevel::Info, message);
+            app.request_refresh();
+        }
+        Action::ToggleYScale => {
+            app.y_scale = app.y_scale.toggled();
+            app.toast(ToastLevel::Info, format!("Y axis: {}", app.y_scale.label()));
+            app.request_refresh();
+        }
+        Action::CycleXAxis => {
+            app.x_axis = app.x_axis.next();
+            let message = match (app.x_axis, app.timeframe.as_str()) {
+                (XAxis::Sliding, "1d") => format!("Time axis: last {} minutes", app.sliding_minutes),
+                (x_axis, "1d") => format!("Time axis: {}", x_axis.label()),
+                (x_axis, _) => format!("Time axis: {}, shows on the 1-day chart", x_axis.label()),
+            };
+            app.toast(ToastLevel::Info, message);
+            app.request_refresh();
+        }
+        Action::CycleInterval => {
+            let current = INTERVALS.iter().position(|i| *i == app.interval).unwrap_or(0);
+            let next = (1..=INTERVALS.len())
+                .map(|step| INTERVALS[(current + step) % INTERVALS.len()])
+                .find(|i| interval_allowed(&app.timeframe, i));
+            if let Some(next) = next {
+                app.set_interval(next);
+            }
+        }
+        Action::ToggleLogScale => {
+            app.log_scale = !app.log_scale;
+            let message = match (app.log_scale, app.price_view || app.chart_type != "line") {
+                (false, _) => "Linear price axis",
+                (true, true) => "Log price axis",
+                (true, false) => "Log price axis: shows in the price view",
+            };
+            app.toast(ToastLevel::Info, message.to_string());
+            app.request_refresh();
+        }
+        Action::ToggleBillboard => {
+            app.billboard = !app.billboard;
+        }
+        Action::ToggleTape => {
+            app.show_tape = !app.show_tape;
+        }
+        Action::ToggleFooter => {
+            app.show_footer = !app.show_footer;
+        }
+        Action::ToggleRangeGauge => {
+            app.show_range_gauge = !app.show_range_gauge;
+        }
+        Action::ToggleBaseline => {
+            app.vs_open = !app.vs_open;
+            app.request_refresh();
+        }
+        Action::ToggleDirectionColors => {
+            app.direction_colors = !app.direction_colors;
+            app.request_refresh();
+        }
+        Action::TogglePreviousDay => {
+            app.prev_day = !app.prev_day;
+            if app.prev_day && app.timeframe != "1d" {
+                app.toast(ToastLevel::Info, "The previous day shows on the 1d chart".to_string());
+            }
+            app.request_refresh();
+        }
+        Action::ToggleObserved => {
+            app.show_observed = !app.show_observed;
+            if app.show_observed && app.timeframe != "1d" {
+                app.toast(ToastLevel::Info, "The observed prices show on the 1d chart".to_string());
+            }
+            app.request_refresh();
+        }
+        Action::ToggleDebug => {
+            app.show_debug = !app.show_debug;
+        }
+        Action::Refresh => app.request_refresh(),
+        Action::ToggleLowPower => {
+            app.low_power = !app.low_power;
+            app.last_countdown = app.shown_countdown();
+            app.toast(ToastLevel::Info, format!("Low-power mode: {}", if app.low_power { "on" } else { "off" }));
+        }
+        Action::CopyQuote => {
+            if app.stats.symbol.is_empty() {
+                app.toast(ToastLevel::Info, "Nothing to copy yet".to_string());
+            } else {
+                let text = quote_summary(&app.stats, &app.formatter);
+                app.copy_to_clipboard(text);
+            }
+        }
+        Action::ReplayPause | Action::ReplayBack | Action::ReplayForward => {
+            let DataSource::Replay(replay) = &mut app.source else {
+                app.toast(ToastLevel::Info, "Only available with --replay".to_string());
+                return false;
+            };
+            let stepped = match action {
+                Action::ReplayBack => replay.step(false),
+                Action::ReplayForward => replay.step(true),
+                _ => {
+                    replay.toggle_pause();
+                    None
+                }
+            };
+            if let Some(stats) = stepped {
+                app.apply_stats(stats);
+            }
+        }
+        Action::ExportCsv => {
+            let template = app.export_path.clone();
+            export_chart(app, &template);
+        }
+        Action::Snapshot => app.snapshot_pending = Some((app.snapshot_path.clone(), false)),
+        Action::OpenInBrowser => {
+            let url = app.quote_url.replace("{symbol}", &app.ticker);
+            match open::that_detached(&url) {
+                Ok(()) => app.toast(ToastLevel::Info, format!("Opened {}", url)),
+                Err(e) => app.toast(ToastLevel::Error, format!("Couldn't open browser: {}", e)),
+            }
+        }
+    }
+    false
+}
+
+/// Writes the chart data in view to the path `template` names.
+fn export_chart(app: &mut App, template: &str) {
+    match &app.stats.series {
+        Some(series) if !series.is_empty() => {
+            let (path, overwrite) = export::expand_path(template, &app.stats.symbol, &app.timeframe, series);
+            app.exporter.spawn(path, series.clone(), overwrite, app.formatter.decimals);
+        }
+        _ => app.toast(ToastLevel::Info, "No chart data to export yet".to_string()),
+    }
+}
+
+/// Writes a snapshot of the screen to the path `template` names, stamped
+/// in New York time once a quote has told its offset, UTC before that.
+pub(super) fn save_snapshot(app: &mut App, template: &str, text: String) {
+    let symbol = if app.stats.symbol.is_empty() { &app.ticker } else { &app.stats.symbol };
+    let offset = app.stats.utc_offset.unwrap_or(0);
+    let (path, overwrite) = snapshot::expand_path(template, symbol, app.clock.now_utc(), offset);
+    app.exporter.spawn_text(path, text, overwrite);
+}
+
+/// Runs a `:` command line; mistakes are shown as a toast. Returns
+/// `true` when the app should quit.
+fn run_command(app: &mut App, line: &str) -> bool {
+    if line.trim().is_empty() {
+        return false;
+    }
+    let command = match command::parse(line) {
+        Ok(command) => command,
+        Err(e) => {
+            app.toast(ToastLevel::Warn, e);
+            return false;
+        }
+    };
+    match command {
+        CommandLine::Action(action) => return apply_action(app, action),
+        CommandLine::Ticker(symbol) => {
+            app.open_ticker(symbol);
+        }
+        CommandLine::Range(range) => {
+            app.set_timeframe(&range);
+            app.request_refresh();
+        }
+        CommandLine::Interval(interval) => app.set_interval(&interval),
+        CommandLine::Refresh(secs) => {
+            app.refresh_interval = Duration::from_secs(secs);
+            app.toast(ToastLevel::Info, format!("Refreshing every {}s", secs));
+        }
+        CommandLine::Alert(rule) => {
+            let mut alert = Alert::new(&app.ticker, rule, app.alert_hysteresis);
+            if app.stats.symbol == app.ticker {
+                alert = alert.starting_at(app.stats.price);
+            }
+            app.toast(ToastLevel::Info, format!("Alert added: {} {}", alert.symbol, rule));
+            app.alerts.push(alert);
+        }
+        CommandLine::Level(level) => {
+            let message = match &level.label {
+                label if label.is_empty() => format!("Level marked at {}", app.formatter.price(level.price)),
+                label => format!("Level marked at {} ({})", app.formatter.price(level.price), label),
+            };
+            let symbol = app.ticker.clone();
+            match app.levels.add(&symbol, level) {
+                Ok(()) => app.toast(ToastLevel::Info, message),
+                Err(e) => app.toast(ToastLevel::Error, format!("Couldn't save the level: {}", e)),
+            }
+            app.request_refresh();
+        }
+        CommandLine::Export(template) => export_chart(app, &template),
+        CommandLine::Snapshot { path, ansi } => {
+            app.snapshot_pending = Some((path.unwrap_or_else(|| app.snapshot_path.clone()), ansi));
+        }
+    }
+    false
+}
+
+/// Moves the grid focus with the arrow keys and opens the focused symbol on
+/// Enter. Returns whether the key was used.
+fn handle_grid_key(app: &mut App, key: KeyEvent) -> bool {
+    let Some(grid) = app.grid.as_mut() else { return false };
+    match key.code {
+        KeyCode::Left => grid.move_focus(-1, 0),
+        KeyCode::Right => grid.move_focus(1, 0),
+        KeyCode::Up => grid.move_focus(0, -1),
+        KeyCode::Down => grid.move_focus(0, 1),
+        KeyCode::Enter => {
+            let cell = grid.focused();
+            let (symbol, stats) = (cell.symbol.clone(), cell.stats.clone());
+            app.grid_view = false;
+            if (symbol != app.ticker || app.stats.symbol.is_empty())
+                && !app.open_ticker(symbol)
+                // Show what the grid already has until the full chart arrives
+                && !stats.symbol.is_empty()
+                && stats.error.is_none()
+            {
+                app.apply_stats(stats);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Applies a key press to the app state. Returns `true` when the app should quit.
+pub(super) fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    match app.input_mode {
+        InputMode::Normal => {
+            if app.grid_view && handle_grid_key(app, key) {
+                return false;
+            }
+            if let KeyCode::Char(c @ '1'..='3') = key.code
+                && let Some(r) = app.shown_suggestions().get(c as usize - '1' as usize)
+            {
+                let symbol = r.ticker.clone();
+                app.open_ticker(symbol);
+                return false;
+            }
+            if let Some(action) = keymap::lookup(&key) {
+                return apply_action(app, action);
+            }
+        }
+        InputMode::Command => match key.code {
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            KeyCode::Enter => {
+                app.input_mode = InputMode::Normal;
+                let line = std::mem::take(&mut app.command_input);
+                return run_command(app, &line);
+            }
+            KeyCode::Tab => {
+                if let Some(completed) = command::complete(&app.command_input) {
+                    app.command_input = completed;
+                }
+            }
+            // Deleting past the ':' closes the command line, as in vim
+            KeyCode::Backspace if app.command_input.is_empty() => app.input_mode = InputMode::Normal,
+            KeyCode::Backspace => {
+                app.command_input.pop();
+            }
+            KeyCode::Char(c) => app.command_input.push(c),
+            _ => {}
+        },
+        InputMode::History => {
+            let len = app.history.recent().len();
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => app.input_mode = InputMode::Normal,
+                KeyCode::Down => {
+                    let i = app.history_state.selected().map_or(0, |i| (i + 1) % len);
+                    app.history_state.select(Some(i));
+                }
+                KeyCode::Up => {
+                    let i = app.history_state.selected().map_or(0, |i| (i + len - 1) % len);
+                    app.history_state.select(Some(i));
+                }
+                KeyCode::Enter => {
+                    let chosen = app.history_state.selected().and_then(|i| app.history.recent().get(i).map(|s| s.to_string()));
+                    if let Some(symbol) = chosen {
+                        app.open_ticker(symbol);
+                    }
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            }
+        }
+        InputMode::AlertLog => {
+            let len = app.alert_log.len().max(1);
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('A') => app.input_mode = InputMode::Normal,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let i = app.alert_log_state.selected().map_or(0, |i| (i + 1).min(len - 1));
+                    app.alert_log_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let i = app.alert_log_state.selected().map_or(0, |i| i.saturating_sub(1));
+                    app.alert_log_state.select(Some(i));
+                }
+                _ => {}
+            }
+        }
+        InputMode::News => {
+            let headlines = app.news.get(&app.ticker).map(|known| known.headlines.as_slice()).unwrap_or_default();
+            let len = headlines.len().max(1);
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => app.input_mode = InputMode::Normal,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let i = app.news_state.selected().map_or(0, |i| (i + 1).min(len - 1));
+                    app.news_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let i = app.news_state.selected().map_or(0, |i| i.saturating_sub(1));
+                    app.news_state.select(Some(i));
+                }
+                KeyCode::Enter => {
+                    let link = app.news_state.selected().and_then(|i| headlines.get(i)).map(|h| h.link.clone());
+                    match link.filter(|link| !link.is_empty()) {
+                        Some(url) => match open::that_detached(&url) {
+                            Ok(()) => app.toast(ToastLevel::Info, format!("Opened {}", url)),
+                            Err(e) => app.toast(ToastLevel::Error, format!("Couldn't open browser: {}", e)),
+                        },
+                        None => app.toast(ToastLevel::Info, "No link for this headline".to_string()),
+                    }
+                }
+                _ => {}
+            }
+        }
+        InputMode::Levels => {
+            let len = app.levels.get(&app.ticker).len();
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('l') => app.input_mode = InputMode::Normal,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let i = app.levels_state.selected().map_or(0, |i| (i + 1).min(len.saturating_sub(1)));
+                    app.levels_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let i = app.levels_state.selected().map_or(0, |i| i.saturating_sub(1));
+                    app.levels_state.select(Some(i));
+                }
+                // Mark the current price
+                KeyCode::Char('a') if app.stats.symbol == app.ticker && app.stats.price > 0.0 => {
+                    let symbol = app.ticker.clone();
+                    let level = Level { price: app.stats.price, label: String::new() };
+                    match app.levels.add(&symbol, level) {
+                        Ok(()) => app.request_refresh(),
+                        Err(e) => app.toast(ToastLevel::Error, format!("Couldn't save the level: {}", e)),
+                    }
+                    let len = app.levels.get(&symbol).len();
+                    app.levels_state.select(Some(app.levels_state.selected().unwrap_or(0).min(len - 1)));
+                }
+                // Plain d only: Ctrl+d is the debug overlay
+                KeyCode::Char('d') if key.modifiers.is_empty() => remove_level(app),
+                KeyCode::Delete | KeyCode::Backspace => remove_level(app),
+                _ => {}
+            }
+        }
+        InputMode::Portfolio => {
+            let len = app.portfolio.holdings.len() + app.portfolio.watchlist.len();
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => app.input_mode = InputMode::Normal,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let i = app.portfolio_state.selected().map_or(0, |i| (i + 1).min(len.saturating_sub(1)));
+                    app.portfolio_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let i = app.portfolio_state.selected().map_or(0, |i| i.saturating_sub(1));
+                    app.portfolio_state.select(Some(i));
+                }
+                KeyCode::Char('s') => {
+                    app.portfolio_sort = app.portfolio_sort.next();
+                    app.portfolio_state.select(Some(0));
+                }
+                // Chart the selected symbol
+                KeyCode::Enter => {
+                    let rows = app.portfolio_rows();
+                    if let Some(row) = app.portfolio_state.selected().and_then(|i| rows.get(i)) {
+                        let symbol = row.symbol.clone();
+                        app.input_mode = InputMode::Normal;
+                        if symbol != app.ticker {
+                            app.open_ticker(symbol);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        InputMode::Help => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.help_scroll = app.help_scroll.saturating_add(1);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.help_scroll = app.help_scroll.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                app.help_scroll = app.help_scroll.saturating_add(10);
+            }
+            KeyCode::PageUp => {
+                app.help_scroll = app.help_scroll.saturating_sub(10);
+            }
+            _ => {}
+        },
+        InputMode::Editing => match key.code {
+            KeyCode::Esc => {
+                app.input_mode = InputMode::Normal;
+                app.search_due = None;
+            }
+            KeyCode::Enter => {
+                let selected = app
+                    .list_state
+                    .selected()
+                    .and_then(|i| app.filtered_tickers.get(i))
+                    .map(|t| t.ticker.clone());
+                // Until the search answers, a symbol typed in full beats a
+                // row that merely contains it (ARM over Aramark's ARMK),
+                // unless the user picked that row
+                let typed = (!app.selection_moved && app.search_results.is_empty())
+                    .then(|| normalize_symbol(&app.input).ok())
+                    .flatten();
+                // Fall back to the typed symbol when nothing matched (yet)
+                let choice = match (selected, typed) {
+                    (Some(ticker), Some(typed)) if typed != ticker => Some(typed),
+                    (Some(ticker), _) => Some(ticker),
+                    (None, _) if app.input.trim().is_empty() => None,
+                    (None, _) => match normalize_symbol(&app.input) {
+                        Ok(ticker) => Some(ticker),
+                        Err(e) => {
+                            app.toast(ToastLevel::Warn, e);
+                            None
+                        }
+                    },
+                };
+                if let Some(ticker) = choice {
+                    app.open_ticker(ticker);
+                    app.input_mode = InputMode::Normal;
+                    app.search_due = None;
+                }
+            }
+            KeyCode::Char(c) => {
+                app.input.insert(app.character_index, c);
+                app.character_index += 1;
+                app.on_search_input_changed();
+            }
+            KeyCode::Backspace if app.character_index > 0 => {
+                app.character_index -= 1;
+                app.input.remove(app.character_index);
+                app.on_search_input_changed();
+            }
+            KeyCode::Down => {
+                let i = match app.list_state.selected() {
+                    Some(i) => {
+                        if i >= app.filtered_tickers.len().saturating_sub(1) {
+                            0
+                        } else {
+                            i + 1
+                        }
+                    }
+                    None => 0,
+                };
+                app.list_state.select(Some(i));
+                app.selection_moved = true;
+            }
+            KeyCode::Up => {
+                let i = match app.list_state.selected() {
+                    Some(i) => {
+                        if i == 0 {
+                            app.filtered_tickers.len().saturating_sub(1)
+                        } else {
+                            i - 1
+                        }
+                    }
+                    None => 0,
+                };
+                app.list_state.select(Some(i));
+                app.selection_moved = true;
+            }
+            _ => {}
+        },
+        InputMode::SettingsMain => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.input_mode = InputMode::Normal;
+                app.request_refresh();
+            }
+            KeyCode::Down => {
+                let i = match app.settings_main_state.selected() {
+                    Some(i) => {
+                        if i >= app.settings_items.len().saturating_sub(1) { 0 } else { i + 1 }
+                    }
+                    None => 0,
+                };
+                app.settings_main_state.select(Some(i));
+            }
+            KeyCode::Up => {
+                let i = match app.settings_main_state.selected() {
+                    Some(i) => {
+                        if i == 0 { app.settings_items.len().saturating_sub(1) } else { i - 1 }
+                    }
+                    None => 0,
+                };
+                app.settings_main_state.select(Some(i));
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(i) = app.settings_main_state.selected() {
+                    match i {
+                        0 => { // Indicators
+                            app.input_mode = InputMode::SettingsIndicators;
+                            app.settings_ind_state.select(Some(0));
+                        }
+                        1 => { // Timeframe
+                            app.input_mode = InputMode::SettingsTimeframe;
+                            app.settings_tf_state.select(Some(0));
+                        }
+                        2 => { // Interval
+                            app.input_mode = InputMode::SettingsInterval;
+                            app.settings_int_state.select(Some(0));
+                        }
+                        3 => { // View Mode
+                            app.price_view = !app.price_view;
+                        }
+                        4 => { // Chart Type
+                            app.chart_type = match app.chart_type.as_str() {
+                                "line" => "candle",
+                                "candle" => "heikin-ashi",
+                                _ => "line",
+                            }
+                            .to_string();
+                            if app.chart_type != "line" {
+                                app.price_view = true; // Candles imply price view
+                            }
+                        }
+                        5 => { // Time Format
+                            app.use_24h_time = !app.use_24h_time;
+                        }
+                        6 => { // Header
+                            app.show_header = !app.show_header;
+                        }
+                        7 => { // Save & Exit
+                            app.input_mode = InputMode::Normal;
+                            app.request_refresh();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        },
+        InputMode::SettingsIndicators => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
+                app.input_mode = InputMode::SettingsMain;
+            }
+            KeyCode::Down => {
+                let len = app.available_indicators.len() + 1; // +1 for Back
+                let i = match app.settings_ind_state.selected() {
+                    Some(i) => {
+                        if i >= len.saturating_sub(1) { 0 } else { i + 1 }
+                    }
+                    None => 0,
+                };
+                app.settings_ind_state.select(Some(i));
+            }
+            KeyCode::Up => {
+                let len = app.available_indicators.len() + 1;
+                let i = match app.settings_ind_state.selected() {
+                    Some(i) => {
+                        if i == 0 { len.saturating_sub(1) } else { i - 1 }
+                    }
+                    None => 0,
+                };
+                app.settings_ind_state.select(Some(i));
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(i) = app.settings_ind_state.selected() {
+                    if i < app.available_indicators.len() {
+                        if let Some(ind_meta) = app.available_indicators.get(i) {
+                            let name = ind_meta.name.clone();
+                            if app.enabled_indicators.contains(&name) {
+                                app.enabled_indicators.remove(&name);
+                            } else {
+                                app.enabled_indicators.insert(name);
+                                if ind_meta.requires_price {
+                                    app.price_view = true;
+                                }
+                            }
+                        }
+                    } else {
+                        // Back button
+                        app.input_mode = InputMode::SettingsMain;
+                    }
+                }
+            }
+            _ => {}
+        },
+        InputMode::SettingsTimeframe => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
+                app.input_mode = InputMode::SettingsMain;
+            }
+            KeyCode::Down => {
+                let len = app.available_timeframes.len();
+                let i = match app.settings_tf_state.selected() {
+                    Some(i) => if i >= len.saturating_sub(1) { 0 } else { i + 1 },
+                    None => 0,
+                };
+                app.settings_tf_state.select(Some(i));
+            }
+            KeyCode::Up => {
+                let len = app.available_timeframes.len();
+                let i = match app.settings_tf_state.selected() {
+                    Some(i) => if i == 0 { len.saturating_sub(1) } else { i - 1 },
+                    None => 0,
+                };
+                app.settings_tf_state.select(Some(i));
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(i) = app.settings_tf_state.selected()
+                    && let Some(tf) = app.available_timeframes.get(i)
+                {
+                    app.set_timeframe(tf);
+                    app.input_mode = InputMode::SettingsMain;
+                }
+            }
+            _ => {}
+        },
+        InputMode::SettingsInterval => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
+                app.input_mode = InputMode::SettingsMain;
+            }
+            KeyCode::Down => {
+                let len = app.available_intervals.len();
+                let i = match app.settings_int_state.selected() {
+                    Some(i) => if i >= len.saturating_sub(1) { 0 } else { i + 1 },
+                    None => 0,
+                };
+                app.settings_int_state.select(Some(i));
+            }
+            KeyCode::Up => {
+                let len = app.available_intervals.len();
+                let i = match app.settings_int_state.selected() {
+                    Some(i) => if i == 0 { len.saturating_sub(1) } else { i - 1 },
+                    None => 0,
+                };
+                app.settings_int_state.select(Some(i));
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(i) = app.settings_int_state.selected()
+                    && let Some(intv) = app.available_intervals.get(i)
+                {
+                    app.set_interval(intv);
+                    app.input_mode = InputMode::SettingsMain;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Removes the level selected in the levels popup.
+fn remove_level(app: &mut App) {
+    let symbol = app.ticker.clone();
+    let Some(i) = app.levels_state.selected().filter(|i| *i < app.levels.get(&symbol).len()) else { return };
+    if let Err(e) = app.levels.remove(&symbol, i) {
+        app.toast(ToastLevel::Error, format!("Couldn't save the levels: {}", e));
+    }
+    app.request_refresh();
+    let len = app.levels.get(&symbol).len();
+    app.levels_state.select(if len == 0 { None } else { Some(i.min(len - 1)) });
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+    use crate::app::tests::{test_app, ticker};
+
+    fn press(app: &mut App, code: KeyCode) -> bool {
+        handle_key(app, KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn q_and_esc_quit_from_the_main_screen() {
+        let mut app = test_app();
+        assert!(press(&mut app, KeyCode::Char('q')));
+        assert!(press(&mut app, KeyCode::Esc));
+        assert!(!press(&mut app, KeyCode::Char('?')));
+        assert_eq!(app.input_mode, InputMode::Help);
+    }
+
+    #[test]
+    fn command_line_adds_an_alert() {
+        let mut app = test_app();
+        press(&mut app, KeyCode::Char(':'));
+        assert_eq!(app.input_mode, InputMode::Command);
+        type_text(&mut app, "alert price > 200");
+        assert!(!press(&mut app, KeyCode::Enter));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.alerts.len(), 1);
+        assert_eq!(app.alerts[0].symbol, "AAPL");
+    }
+
+    #[test]
+    fn backspace_past_the_colon_closes_the_command_line() {
+        let mut app = test_app();
+        press(&mut app, KeyCode::Char(':'));
+        type_text(&mut app, "q");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.input_mode, InputMode::Command);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn typed_symbol_opens_normalized() {
+        let mut app = test_app();
+        assert!(!handle_key(&mut app, KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL)));
+        assert_eq!(app.input_mode, InputMode::Editing);
+        type_text(&mut app, "brk.b");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.ticker, "BRK-B");
+    }
+
+    /// The picker open on `text`, with some of top-tickers.csv's rows that
+    /// contain "arm" but none that is ARM.
+    fn picker_with(text: &str) -> App {
+        let mut app = test_app();
+        app.tickers_db = [("ARMK", "Aramark"), ("ARMN", "Aris Mining Corporation"), ("HARM", "Harmony Gold Mining")]
+            .iter()
+            .map(|(symbol, name)| ticker(symbol, name))
+            .collect();
+        apply_action(&mut app, Action::OpenTicker);
+        type_text(&mut app, text);
+        app
+    }
+
+    #[test]
+    fn typed_symbol_beats_a_row_containing_it_before_the_search_answers() {
+        let mut app = picker_with("arm");
+        assert_eq!(app.filtered_tickers[0].ticker, "ARMK");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.ticker, "ARM");
+    }
+
+    #[test]
+    fn picked_row_opens_over_the_typed_symbol() {
+        let mut app = picker_with("arm");
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.ticker, "ARMK");
+    }
+
+    #[test]
+    fn exact_symbol_from_the_search_ranks_first() {
+        let mut app = picker_with("arm");
+        app.search_results = vec![ticker("ARMK", "Aramark"), ticker("ARM", "Arm Holdings plc")];
+        app.update_filtered_tickers();
+        assert_eq!(app.filtered_tickers[0].ticker, "ARM");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.ticker, "ARM");
+    }
+}
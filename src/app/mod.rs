@@ -0,0 +1,2219 @@
+//! The app's state, and the loop that fetches, draws and handles keys.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    error::Error,
+    fs::{self, File},
+    io::{self, Write},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use crossterm::{
+    event::{self, Event, KeyEventKind},
+    execute,
+    terminal::SetTitle,
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    widgets::{ListState, TableState},
+    Terminal,
+};
+use ratatui_image::{
+    picker::Picker,
+    protocol::StatefulProtocol,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    alert::{self, Alert, Trigger},
+    api::yahoo::{Calendar, FetchError, Headline, MiniQuote, TickerRecord},
+    cli::{Cli, PastDay, Provider, INTERVALS, TIMEFRAMES},
+    clock::{Clock, JumpDetector},
+    config::{Config, FlashConfig, LineStyle, XAxis, YScale},
+    export::{Exporter, DEFAULT_EXPORT_PATH},
+    fetch::{
+        decode_image, fetch_calendar, fetch_mini_quotes, fetch_news, interval_for_day, intraday_bar, search_symbols,
+        snap_interval, suggested_interval, DataSource, FetchRequest, FetchTimings, DEFAULT_SLIDING_MINUTES,
+    },
+    format::{format_age, format_clock, format_pct, Formatter},
+    grid::Grid,
+    history::History,
+    levels::Levels,
+    model::StockStats,
+    notify::Notifier,
+    observed::ObservedPrices,
+    portfolio::{Portfolio, PortfolioRow, SortColumn},
+    session::SessionStats,
+    snapshot::{self, DEFAULT_SNAPSHOT_PATH},
+    sparkline::RecentPrices,
+    theme::{change_arrow, Theme},
+};
+use keys::{handle_key, save_snapshot};
+use ui::ui;
+
+mod keys;
+mod ui;
+
+/// A symbol's calendar and when to look it up again.
+#[derive(Debug, Clone, Copy)]
+struct KnownCalendar {
+    calendar: Calendar,
+    recheck_at: Instant,
+}
+
+/// A conversion rate into the home currency, None after a failed lookup,
+/// and when to look it up again.
+#[derive(Debug, Clone, Copy)]
+struct KnownRate {
+    rate: Option<f64>,
+    recheck_at: Instant,
+}
+
+/// The latest second-source price for a symbol, or why there wasn't one,
+/// against the chart's price when it arrived.
+#[derive(Debug, Clone)]
+struct CrossCheck {
+    symbol: String,
+    ours: f64,
+    theirs: Result<f64, String>,
+    at: Instant,
+}
+
+impl CrossCheck {
+    /// Percent the second source is off from the chart's price.
+    fn gap_pct(&self) -> Option<f64> {
+        self.theirs.as_ref().ok().map(|theirs| (theirs - self.ours) / self.ours * 100.0)
+    }
+}
+
+/// A symbol's latest headlines, or why they couldn't be fetched, and when to
+/// look again.
+#[derive(Debug, Clone)]
+struct KnownNews {
+    headlines: Vec<Headline>,
+    error: Option<String>,
+    recheck_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ToastLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// A transient message in the bottom-right corner: a confirmation, or a
+/// failure that doesn't stop the app.
+#[derive(Debug, Clone)]
+struct Toast {
+    text: String,
+    level: ToastLevel,
+    created_at: Instant,
+}
+
+/// How long the header stays inverted after a significant move.
+const FLASH_DURATION: Duration = Duration::from_secs(1);
+
+/// How long the change since the previous fetch stays colored: bold for the
+/// first half, plain for the second, then gray until the next fetch.
+const TICK_FADE: Duration = Duration::from_secs(6);
+
+/// During regular hours, the newest bar may lag by this much (plus one bar)
+/// before the data is flagged as stale.
+const STALE_AFTER: Duration = Duration::from_secs(180);
+
+/// Within this percent of the day's high the header's distance-from-high is
+/// green; at least `FAR_FROM_HIGH_PCT` below it, red.
+const NEAR_HIGH_PCT: f64 = 0.5;
+const FAR_FROM_HIGH_PCT: f64 = 2.0;
+
+/// An earlier hint: past one bar plus this, the header's last-bar time turns
+/// yellow.
+const LAG_SLACK: Duration = Duration::from_secs(60);
+
+/// A later one: with no new bar for this long (plus one bar) while quotes
+/// keep arriving, the symbol is flagged as possibly halted.
+const HALT_AFTER: Duration = Duration::from_secs(300);
+
+/// How often the market index strip is refreshed.
+const TAPE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often a symbol's earnings and dividend dates are looked up again, and
+/// how soon after a failed lookup.
+const CALENDAR_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const CALENDAR_RETRY: Duration = Duration::from_secs(10 * 60);
+
+/// How long a home-currency rate is used before it's fetched again, and how
+/// soon after a failed fetch.
+const FX_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const FX_RETRY: Duration = Duration::from_secs(2 * 60);
+
+/// How often the news panel refreshes its headlines while open, and how soon
+/// after a failed fetch.
+const NEWS_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const NEWS_RETRY: Duration = Duration::from_secs(60);
+
+/// How often the price is checked against the provider's batch quotes,
+/// when [cross_check] is enabled.
+const CROSS_CHECK_INTERVAL: Duration = Duration::from_secs(3 * 60);
+
+/// The line chart's smoothing settings in cycling order, in bars; 0 is off.
+const SMOOTHING_WINDOWS: [usize; 4] = [0, 3, 5, 9];
+
+/// Within this many days the earnings countdown turns yellow.
+const EARNINGS_SOON_DAYS: i64 = 3;
+
+/// Within this many days the ex-dividend date is highlighted.
+const EX_DIVIDEND_SOON_DAYS: i64 = 7;
+
+/// Percent by which the live price may differ from the last bar's close
+/// before the debug overlay highlights it (the fetcher marks it on the chart
+/// past the same gap).
+const LIVE_PRICE_GAP_PCT: f64 = 0.05;
+
+/// Past this many symbols the portfolio table refreshes less often.
+const LARGE_WATCHLIST: usize = 100;
+
+/// How often the loop looks at the timers while waiting for a key, and the
+/// longest it waits in low-power mode.
+const UI_TICK: Duration = Duration::from_millis(200);
+const LOW_POWER_TICK: Duration = Duration::from_secs(10);
+
+/// How long a toast stays up, how many show at once, and how many are kept
+/// waiting behind them.
+const TOAST_TTL: Duration = Duration::from_secs(4);
+const SHOWN_TOASTS: usize = 2;
+const MAX_TOASTS: usize = 8;
+
+/// How many times a failing fetch is tried before the chart gives up and
+/// shows why, and how long it waits between tries.
+const FETCH_ATTEMPTS: u32 = 3;
+const FETCH_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Frames of the spinner shown while the chart waits for a fetch, one per UI tick.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+const DEFAULT_QUOTE_URL: &str = "https://finance.yahoo.com/quote/{symbol}";
+
+/// Delay between the last keystroke and the remote symbol search.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How long the chart area must keep its size before the chart is redrawn
+/// for it.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(1500);
+
+/// A loop iteration this much later than due, by either clock, means the
+/// machine slept or the clock was changed. The loop never waits more than
+/// `LOW_POWER_TICK` on its own.
+const CLOCK_JUMP: Duration = Duration::from_secs(60);
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum InputMode {
+    Normal,
+    Editing,
+    SettingsMain,
+    SettingsIndicators,
+    SettingsTimeframe,
+    SettingsInterval,
+    Help,
+    Command,
+    History,
+    AlertLog,
+    News,
+    Portfolio,
+    Levels,
+}
+
+/// The last fetch of a symbol, kept so going back to it is instant.
+struct CachedQuote {
+    stats: StockStats,
+    // Chart settings it was made with, at size 0x0
+    settings: FetchRequest,
+    area_size: (u16, u16),
+    fetched_at: Instant,
+}
+
+pub struct App {
+    ticker: String,
+    stats: StockStats,
+    // Name and type of `stats.symbol`, resolved once per fetch rather than per frame
+    symbol_info: (String, String),
+    input_mode: InputMode,
+    input: String,
+    character_index: usize,
+    tickers_db: Vec<TickerRecord>,
+    filtered_tickers: Vec<TickerRecord>,
+    list_state: ListState,
+    // Whether Up/Down moved the highlight since the input last changed
+    selection_moved: bool,
+    // Remote symbol search
+    search_results: Vec<TickerRecord>,
+    search_due: Option<Instant>,
+    search_tx: Sender<(String, Result<Vec<TickerRecord>, String>)>,
+    search_rx: Receiver<(String, Result<Vec<TickerRecord>, String>)>,
+    // Matches offered for a symbol Yahoo doesn't know, and that symbol
+    suggestions: Vec<TickerRecord>,
+    suggested_for: String,
+    // Market index strip, fetched in one batch every TAPE_INTERVAL
+    show_tape: bool,
+    // Session statistics under the chart, recomputed on each fetch
+    show_footer: bool,
+    // Where the price sits in the day's range, under the header
+    show_range_gauge: bool,
+    // Data source, cadence and a few keys, on the bottom row
+    show_hint_line: bool,
+    annualize_volatility: bool,
+    session_stats: Option<SessionStats>,
+    tape_symbols: Vec<String>,
+    tape: Vec<MiniQuote>,
+    tape_fetched: Option<Instant>,
+    tape_busy: bool,
+    tape_tx: Sender<Result<Vec<MiniQuote>, String>>,
+    tape_rx: Receiver<Result<Vec<MiniQuote>, String>>,
+    // Earnings and dividend dates per symbol, looked up alongside the quote fetch
+    calendars: HashMap<String, KnownCalendar>,
+    calendar_pending: Option<String>,
+    calendar_tx: Sender<(String, Result<Calendar, String>)>,
+    calendar_rx: Receiver<(String, Result<Calendar, String>)>,
+    // Rates into --home-currency per quote currency, fetched on their own thread
+    home_currency: Option<String>,
+    fx_rates: HashMap<String, KnownRate>,
+    fx_pending: Option<String>,
+    fx_tx: Sender<(String, Result<f64, String>)>,
+    fx_rx: Receiver<(String, Result<f64, String>)>,
+    // The price checked against the provider's batch quotes every
+    // CROSS_CHECK_INTERVAL on its own thread; None tolerance when disabled
+    cross_check_tolerance: Option<f64>,
+    cross_check: Option<CrossCheck>,
+    cross_checked: Option<Instant>,
+    cross_check_busy: bool,
+    cross_check_tx: Sender<(String, Result<f64, String>)>,
+    cross_check_rx: Receiver<(String, Result<f64, String>)>,
+    // Price levels marked per symbol, saved in the state directory
+    levels: Levels,
+    levels_state: ListState,
+    // Holdings from portfolio.toml and their quotes, fetched on their own
+    // thread while the portfolio view is open
+    portfolio: Portfolio,
+    portfolio_quotes: HashMap<String, MiniQuote>,
+    portfolio_fetched: Option<Instant>,
+    portfolio_busy: bool,
+    portfolio_error: Option<String>,
+    portfolio_tx: Sender<Result<Vec<MiniQuote>, String>>,
+    portfolio_rx: Receiver<Result<Vec<MiniQuote>, String>>,
+    portfolio_sort: SortColumn,
+    portfolio_state: TableState,
+    // Headlines per symbol, fetched on their own thread while the news panel is open
+    news: HashMap<String, KnownNews>,
+    news_pending: Option<String>,
+    news_tx: Sender<(String, Result<Vec<Headline>, String>)>,
+    news_rx: Receiver<(String, Result<Vec<Headline>, String>)>,
+    news_state: ListState,
+    image_protocol: Option<StatefulProtocol>,
+    picker: Picker,
+    last_fetched_size: (u16, u16),
+    current_image_area_size: (u16, u16),
+    last_size_change_time: Instant,
+    last_fetch_time: Instant,
+    // A fetch is wanted whatever the timer says: settings changed, or 'r'
+    refresh_requested: bool,
+    // The symbol and start of a fetch the chart is blocked on, for the spinner
+    waiting: Option<(String, Instant)>,
+    // Fetches failed in a row, and why the last one did once they're used up
+    failed_attempts: u32,
+    fetch_failure: Option<FetchError>,
+    refresh_interval: Duration,
+    source: DataSource,
+    // Settings
+    available_indicators: Vec<IndicatorMeta>,
+    enabled_indicators: HashSet<String>,
+    // Settings State
+    settings_main_state: ListState,
+    settings_ind_state: ListState,
+    settings_tf_state: ListState,
+    settings_int_state: ListState,
+    settings_items: Vec<&'static str>,
+    available_timeframes: Vec<&'static str>,
+    available_intervals: Vec<&'static str>,
+    timeframe: String,
+    interval: String,
+    // A past day from --date: its 1d chart, never refreshed on a timer
+    date: Option<PastDay>,
+    // Picked by the user, so it's kept (snapped if need be) when the range
+    // changes rather than replaced with the range's suggested interval
+    interval_chosen: bool,
+    pre_market: bool,
+    // Percentages are measured from today's open instead of the previous close
+    vs_open: bool,
+    // Rising and falling stretches of the line in the up/down colors
+    direction_colors: bool,
+    // The previous trading day's path drawn behind the 1-day chart
+    prev_day: bool,
+    // Each fetch's price, drawn over the 1-day chart's bars when shown
+    observed: ObservedPrices,
+    show_observed: bool,
+    provider: Provider,
+    quote_url: String,
+    export_path: String,
+    snapshot_path: String,
+    // Path template and whether to keep colors, for a snapshot of the next frame
+    snapshot_pending: Option<(String, bool)>,
+    exporter: Exporter,
+    formatter: Formatter,
+    // Prices of the last fetches, for the header's sparkline
+    recent_prices: RecentPrices,
+    gridlines: bool,
+    line_style: LineStyle,
+    // Bars in the line's centered moving average; 0 draws it raw
+    smoothing: usize,
+    y_scale: YScale,
+    x_axis: XAxis,
+    sliding_minutes: u32,
+    log_scale: bool,
+    rvol_time_adjusted: bool,
+    // Configuration
+    show_header: bool,
+    // Full-screen chart: header hidden, key facts folded into the chart title
+    fullscreen: bool,
+    // The price in large block digits in place of the chart
+    billboard: bool,
+    show_debug: bool,
+    // Grid dashboard (--grid); when grid_view is false the focused symbol is
+    // shown on its own
+    grid: Option<Grid>,
+    grid_view: bool,
+    fetch_timings: FetchTimings,
+    // Minutes of staleness last drawn, so the header repaints as it grows
+    last_stale_minutes: Option<u64>,
+    // Notices sleep and clock changes between ticks
+    clock_jumps: JumpDetector,
+    // After a sleep or clock jump, when the shown data was fetched; it's
+    // stale whatever the market until a fetch succeeds
+    stale_since: Option<SystemTime>,
+    // Whether the halt banner was last drawn
+    last_halt_shown: bool,
+    // Wake about every LOW_POWER_TICK rather than every UI_TICK, with the
+    // countdown and clock shown coarsely to match
+    low_power: bool,
+    // Second (minute in low-power mode) shown by the header clock
+    last_clock: u64,
+    last_countdown: u64,
+    // Price before the latest fetch and when the latest fetch changed it;
+    // None on a symbol's first fetch or when the price didn't change
+    last_tick: Option<(f64, Instant)>,
+    // Fade step of the tick last drawn
+    last_tick_stage: u32,
+    help_scroll: u16,
+    use_24h_time: bool,
+    // Set whenever something visible changed; the loop only redraws when dirty
+    dirty: bool,
+    price_view: bool, // true = Price, false = % Change
+    chart_type: String,
+    theme: Theme,
+    ascii: bool,
+    notifier: Option<Notifier>,
+    // From --alert (for the first ticker) and :alert
+    alerts: Vec<Alert>,
+    alert_hysteresis: f64,
+    // Today's triggers from the alert log, read when the popup opens
+    alert_log: Vec<Trigger>,
+    alert_log_state: ListState,
+    // Text after the ':' while the command line is open
+    command_input: String,
+    history: History,
+    history_state: ListState,
+    // Latest quote of each symbol in the history
+    quote_cache: HashMap<String, CachedQuote>,
+    // Past days already fetched, by "SYMBOL YYYY-MM-DD"; their bars are final
+    day_cache: HashMap<String, CachedQuote>,
+    // Direction of the last step through past days, to keep going past holidays
+    day_step: i64,
+    flash: FlashConfig,
+    flash_started: Option<Instant>,
+    bell_pending: bool,
+    // Mirror the quote into the terminal window title
+    set_title: bool,
+    title_pending: Option<String>,
+    // Kept alive because on X11 the copied text disappears with its owner
+    clipboard: Option<arboard::Clipboard>,
+    // Text to copy through the terminal (OSC 52) when there is no clipboard
+    osc52_pending: Option<String>,
+    // Transient messages, oldest first; the newest few are shown
+    toasts: VecDeque<Toast>,
+    // Where every timer reads the time
+    clock: Rc<dyn Clock>,
+}
+
+impl App {
+    pub fn new(
+        cli: &Cli,
+        config: Config,
+        theme: Theme,
+        tickers_db: Vec<TickerRecord>,
+        picker: Picker,
+        source: DataSource,
+        clock: Rc<dyn Clock>,
+    ) -> App {
+        let (search_tx, search_rx) = mpsc::channel();
+        let (tape_tx, tape_rx) = mpsc::channel();
+        let (calendar_tx, calendar_rx) = mpsc::channel();
+        let (news_tx, news_rx) = mpsc::channel();
+        let (fx_tx, fx_rx) = mpsc::channel();
+        let (portfolio_tx, portfolio_rx) = mpsc::channel();
+        let (cross_check_tx, cross_check_rx) = mpsc::channel();
+        let refresh_interval = Duration::from_secs(cli.refresh);
+        let formatter = config.formatter();
+        let mut flash = config.flash;
+        flash.bell &= !cli.no_bell;
+        App {
+            ticker: cli.tickers[0].clone(),
+            stats: StockStats::default(),
+            symbol_info: ("Unknown".to_string(), "Unknown".to_string()),
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            character_index: 0,
+            tickers_db,
+            filtered_tickers: Vec::new(),
+            list_state: ListState::default(),
+            selection_moved: false,
+            search_results: Vec::new(),
+            search_due: None,
+            search_tx,
+            search_rx,
+            suggestions: Vec::new(),
+            suggested_for: String::new(),
+            show_tape: config.tape.enabled && !config.tape.symbols.is_empty(),
+            show_footer: config.footer.enabled,
+            show_hint_line: config.hint_line.unwrap_or(true),
+            show_range_gauge: true,
+            annualize_volatility: config.footer.annualize,
+            session_stats: None,
+            tape_symbols: config.tape.symbols,
+            tape: Vec::new(),
+            tape_fetched: None,
+            tape_busy: false,
+            tape_tx,
+            tape_rx,
+            calendars: HashMap::new(),
+            calendar_pending: None,
+            calendar_tx,
+            calendar_rx,
+            home_currency: cli.home_currency.clone().or_else(|| config.home_currency.clone()),
+            fx_rates: HashMap::new(),
+            fx_pending: None,
+            fx_tx,
+            fx_rx,
+            cross_check_tolerance: config.cross_check.enabled.then_some(config.cross_check.tolerance),
+            cross_check: None,
+            cross_checked: None,
+            cross_check_busy: false,
+            cross_check_tx,
+            cross_check_rx,
+            levels: Levels::default(),
+            levels_state: ListState::default(),
+            portfolio: Portfolio::default(),
+            portfolio_quotes: HashMap::new(),
+            portfolio_fetched: None,
+            portfolio_busy: false,
+            portfolio_error: None,
+            portfolio_tx,
+            portfolio_rx,
+            portfolio_sort: SortColumn::default(),
+            portfolio_state: TableState::default(),
+            news: HashMap::new(),
+            news_pending: None,
+            news_tx,
+            news_rx,
+            news_state: ListState::default(),
+            image_protocol: None,
+            picker,
+            last_fetched_size: (0, 0),
+            current_image_area_size: (0, 0),
+            last_size_change_time: clock.now_instant(),
+            last_fetch_time: clock.now_instant().checked_sub(refresh_interval * 2).unwrap_or(clock.now_instant()), // force initial fetch
+            refresh_requested: true,
+            waiting: None,
+            failed_attempts: 0,
+            fetch_failure: None,
+            refresh_interval,
+            source,
+            available_indicators: get_available_indicators(),
+            enabled_indicators: HashSet::new(),
+            settings_main_state: ListState::default(),
+            settings_ind_state: ListState::default(),
+            settings_tf_state: ListState::default(),
+            settings_int_state: ListState::default(),
+            settings_items: vec![
+                "Indicators >",
+                "Timeframe >",
+                "Interval >",
+                "View: % Change",
+                "Type: Line",
+                "Time: 12h",     
+                "Header: Show",  
+                "Save & Exit",
+            ],
+            available_timeframes: TIMEFRAMES.to_vec(),
+            available_intervals: INTERVALS.to_vec(),
+            timeframe: cli.range.clone(),
+            interval: match &cli.date {
+                Some(day) => day.interval().to_string(),
+                None => suggested_interval(&cli.range).to_string(),
+            },
+            date: cli.date.clone(),
+            interval_chosen: false,
+            pre_market: cli.pre_market,
+            vs_open: false,
+            direction_colors: false,
+            prev_day: false,
+            observed: ObservedPrices::default(),
+            show_observed: false,
+            provider: cli.provider,
+            quote_url: config.quote_url.unwrap_or_else(|| DEFAULT_QUOTE_URL.to_string()),
+            export_path: config.export_path.unwrap_or_else(|| DEFAULT_EXPORT_PATH.to_string()),
+            snapshot_path: config.snapshot_path.unwrap_or_else(|| DEFAULT_SNAPSHOT_PATH.to_string()),
+            snapshot_pending: None,
+            exporter: Exporter::new(),
+            formatter,
+            recent_prices: RecentPrices::default(),
+            gridlines: config.gridlines.unwrap_or(true),
+            line_style: config.line_style.unwrap_or_default(),
+            smoothing: 0,
+            y_scale: config.y_scale.unwrap_or_default(),
+            x_axis: config.x_axis.unwrap_or_default(),
+            sliding_minutes: config.sliding_minutes.unwrap_or(DEFAULT_SLIDING_MINUTES),
+            log_scale: false,
+            rvol_time_adjusted: config.rvol_time_adjusted.unwrap_or(false),
+            show_header: true,
+            fullscreen: false,
+            billboard: cli.billboard,
+            show_debug: false,
+            grid: cli.grid.then(|| Grid::new(&cli.tickers, &theme)),
+            grid_view: cli.grid,
+            fetch_timings: FetchTimings::default(),
+            last_stale_minutes: None,
+            clock_jumps: JumpDetector::new(clock.as_ref(), CLOCK_JUMP),
+            stale_since: None,
+            last_halt_shown: false,
+            low_power: cli.low_power,
+            last_clock: 0,
+            last_countdown: 0,
+            last_tick: None,
+            last_tick_stage: 0,
+            help_scroll: 0,
+            dirty: true,
+            use_24h_time: false,
+            price_view: false,
+            chart_type: "line".to_string(),
+            theme,
+            ascii: cli.ascii || config.ascii.unwrap_or_else(locale_lacks_utf8),
+            notifier: (config.notifications.enabled && !cli.no_notify)
+                .then(|| Notifier::new(config.notifications.move_threshold)),
+            alerts: cli.alerts.iter().map(|rule| Alert::new(&cli.tickers[0], *rule, config.alerts.hysteresis)).collect(),
+            alert_hysteresis: config.alerts.hysteresis,
+            alert_log: Vec::new(),
+            alert_log_state: ListState::default(),
+            command_input: String::new(),
+            history: History::new(&cli.tickers[0]),
+            history_state: ListState::default(),
+            quote_cache: HashMap::new(),
+            day_cache: HashMap::new(),
+            day_step: 0,
+            flash,
+            flash_started: None,
+            bell_pending: false,
+            set_title: !cli.no_title,
+            title_pending: None,
+            clipboard: None,
+            osc52_pending: None,
+            toasts: VecDeque::new(),
+            clock,
+        }
+    }
+
+    /// Takes over the saved portfolio and levels, opening the portfolio
+    /// table when it's a `--watchlist` or `--stdin` list.
+    pub fn set_portfolio(&mut self, portfolio: Portfolio, levels: Levels, watchlist: bool) {
+        self.portfolio = portfolio;
+        self.levels = levels;
+        if watchlist {
+            self.portfolio_state.select(Some(0));
+            self.input_mode = InputMode::Portfolio;
+            let count = self.portfolio.symbols().len();
+            if count > LARGE_WATCHLIST {
+                let every = format_age(self.portfolio_refresh());
+                warn!(count, %every, "long watchlist, table refresh throttled");
+                self.toast(ToastLevel::Info, format!("{} symbols: the table refreshes every {} to stay within rate limits", count, every));
+            }
+        }
+    }
+
+    /// Stops any fetch still running, before the app exits.
+    pub fn cancel_fetches(&mut self) {
+        if let Some(fetcher) = self.source.fetcher() {
+            fetcher.cancel();
+        }
+        if let Some(grid) = self.grid.as_mut() {
+            grid.cancel();
+        }
+    }
+
+    /// Whether the window title was set, and should be cleared on exit.
+    pub fn sets_title(&self) -> bool {
+        self.set_title
+    }
+
+    fn update_filtered_tickers(&mut self) {
+        if self.input.is_empty() {
+            self.filtered_tickers = self.tickers_db.clone();
+        } else {
+            let query = self.input.to_lowercase();
+            let local: Vec<TickerRecord> = self.tickers_db
+                .iter()
+                .filter(|t| {
+                    t.ticker.to_lowercase().contains(&query) || 
+                    t.name.to_lowercase().contains(&query)
+                })
+                .filter(|t| !self.search_results.iter().any(|r| r.ticker == t.ticker))
+                .cloned()
+                .collect();
+            let mut rows: Vec<TickerRecord> = self.search_results.iter().cloned().chain(local).collect();
+            // An exact symbol match should always be the first suggestion,
+            // whether the search or the local list found it
+            rows.sort_by_key(|t| t.ticker.to_lowercase() != query);
+            self.filtered_tickers = rows;
+        }
+        self.list_state.select(Some(0));
+    }
+
+    /// Called whenever the search input changes: refilters locally and
+    /// schedules a debounced remote search.
+    fn on_search_input_changed(&mut self) {
+        self.search_results.clear();
+        self.selection_moved = false;
+        self.search_due = if self.input.trim().is_empty() {
+            None
+        } else {
+            Some(self.clock.now_instant() + SEARCH_DEBOUNCE)
+        };
+        self.update_filtered_tickers();
+    }
+
+    /// Starts the remote search once the debounce has elapsed and merges in
+    /// any results that have arrived for the current query.
+    fn poll_search(&mut self) {
+        if let Some(due) = self.search_due
+            && self.clock.now_instant() >= due
+        {
+            self.search_due = None;
+            let query = self.input.trim().to_string();
+            let tx = self.search_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let results = search_symbols(provider, &query).map_err(|e| {
+                    warn!(query = %query, error = %e, "symbol search failed");
+                    e.to_string()
+                });
+                let _ = tx.send((query, results));
+            });
+        }
+
+        while let Ok((query, results)) = self.search_rx.try_recv() {
+            let results = results.unwrap_or_else(|e| {
+                self.toast(ToastLevel::Warn, format!("Symbol search failed: {}", e));
+                Vec::new()
+            });
+            if query == self.suggested_for {
+                self.suggestions = results.into_iter().filter(|r| r.ticker != query).take(3).collect();
+                self.dirty = true;
+                continue;
+            }
+            // Drop responses for queries the user has already typed past
+            if self.input_mode == InputMode::Editing && query == self.input.trim() {
+                let selected = self.list_state.selected();
+                self.search_results = results;
+                self.update_filtered_tickers();
+                self.list_state.select(selected.or(Some(0)));
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Looks for symbols close to `self.ticker` after Yahoo said it has no
+    /// such symbol, once per symbol.
+    fn suggest_symbols(&mut self) {
+        if self.suggested_for == self.ticker {
+            return;
+        }
+        self.suggested_for = self.ticker.clone();
+        self.suggestions.clear();
+        let query = self.ticker.clone();
+        let tx = self.search_tx.clone();
+        let provider = self.provider;
+        thread::spawn(move || {
+            let results = search_symbols(provider, &query).map_err(|e| {
+                warn!(query = %query, error = %e, "symbol search failed");
+                e.to_string()
+            });
+            let _ = tx.send((query, results));
+        });
+    }
+
+    /// The matches to offer in place of the current symbol, while its fetch
+    /// is failing because Yahoo doesn't know it.
+    fn shown_suggestions(&self) -> &[TickerRecord] {
+        if self.stats.not_found && self.suggested_for == self.ticker {
+            &self.suggestions
+        } else {
+            &[]
+        }
+    }
+
+    /// Refreshes the index strip while it's shown and picks up finished
+    /// fetches. Recorded sessions have no live market to show.
+    fn poll_tape(&mut self) {
+        while let Ok(result) = self.tape_rx.try_recv() {
+            self.tape_busy = false;
+            match result {
+                Ok(quotes) => {
+                    self.tape = quotes;
+                    self.dirty = true;
+                }
+                Err(e) => {
+                    warn!(error = %e, "index strip fetch failed");
+                    self.toast(ToastLevel::Warn, format!("Index strip fetch failed: {}", e));
+                }
+            }
+        }
+
+        let due = self.tape_fetched.is_none_or(|at| self.clock.elapsed(at) >= TAPE_INTERVAL);
+        let live = matches!(self.source, DataSource::Live { .. });
+        if self.show_tape && live && due && !self.tape_busy && !self.tape_symbols.is_empty() {
+            self.tape_busy = true;
+            self.tape_fetched = Some(self.clock.now_instant());
+            let symbols = self.tape_symbols.clone();
+            let tx = self.tape_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let _ = tx.send(fetch_mini_quotes(provider, &symbols, false).map_err(|e| e.to_string()));
+            });
+        }
+    }
+
+    /// Looks up the current symbol's earnings and dividend dates on their
+    /// own thread, once a day, so the quote fetch never waits on them.
+    fn poll_calendar(&mut self) {
+        while let Ok((symbol, result)) = self.calendar_rx.try_recv() {
+            self.calendar_pending = None;
+            let known = match result {
+                Ok(calendar) => KnownCalendar { calendar, recheck_at: self.clock.now_instant() + CALENDAR_INTERVAL },
+                Err(e) => {
+                    warn!(symbol = %symbol, error = %e, "calendar lookup failed");
+                    self.toast(ToastLevel::Warn, format!("Calendar lookup for {} failed: {}", symbol, e));
+                    let calendar = self.calendars.get(&symbol).map(|known| known.calendar).unwrap_or_default();
+                    KnownCalendar { calendar, recheck_at: self.clock.now_instant() + CALENDAR_RETRY }
+                }
+            };
+            self.calendars.insert(symbol, known);
+            self.dirty = true;
+        }
+
+        let due = self.calendars.get(&self.ticker).is_none_or(|known| self.clock.now_instant() >= known.recheck_at);
+        let live = matches!(self.source, DataSource::Live { .. });
+        if live && due && self.calendar_pending.is_none() {
+            self.calendar_pending = Some(self.ticker.clone());
+            let symbol = self.ticker.clone();
+            let tx = self.calendar_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let result = fetch_calendar(provider, &symbol).map_err(|e| e.to_string());
+                let _ = tx.send((symbol, result));
+            });
+        }
+    }
+
+    /// Looks up rates into the home currency on their own thread, one at a
+    /// time, reusing each for 15 minutes: for the shown quote's currency, and
+    /// for every portfolio currency while the portfolio is open. A failed
+    /// lookup only hides converted amounts until the retry.
+    fn poll_fx(&mut self) {
+        while let Ok((currency, result)) = self.fx_rx.try_recv() {
+            self.fx_pending = None;
+            let known = match result {
+                Ok(rate) => KnownRate { rate: Some(rate), recheck_at: self.clock.now_instant() + FX_INTERVAL },
+                Err(e) => {
+                    warn!(currency = %currency, error = %e, "exchange rate lookup failed");
+                    self.toast(ToastLevel::Warn, format!("No {} exchange rate: {}", currency, e));
+                    KnownRate { rate: None, recheck_at: self.clock.now_instant() + FX_RETRY }
+                }
+            };
+            self.fx_rates.insert(currency, known);
+            self.dirty = true;
+        }
+
+        let Some(home) = self.home_currency.clone() else { return };
+        let live = matches!(self.source, DataSource::Live { .. });
+        if !live || self.fx_pending.is_some() {
+            return;
+        }
+        let mut wanted = Vec::new();
+        if !self.stats.symbol.is_empty() {
+            wanted.push(self.stats.currency.as_str());
+        }
+        if self.input_mode == InputMode::Portfolio {
+            wanted.extend(self.portfolio_quotes.values().map(|quote| quote.currency.as_str()));
+        }
+        let due = wanted.into_iter().map(|currency| fx_currency(currency).0).find(|currency| {
+            *currency != home && self.fx_rates.get(currency).is_none_or(|known| self.clock.now_instant() >= known.recheck_at)
+        });
+        if let Some(currency) = due {
+            self.fx_pending = Some(currency.clone());
+            let tx = self.fx_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let pair = format!("{}{}=X", currency, home);
+                let result = fetch_mini_quotes(provider, std::slice::from_ref(&pair), false)
+                    .map_err(|e| e.to_string())
+                    .and_then(|quotes| {
+                        quotes
+                            .into_iter()
+                            .find(|quote| quote.symbol == pair && quote.price > 0.0)
+                            .map(|quote| quote.price)
+                            .ok_or_else(|| format!("no quote for {}", pair))
+                    });
+                let _ = tx.send((currency, result));
+            });
+        }
+    }
+
+    /// Checks the shown symbol's price against the provider's batch quotes
+    /// on its own thread every `CROSS_CHECK_INTERVAL`, while the regular
+    /// session trades (the batch quotes are daily, so outside it they would
+    /// hold the previous close) or around the clock for crypto.
+    fn poll_cross_check(&mut self) {
+        while let Ok((symbol, result)) = self.cross_check_rx.try_recv() {
+            self.cross_check_busy = false;
+            self.apply_cross_check(symbol, result);
+        }
+
+        if self.cross_check_due() {
+            self.cross_check_busy = true;
+            self.cross_checked = Some(self.clock.now_instant());
+            let symbol = self.stats.symbol.clone();
+            let tx = self.cross_check_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let result = fetch_mini_quotes(provider, std::slice::from_ref(&symbol), false)
+                    .map_err(|e| e.to_string())
+                    .and_then(|quotes| {
+                        quotes
+                            .into_iter()
+                            .find(|quote| quote.symbol == symbol && quote.price > 0.0)
+                            .map(|quote| quote.price)
+                            .ok_or_else(|| format!("no quote for {}", symbol))
+                    });
+                let _ = tx.send((symbol, result));
+            });
+        }
+    }
+
+    fn cross_check_due(&self) -> bool {
+        let live = matches!(self.source, DataSource::Live { .. }) && self.date.is_none();
+        let trading = self.stats.market_state == "REGULAR" || self.stats.instrument_type == "CRYPTOCURRENCY";
+        let due = self.cross_checked.is_none_or(|at| self.clock.elapsed(at) >= CROSS_CHECK_INTERVAL);
+        self.cross_check_tolerance.is_some() && live && trading && due && !self.cross_check_busy && self.stats.price > 0.0
+    }
+
+    /// Records a second-source price for `symbol` against the chart's. A
+    /// failed check is only logged and shown in the debug overlay: the
+    /// check is a safeguard, not something to interrupt the user about.
+    fn apply_cross_check(&mut self, symbol: String, theirs: Result<f64, String>) {
+        if symbol != self.stats.symbol || self.stats.price <= 0.0 {
+            return;
+        }
+        if let Err(e) = &theirs {
+            warn!(symbol = %symbol, error = %e, "price cross-check failed");
+        }
+        let check = CrossCheck { symbol, ours: self.stats.price, theirs, at: self.clock.now_instant() };
+        if let Some(gap_pct) = check.gap_pct() {
+            debug!(symbol = %check.symbol, ours = check.ours, gap_pct, "price cross-checked");
+        }
+        self.cross_check = Some(check);
+        self.dirty = true;
+    }
+
+    /// Percent the second source last disagreed with the shown symbol's
+    /// price by, when that's past the configured tolerance.
+    fn providers_disagree(&self) -> Option<f64> {
+        let tolerance = self.cross_check_tolerance?;
+        let check = self.cross_check.as_ref().filter(|check| check.symbol == self.stats.symbol)?;
+        check.gap_pct().filter(|gap_pct| gap_pct.abs() > tolerance)
+    }
+
+    /// `value`, in the shown quote's currency, converted to the home
+    /// currency. None without a home currency, when the quote is already in
+    /// it, or while no rate is known.
+    fn in_home_currency(&self, value: f64) -> Option<f64> {
+        let home = self.home_currency.as_deref()?;
+        if fx_currency(&self.stats.currency).0 == home {
+            return None;
+        }
+        self.home_rate(&self.stats.currency).map(|rate| value * rate)
+    }
+
+    /// The factor taking prices in `currency` into the home currency: 1 for
+    /// the home currency itself, None without one or while no rate is known.
+    fn home_rate(&self, currency: &str) -> Option<f64> {
+        let home = self.home_currency.as_deref()?;
+        let (code, scale) = fx_currency(currency);
+        if code == home {
+            return Some(scale);
+        }
+        Some(scale * self.fx_rates.get(&code)?.rate?)
+    }
+
+    /// The portfolio table's rows in the chosen order, in the home currency
+    /// where a rate is known.
+    fn portfolio_rows(&self) -> Vec<PortfolioRow> {
+        let mut rows = self.portfolio.rows(&self.portfolio_quotes, |currency| {
+            let home = self.home_currency.as_ref()?;
+            self.home_rate(currency).map(|rate| (home.clone(), rate))
+        });
+        self.portfolio_sort.sort(&mut rows);
+        rows
+    }
+
+    /// Quotes the portfolio's symbols on their own thread every refresh
+    /// interval while the portfolio view is open. A failed fetch keeps the
+    /// last prices and is reported in the view.
+    fn poll_portfolio(&mut self) {
+        while let Ok(result) = self.portfolio_rx.try_recv() {
+            self.portfolio_busy = false;
+            match result {
+                Ok(quotes) => {
+                    self.portfolio_quotes = quotes.into_iter().map(|quote| (quote.symbol.clone(), quote)).collect();
+                    self.portfolio_error = None;
+                }
+                Err(e) => {
+                    warn!(error = %e, "portfolio fetch failed");
+                    self.portfolio_error = Some(e);
+                }
+            }
+            self.dirty = true;
+        }
+
+        let due = self.portfolio_fetched.is_none_or(|at| self.clock.elapsed(at) >= self.portfolio_refresh());
+        let live = matches!(self.source, DataSource::Live { .. });
+        if self.input_mode == InputMode::Portfolio && live && due && !self.portfolio_busy {
+            self.portfolio_busy = true;
+            self.portfolio_fetched = Some(self.clock.now_instant());
+            let symbols = self.portfolio.symbols();
+            let tx = self.portfolio_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let _ = tx.send(fetch_mini_quotes(provider, &symbols, true).map_err(|e| e.to_string()));
+            });
+        }
+    }
+
+    /// How often the portfolio table is quoted: the refresh interval, once
+    /// per `LARGE_WATCHLIST` symbols, so long lists don't hit Yahoo's rate
+    /// limits.
+    fn portfolio_refresh(&self) -> Duration {
+        let batches = self.portfolio.symbols().len().div_ceil(LARGE_WATCHLIST).max(1);
+        self.refresh_interval * batches as u32
+    }
+
+    /// Fetches the current symbol's headlines on their own thread while the
+    /// news panel is open, refreshing every few minutes. A failed fetch keeps
+    /// the headlines already shown and is retried sooner.
+    fn poll_news(&mut self) {
+        while let Ok((symbol, result)) = self.news_rx.try_recv() {
+            self.news_pending = None;
+            let known = match result {
+                Ok(headlines) => KnownNews { headlines, error: None, recheck_at: self.clock.now_instant() + NEWS_INTERVAL },
+                Err(e) => {
+                    warn!(symbol = %symbol, error = %e, "news fetch failed");
+                    let headlines = self.news.get(&symbol).map(|known| known.headlines.clone()).unwrap_or_default();
+                    KnownNews { headlines, error: Some(e), recheck_at: self.clock.now_instant() + NEWS_RETRY }
+                }
+            };
+            if symbol == self.ticker {
+                let len = known.headlines.len();
+                let selected = self.news_state.selected().map(|i| i.min(len.saturating_sub(1)));
+                self.news_state.select(if len == 0 { None } else { selected.or(Some(0)) });
+            }
+            self.news.insert(symbol, known);
+            self.dirty = true;
+        }
+
+        if self.input_mode != InputMode::News {
+            return;
+        }
+        let due = self.news.get(&self.ticker).is_none_or(|known| self.clock.now_instant() >= known.recheck_at);
+        let live = matches!(self.source, DataSource::Live { .. });
+        if live && due && self.news_pending.is_none() {
+            self.news_pending = Some(self.ticker.clone());
+            let symbol = self.ticker.clone();
+            let tx = self.news_tx.clone();
+            let provider = self.provider;
+            thread::spawn(move || {
+                let result = fetch_news(provider, &symbol).map_err(|e| e.to_string());
+                let _ = tx.send((symbol, result));
+            });
+        }
+    }
+
+    /// The shown symbol's calendar, once it has been looked up.
+    fn calendar(&self) -> Option<&Calendar> {
+        self.calendars.get(&self.stats.symbol).map(|known| &known.calendar)
+    }
+
+    /// Whole New York calendar days from today until the day starting at
+    /// `day`, 0 on the day itself. None once it has passed.
+    fn days_until(&self, day: i64) -> Option<i64> {
+        let offset = i64::from(self.stats.utc_offset?);
+        let now = self.clock.now_utc().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+        let days = (day + offset).div_euclid(86_400) - (now + offset).div_euclid(86_400);
+        (days >= 0).then_some(days)
+    }
+
+    fn fetch_request(&self, width: u16, height: u16) -> FetchRequest {
+        FetchRequest {
+            symbol: self.ticker.clone(),
+            width,
+            height,
+            indicators: self.enabled_indicators.clone(),
+            use_24h: self.use_24h_time,
+            price_view: self.price_view,
+            period: self.timeframe.clone(),
+            interval: self.interval.clone(),
+            chart_type: self.chart_type.clone(),
+            palette: self.theme.chart.clone(),
+            change_grades: self.theme.grades,
+            compact: width < 60 || height < 15,
+            gridlines: self.gridlines,
+            line_style: self.line_style,
+            smoothing: self.smoothing,
+            y_scale: self.y_scale,
+            x_axis: self.x_axis,
+            sliding_minutes: self.sliding_minutes,
+            log_scale: self.log_scale,
+            pre_market: self.pre_market,
+            vs_open: self.vs_open,
+            direction_colors: self.direction_colors,
+            prev_day: self.prev_day,
+            levels: self.levels.get(&self.ticker).to_vec(),
+            observed: if self.show_observed { self.observed.points(&self.ticker) } else { Vec::new() },
+            date: self.date.as_ref().map(|day| day.date.clone()),
+            formatter: self.formatter,
+            provider: self.provider,
+            chart: true,
+        }
+    }
+
+    /// Marks the current data as expired so the next loop iteration refetches.
+    /// Any fetch still running was made with outdated settings, so drop it.
+    fn request_refresh(&mut self) {
+        if let Some(fetcher) = self.source.fetcher() {
+            fetcher.cancel();
+        }
+        self.last_fetch_time = self.clock.now_instant().checked_sub(self.refresh_interval * 2).unwrap_or(self.clock.now_instant());
+        self.refresh_requested = true;
+    }
+
+    /// Forgets failed fetches and stops waiting, once the chart shows
+    /// something that didn't need the fetcher.
+    fn reset_fetch_state(&mut self) {
+        self.waiting = None;
+        self.failed_attempts = 0;
+        self.fetch_failure = None;
+    }
+
+    /// The spinner line for a fetch the chart is waiting on: its symbol, how
+    /// long it's been going, and which attempt this is after a failure.
+    fn spinner_label(&self, ascii: bool) -> Option<String> {
+        let (symbol, since) = self.waiting.as_ref()?;
+        let elapsed = self.clock.elapsed(*since);
+        let frame = (elapsed.as_millis() / UI_TICK.as_millis()) as usize;
+        let spinner = if ascii { ASCII_SPINNER[frame % ASCII_SPINNER.len()] } else { SPINNER[frame % SPINNER.len()] };
+        let mut label = format!("{} Fetching {} {:.1}s", spinner, symbol, elapsed.as_secs_f64());
+        if self.failed_attempts > 0 {
+            label.push_str(&format!(", attempt {} of {}", self.failed_attempts + 1, FETCH_ATTEMPTS));
+        }
+        Some(label)
+    }
+
+    /// Switches to `symbol` and records it in the history. Returns whether a
+    /// cached quote could be shown right away.
+    fn open_ticker(&mut self, symbol: String) -> bool {
+        self.history.visit(&symbol);
+        self.quote_cache.retain(|cached, _| self.history.contains(cached));
+        self.show_ticker(symbol)
+    }
+
+    /// Switches to `symbol`, showing its cached quote if that's still fresh
+    /// and was made with the current chart settings, or fetching otherwise.
+    fn show_ticker(&mut self, symbol: String) -> bool {
+        self.ticker = symbol;
+        self.day_step = 0;
+        self.grid_view = false;
+        let settings = self.fetch_request(0, 0);
+        let fresh = self
+            .quote_cache
+            .get(&self.ticker)
+            .filter(|cached| cached.settings == settings && self.clock.elapsed(cached.fetched_at) < self.refresh_interval)
+            .map(|cached| (cached.stats.clone(), cached.area_size, cached.fetched_at));
+        let Some((stats, area_size, fetched_at)) = fresh else {
+            self.request_refresh();
+            return false;
+        };
+        if let Some(fetcher) = self.source.fetcher() {
+            fetcher.cancel();
+        }
+        self.reset_fetch_state();
+        // A quote from the cache isn't a new price, so it mustn't flash
+        self.stats = StockStats::default();
+        self.apply_stats(stats);
+        self.last_fetch_time = fetched_at;
+        self.last_fetched_size = area_size;
+        true
+    }
+
+    /// Shows `day`'s 1d chart, or the live chart again for None, straight
+    /// from the days already fetched when possible.
+    fn show_day(&mut self, day: Option<PastDay>) {
+        match &day {
+            Some(day) => {
+                self.timeframe = "1d".to_string();
+                self.interval = interval_for_day(day, &self.interval);
+                self.toast(ToastLevel::Info, format!("{} {}", day.weekday(), day.date));
+            }
+            None => {
+                if !self.interval_chosen {
+                    self.interval = suggested_interval(&self.timeframe).to_string();
+                }
+                self.toast(ToastLevel::Info, "Live".to_string());
+            }
+        }
+        self.date = day;
+        let settings = self.fetch_request(0, 0);
+        let cached = self
+            .date
+            .as_ref()
+            .and_then(|day| self.day_cache.get(&format!("{} {}", self.ticker, day.date)))
+            .filter(|cached| cached.settings == settings)
+            .map(|cached| (cached.stats.clone(), cached.area_size));
+        let Some((stats, area_size)) = cached else {
+            self.request_refresh();
+            return;
+        };
+        if let Some(fetcher) = self.source.fetcher() {
+            fetcher.cancel();
+        }
+        self.reset_fetch_state();
+        self.stats = StockStats::default();
+        self.apply_stats(stats);
+        self.refresh_requested = false;
+        self.last_fetch_time = self.clock.now_instant();
+        self.last_fetched_size = area_size;
+    }
+
+    /// Switches the chart's range, keeping a chosen interval if the range
+    /// allows it and snapping it otherwise.
+    fn set_timeframe(&mut self, timeframe: &str) {
+        if self.date.is_some() && timeframe != "1d" {
+            self.toast(ToastLevel::Info, "A past day only has a 1d chart; PgDn back to live for other ranges".to_string());
+            return;
+        }
+        self.timeframe = timeframe.to_string();
+        if !self.interval_chosen {
+            self.interval = suggested_interval(timeframe).to_string();
+            return;
+        }
+        let snapped = snap_interval(timeframe, &self.interval);
+        if snapped != self.interval {
+            self.toast(ToastLevel::Info, format!("No {} bars for {}: using {}", self.interval, timeframe, snapped));
+            self.interval = snapped.to_string();
+        }
+    }
+
+    /// Sets the bar size, snapped to what the current range allows.
+    fn set_interval(&mut self, interval: &str) {
+        let mut snapped = snap_interval(&self.timeframe, interval).to_string();
+        if let Some(day) = &self.date {
+            snapped = interval_for_day(day, &snapped);
+        }
+        if snapped != interval {
+            self.toast(ToastLevel::Info, format!("No {} bars for {}: using {}", interval, self.timeframe, snapped));
+        } else {
+            self.toast(ToastLevel::Info, format!("{} bars", snapped));
+        }
+        self.interval = snapped;
+        self.interval_chosen = true;
+        self.request_refresh();
+    }
+
+    fn seconds_until_refresh(&self) -> u64 {
+        self.refresh_interval.saturating_sub(self.clock.elapsed(self.last_fetch_time)).as_secs()
+    }
+
+    /// How far behind the newest bar is, when that's unexpected: only
+    /// intraday data during regular trading hours should keep up with the
+    /// clock. Recorded sessions are never live.
+    fn staleness(&self) -> Option<Duration> {
+        if let Some(since) = self.stale_since {
+            return Some(self.clock.now_utc().duration_since(since).unwrap_or_default());
+        }
+        let (age, bar) = self.bar_age()?;
+        (age > STALE_AFTER + bar).then_some(age)
+    }
+
+    /// Whether the newest bar is more than a bar (plus `LAG_SLACK`) behind
+    /// the clock during regular hours.
+    fn bar_lagging(&self) -> bool {
+        self.bar_age().is_some_and(|(age, bar)| age > bar + LAG_SLACK)
+    }
+
+    /// New York time of the last bar, e.g. "10:42", when none has followed it
+    /// for `HALT_AFTER` during regular hours even though the latest fetch
+    /// succeeded: the bars have stopped, not the feed.
+    fn possible_halt(&self) -> Option<String> {
+        if self.stats.error.is_some() {
+            return None;
+        }
+        let (age, bar) = self.bar_age()?;
+        if age <= HALT_AFTER + bar {
+            return None;
+        }
+        let last_bar = UNIX_EPOCH + Duration::from_secs(self.stats.last_bar?);
+        let clock = format_clock(last_bar, self.stats.utc_offset?);
+        Some(clock[..5].to_string())
+    }
+
+    /// Age of the newest bar and the bar length, while the regular session
+    /// is trading live.
+    fn bar_age(&self) -> Option<(Duration, Duration)> {
+        if self.stats.market_state != "REGULAR" || matches!(self.source, DataSource::Replay(_)) {
+            return None;
+        }
+        let bar = intraday_bar(&self.interval)?;
+        // A garbage timestamp just means "not known to be stale"
+        let last_bar = UNIX_EPOCH.checked_add(Duration::from_secs(self.stats.last_bar?))?;
+        let age = self.clock.now_utc().duration_since(last_bar).ok()?;
+        Some((age, bar))
+    }
+
+    /// Change and percent change from the selected baseline.
+    fn change_from_baseline(&self) -> (f64, f64) {
+        if self.vs_open && self.stats.open > 0.0 {
+            let change = self.stats.price - self.stats.open;
+            (change, change / self.stats.open * 100.0)
+        } else {
+            (self.stats.change, self.stats.pct_change)
+        }
+    }
+
+    /// Shows `text` as a toast. Repeating the newest one only restarts its
+    /// timer, so a failure that recurs every fetch doesn't fill the queue.
+    fn toast(&mut self, level: ToastLevel, text: String) {
+        let now = self.clock.now_instant();
+        match self.toasts.back_mut() {
+            Some(last) if last.text == text && last.level == level => last.created_at = now,
+            _ => {
+                self.toasts.push_back(Toast { text, level, created_at: now });
+                if self.toasts.len() > MAX_TOASTS {
+                    self.toasts.pop_front();
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Reacts to the price moving since the previous fetch of the same symbol:
+    /// flashes the header / rings the bell and sends desktop notifications
+    /// when the respective thresholds are crossed.
+    fn check_large_move(&mut self, stats: &StockStats) {
+        let previous = self.stats.price;
+        if stats.symbol != self.stats.symbol || previous <= 0.0 {
+            return;
+        }
+        let move_pct = (stats.price - previous) / previous * 100.0;
+
+        if self.flash.enabled && move_pct.abs() >= self.flash.threshold {
+            self.flash_started = Some(self.clock.now_instant());
+            self.bell_pending = self.flash.bell;
+        }
+
+        if let Some(notifier) = &self.notifier
+            && move_pct.abs() >= notifier.move_threshold
+        {
+            notifier.send(
+                &format!("{} moved {:+.2}%", stats.symbol, move_pct),
+                &format!(
+                    "{} → {} ({:+.2}% today)",
+                    self.formatter.money(previous, &stats.currency),
+                    self.formatter.money(stats.price, &stats.currency),
+                    stats.pct_change
+                ),
+            );
+        }
+    }
+
+    /// Reports triggered alerts as toasts and as desktop
+    /// notifications, and adds them to the alert log.
+    fn check_alerts(&mut self, stats: &StockStats) {
+        for trigger in alert::check_all(&mut self.alerts, stats, &self.formatter) {
+            info!(message = %trigger.message, "alert triggered");
+            if let Err(e) = alert::append_log(&trigger) {
+                warn!(error = %e, "alert log write failed");
+                self.toast(ToastLevel::Error, format!("Alert log write failed: {}", e));
+            }
+            if let Some(notifier) = &self.notifier {
+                notifier.send("stock-tui alert", &trigger.message);
+            }
+            self.toast(ToastLevel::Info, trigger.message);
+        }
+    }
+
+    /// Copies `text` to the system clipboard, falling back to the terminal's
+    /// OSC 52 sequence over SSH or when no clipboard is reachable.
+    fn copy_to_clipboard(&mut self, text: String) {
+        if env::var_os("SSH_CONNECTION").is_none() {
+            if self.clipboard.is_none() {
+                self.clipboard = arboard::Clipboard::new().ok();
+            }
+            if let Some(clipboard) = self.clipboard.as_mut()
+                && clipboard.set_text(text.clone()).is_ok()
+            {
+                self.toast(ToastLevel::Info, format!("Copied: {}", text));
+                return;
+            }
+            debug!("no system clipboard, falling back to OSC 52");
+        }
+        self.toast(ToastLevel::Info, format!("Copied via terminal: {}", text));
+        self.osc52_pending = Some(text);
+    }
+
+    /// Remembers the price before `stats` when the new one looks different,
+    /// for the header's change since the previous fetch. Another symbol or
+    /// an unchanged price clears it.
+    fn record_tick(&mut self, stats: &StockStats) {
+        let previous = self.stats.price;
+        let moved = stats.symbol == self.stats.symbol
+            && previous > 0.0
+            && self.formatter.price(stats.price) != self.formatter.price(previous);
+        self.last_tick = moved.then(|| (previous, self.clock.now_instant()));
+    }
+
+    /// How far the tick has faded: 0 bold, 1 plain, 2 gray.
+    fn tick_stage(&self) -> u32 {
+        self.last_tick.map_or(2, |(_, at)| (self.clock.elapsed(at).as_millis() * 2 / TICK_FADE.as_millis()).min(2) as u32)
+    }
+
+    fn flash_active(&self) -> bool {
+        self.flash_started.is_some_and(|at| self.clock.elapsed(at) < FLASH_DURATION)
+    }
+
+    /// Seconds until the next refresh as shown: exact, or in low-power mode
+    /// rounded up to the next `LOW_POWER_TICK`, which reads as "<20s".
+    fn shown_countdown(&self) -> u64 {
+        let secs = self.seconds_until_refresh();
+        if self.low_power {
+            let step = LOW_POWER_TICK.as_secs();
+            (secs / step + 1) * step
+        } else {
+            secs
+        }
+    }
+
+    fn countdown_label(&self) -> String {
+        if self.low_power { format!("<{}s", self.shown_countdown()) } else { format!("{}s", self.shown_countdown()) }
+    }
+
+    /// How long the loop may wait for a key before looking at the timers
+    /// again. Low-power mode sleeps until the next thing due to change on
+    /// screen (refresh, tick fade step, end of the flash or a toast),
+    /// at most `LOW_POWER_TICK`. Anything in flight (a fetch, a search, a
+    /// replay, the grid) keeps the short tick so results show promptly.
+    fn poll_timeout(&self) -> Duration {
+        let in_flight = match &self.source {
+            DataSource::Live { fetcher, .. } => fetcher.is_busy(),
+            DataSource::Replay(_) => true,
+        } || self.grid.is_some()
+            || self.search_due.is_some()
+            || self.refresh_requested
+            || self.waiting.is_some()
+            || (self.failed_attempts > 0 && self.fetch_failure.is_none());
+        if !self.low_power || in_flight {
+            return UI_TICK;
+        }
+        let mut due = vec![self.last_fetch_time + self.refresh_interval];
+        if let Some((_, at)) = self.last_tick
+            && self.tick_stage() < 2
+        {
+            due.push(at + TICK_FADE * (self.tick_stage() + 1) / 2);
+        }
+        due.extend(self.flash_started.map(|at| at + FLASH_DURATION));
+        due.extend(self.toasts.iter().map(|toast| toast.created_at + TOAST_TTL));
+        let now = self.clock.now_instant();
+        due.into_iter()
+            .map(|at| at.saturating_duration_since(now))
+            .min()
+            .unwrap_or(LOW_POWER_TICK)
+            .clamp(UI_TICK, LOW_POWER_TICK)
+    }
+
+    /// Whether the chart area has kept its size for `RESIZE_DEBOUNCE`.
+    fn resize_settled(&self) -> bool {
+        self.clock.elapsed(self.last_size_change_time) >= RESIZE_DEBOUNCE
+    }
+
+    /// Brings the time-driven state up to the clock: ends the flash and
+    /// drops toasts once they expire, and marks the screen dirty when a
+    /// shown timer (refresh countdown, header clock, tick fade, halt and
+    /// staleness warnings) changes. Returns whether the chart is due for a
+    /// fetch.
+    fn tick(&mut self) -> bool {
+        self.detect_clock_jump();
+        if self.flash_started.is_some() && !self.flash_active() {
+            self.flash_started = None;
+            self.dirty = true;
+        }
+        let shown = self.toasts.len();
+        let now = self.clock.now_instant();
+        self.toasts.retain(|toast| now.saturating_duration_since(toast.created_at) < TOAST_TTL);
+        if self.toasts.len() != shown {
+            self.dirty = true;
+        }
+
+        // The full-screen title and debug overlay show timers, so repaint when they tick
+        if self.fullscreen || self.show_debug {
+            let countdown = self.shown_countdown();
+            if countdown != self.last_countdown {
+                self.last_countdown = countdown;
+                self.dirty = true;
+            }
+        }
+
+        // The header clock ticks every second, or minute in low-power mode
+        if self.show_header && !self.fullscreen && self.stats.utc_offset.is_some() {
+            let second = if self.low_power { self.clock.unix_secs() / 60 } else { self.clock.unix_secs() };
+            if second != self.last_clock {
+                self.last_clock = second;
+                self.dirty = true;
+            }
+        }
+
+        let tick_stage = self.tick_stage();
+        if tick_stage != self.last_tick_stage {
+            self.last_tick_stage = tick_stage;
+            self.dirty = true;
+        }
+
+        let halt_shown = self.possible_halt().is_some();
+        if halt_shown != self.last_halt_shown {
+            self.last_halt_shown = halt_shown;
+            self.dirty = true;
+        }
+
+        let stale_minutes = self.staleness().map(|age| age.as_secs() / 60);
+        if stale_minutes != self.last_stale_minutes {
+            self.last_stale_minutes = stale_minutes;
+            self.dirty = true;
+        }
+
+        // The spinner moves a frame every tick
+        if self.waiting.is_some() {
+            self.dirty = true;
+        }
+
+        let size_changed = self.current_image_area_size != self.last_fetched_size && self.current_image_area_size.0 > 0;
+        // A failed fetch is retried on its own delay, and after giving up
+        // only 'r' or another symbol fetches again
+        let due = if self.failed_attempts > 0 {
+            self.clock.elapsed(self.last_fetch_time) >= FETCH_RETRY_DELAY
+        } else {
+            // A past day's bars don't change
+            (self.clock.elapsed(self.last_fetch_time) >= self.refresh_interval && self.date.is_none())
+                || (size_changed && self.resize_settled())
+        };
+        match self.input_mode {
+            InputMode::Normal => self.refresh_requested || (self.fetch_failure.is_none() && due),
+            InputMode::Editing | InputMode::SettingsMain | InputMode::SettingsIndicators | InputMode::SettingsTimeframe | InputMode::SettingsInterval | InputMode::Help | InputMode::Command | InputMode::History | InputMode::AlertLog | InputMode::News | InputMode::Portfolio | InputMode::Levels => false,
+        }
+    }
+
+    /// Whether the loop should start the main fetch, given whether `tick`
+    /// found one due: not while one is running, or in the grid.
+    fn may_start_fetch(&mut self, due: bool) -> bool {
+        due && !self.grid_view && self.source.fetcher().is_some_and(|fetcher| !fetcher.is_busy())
+    }
+
+    /// After the machine sleeps or the wall clock is set, timers measured
+    /// across the gap mean nothing, so they restart with a fetch right away,
+    /// and the data is marked stale until that fetch succeeds.
+    fn detect_clock_jump(&mut self) {
+        let Some(jump) = self.clock_jumps.check(self.clock.as_ref()) else {
+            return;
+        };
+        info!(gap_secs = jump.gap.as_secs(), wall_gap_secs = jump.wall_gap.as_secs(), "clock jumped, refetching");
+        if matches!(self.source, DataSource::Live { .. }) && !self.stats.symbol.is_empty() && self.date.is_none() {
+            let fetched = self.fetch_timings.last_success.map(|at| jump.utc - jump.instant.saturating_duration_since(at));
+            self.stale_since.get_or_insert(fetched.unwrap_or(jump.utc));
+        }
+        self.request_refresh();
+        // Cached quotes were only fresh by the monotonic clock
+        self.quote_cache.clear();
+        self.last_tick = None;
+        self.flash_started = None;
+        self.tape_fetched = None;
+        self.portfolio_fetched = None;
+        self.dirty = true;
+    }
+
+    /// Counts a fetch that brought back nothing. Until FETCH_ATTEMPTS run
+    /// out it's retried after FETCH_RETRY_DELAY; then a chart waiting on it
+    /// shows why, while one already showing a quote keeps it and goes back
+    /// to refreshing on the timer.
+    fn fetch_failed(&mut self, error: FetchError) {
+        self.failed_attempts += 1;
+        if self.failed_attempts < FETCH_ATTEMPTS {
+            self.toast(
+                ToastLevel::Warn,
+                format!("Fetch failed, retrying ({} of {}): {}", self.failed_attempts + 1, FETCH_ATTEMPTS, error),
+            );
+        } else if self.waiting.take().is_some() {
+            self.fetch_failure = Some(error);
+            self.dirty = true;
+        } else {
+            self.toast(ToastLevel::Error, format!("Fetch failed: {}", error));
+            self.failed_attempts = 0;
+        }
+    }
+
+    /// Takes the next quote from the data source, if one is ready.
+    fn poll_source(&mut self) {
+        let (stats, record_error) = match &mut self.source {
+            DataSource::Live { fetcher, recorder } => {
+                let Some(outcome) = fetcher.try_recv() else { return };
+                self.fetch_timings.record(outcome.elapsed);
+                // Failures count from here, so the retry waits FETCH_RETRY_DELAY
+                self.last_fetch_time = self.clock.now_instant();
+                let stats = match outcome.result {
+                    Ok(stats) => stats,
+                    Err(e) => {
+                        self.fetch_failed(e);
+                        return;
+                    }
+                };
+                // The recorder still borrows the source, so no reset_fetch_state()
+                self.waiting = None;
+                self.failed_attempts = 0;
+                self.fetch_failure = None;
+                self.last_fetched_size = outcome.area_size;
+                if stats.error.is_none() {
+                    self.fetch_timings.last_success = Some(self.last_fetch_time);
+                    self.stale_since = None;
+                }
+                let record_error = match recorder {
+                    Some(recorder) if stats.error.is_none() => recorder.append(&stats).err(),
+                    _ => None,
+                };
+                if stats.not_found {
+                    self.suggest_symbols();
+                }
+                (stats, record_error)
+            }
+            DataSource::Replay(replay) => match replay.poll() {
+                Some(stats) => (stats, None),
+                None => return,
+            },
+        };
+        if stats.error.is_none() {
+            self.recent_prices.push(&stats.symbol, stats.price);
+        }
+        if stats.error.is_none()
+            && !stats.no_trades
+            && self.date.is_none()
+            && matches!(self.source, DataSource::Live { .. })
+            && let (Ok(now), Some(offset)) = (self.clock.now_utc().duration_since(UNIX_EPOCH), stats.utc_offset)
+        {
+            self.observed.push(&stats.symbol, now.as_secs(), offset, stats.price);
+        }
+        // A holiday while stepping through past days: carry on to the next day
+        if stats.no_trading && self.day_step != 0 && let Some(day) = &self.date {
+            let next = day.step(self.day_step);
+            if next.is_some() || self.day_step > 0 {
+                self.show_day(next);
+                return;
+            }
+        }
+        if stats.error.is_none() && matches!(self.source, DataSource::Live { .. }) {
+            let cached = CachedQuote {
+                stats: stats.clone(),
+                settings: self.fetch_request(0, 0),
+                area_size: self.last_fetched_size,
+                fetched_at: self.last_fetch_time,
+            };
+            match &self.date {
+                Some(day) => self.day_cache.insert(format!("{} {}", self.ticker, day.date), cached),
+                None => self.quote_cache.insert(self.ticker.clone(), cached),
+            };
+        }
+        if let Some(e) = record_error {
+            warn!(error = %e, "recording failed");
+            self.toast(ToastLevel::Error, format!("Recording failed: {}", e));
+        }
+        self.apply_stats(stats);
+    }
+
+    fn apply_stats(&mut self, stats: StockStats) {
+        if let Some(series) = &stats.series {
+            let unmatched = series.unmatched();
+            if unmatched > 0 {
+                warn!(symbol = %stats.symbol, rows = series.len(), unmatched, "chart series columns differ in length; extra points ignored");
+                self.toast(ToastLevel::Warn, format!("{} chart points ignored: columns differ in length", unmatched));
+            }
+        }
+        if stats.error.is_none() {
+            // Without trades the price is only the last close, not a move;
+            // a past day's price isn't one either
+            if !stats.no_trades && self.date.is_none() {
+                self.record_tick(&stats);
+                self.check_large_move(&stats);
+                self.check_alerts(&stats);
+            }
+            if self.set_title {
+                self.title_pending = Some(window_title(&stats, self.ascii, &self.formatter));
+            }
+        }
+        if matches!(self.source, DataSource::Replay(_)) {
+            self.ticker = stats.symbol.clone();
+        }
+        if stats.symbol != self.stats.symbol {
+            self.symbol_info = self.tickers_db
+                .iter()
+                .find(|t| t.ticker == stats.symbol)
+                .map(|info| (info.name.clone(), info.kind.clone()))
+                .unwrap_or_else(|| ("Unknown".to_string(), "Unknown".to_string()));
+        }
+        if stats.error.is_none() {
+            self.session_stats = intraday_bar(&self.interval)
+                .filter(|_| self.timeframe == "1d")
+                .and_then(|bar| SessionStats::compute(&stats, bar));
+        }
+        self.stats = stats;
+        self.dirty = true;
+        if self.stats.no_trades {
+            self.image_protocol = None;
+        }
+        if let Some(ref data) = self.stats.image_data {
+            match decode_image(data) {
+                Some(img) => self.image_protocol = Some(self.picker.new_resize_protocol(img)),
+                None => {
+                    warn!(symbol = %self.stats.symbol, bytes = data.len(), "chart image could not be decoded");
+                    self.toast(ToastLevel::Error, "The chart image could not be decoded".to_string());
+                }
+            }
+        }
+    }
+}
+
+/// One-line quote for the clipboard, e.g. "AAPL $231.45 +1.23% @ 14:32 ET".
+fn quote_summary(stats: &StockStats, fmt: &Formatter) -> String {
+    let sign = if stats.pct_change > 0.0 { "+" } else { "" };
+    let mut summary = format!(
+        "{} {} {}{}",
+        stats.symbol,
+        fmt.money(stats.price, &stats.currency),
+        sign,
+        format_pct(stats.pct_change)
+    );
+    if !stats.as_of.is_empty() {
+        summary.push_str(&format!(" @ {}", stats.as_of));
+    }
+    summary
+}
+
+/// Terminal title for a quote, e.g. "AAPL ▲1.20% $231.45".
+fn window_title(stats: &StockStats, ascii: bool, fmt: &Formatter) -> String {
+    format!(
+        "{} {}{} {}",
+        stats.symbol,
+        change_arrow(stats.change, ascii),
+        format_pct(stats.pct_change),
+        fmt.money(stats.price, &stats.currency)
+    )
+}
+
+/// The ISO code to convert a quote currency from, and the factor taking
+/// prices into it: London quotes in pence ("GBp") convert as GBP / 100. An
+/// empty code is treated as USD.
+fn fx_currency(currency: &str) -> (String, f64) {
+    match currency {
+        "" => ("USD".to_string(), 1.0),
+        "GBp" | "GBX" => ("GBP".to_string(), 0.01),
+        "ZAc" => ("ZAR".to_string(), 0.01),
+        "ILA" => ("ILS".to_string(), 0.01),
+        code => (code.to_uppercase(), 1.0),
+    }
+}
+
+pub fn load_tickers() -> Result<Vec<TickerRecord>, Box<dyn Error>> {
+    let file = File::open("top-tickers.csv")?;
+    let mut rdr = csv::Reader::from_reader(file);
+    let mut tickers = Vec::new();
+    for result in rdr.deserialize() {
+        let record: TickerRecord = result?;
+        tickers.push(record);
+    }
+    Ok(tickers)
+}
+
+#[derive(Clone, Debug)]
+struct IndicatorMeta {
+    name: String,
+    requires_price: bool,
+}
+
+fn get_available_indicators() -> Vec<IndicatorMeta> {
+    let mut indicators = Vec::new();
+    if let Ok(entries) = fs::read_dir("indicators") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("py")
+                && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+                && stem != "__init__"
+            {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                let requires_price = content.contains("REQUIRES_PRICE = True");
+                indicators.push(IndicatorMeta {
+                    name: stem.to_string(),
+                    requires_price,
+                });
+            }
+        }
+    }
+    indicators.sort_by(|a, b| a.name.cmp(&b.name));
+    indicators
+}
+
+/// True when the locale explicitly selects a non-UTF-8 character set, in which
+/// case box drawing and arrow glyphs are likely to render as garbage.
+fn locale_lacks_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_lowercase();
+            !value.contains("utf-8") && !value.contains("utf8")
+        })
+}
+
+/// Wipes the old layout (and any chart image drawn outside the cell buffer)
+/// so nothing of it survives, and redraws at the new size straight away.
+fn resized<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    terminal.clear()?;
+    app.dirty = true;
+    Ok(())
+}
+
+/// Draws, handles keys and fetches until the user quits.
+pub fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+    loop {
+        if app.dirty {
+            let frame = terminal.draw(|f| ui(f, app))?;
+            if let Some((template, ansi)) = app.snapshot_pending.take() {
+                let text = snapshot::render(frame.buffer, ansi);
+                save_snapshot(app, &template, text);
+            }
+            app.dirty = false;
+        }
+
+        if event::poll(app.poll_timeout())? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    app.dirty = true;
+                    if handle_key(app, key) {
+                        return Ok(());
+                    }
+                }
+                Event::Resize(_, _) => resized(terminal, app)?,
+                _ => {}
+            }
+        }
+
+        app.poll_search();
+        app.poll_tape();
+        app.poll_calendar();
+        app.poll_news();
+        app.poll_portfolio();
+        app.poll_fx();
+        app.poll_cross_check();
+
+        if let Some(err) = app.notifier.as_ref().and_then(|n| n.take_error()) {
+            warn!(error = %err, "desktop notification failed");
+            app.toast(ToastLevel::Warn, err);
+        }
+        if let Some(result) = app.exporter.take_result() {
+            match result {
+                Ok(path) => app.toast(ToastLevel::Info, format!("Exported {}", path.display())),
+                Err(e) => {
+                    warn!(error = %e, "export failed");
+                    app.toast(ToastLevel::Error, format!("Export failed: {}", e));
+                }
+            }
+        }
+        if app.bell_pending {
+            app.bell_pending = false;
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
+        }
+        if let Some(text) = app.osc52_pending.take() {
+            let backend = terminal.backend_mut();
+            write!(backend, "\x1b]52;c;{}\x07", general_purpose::STANDARD.encode(text))?;
+            backend.flush()?;
+        }
+        if let Some(title) = app.title_pending.take() {
+            execute!(terminal.backend_mut(), SetTitle(title))?;
+        }
+        app.poll_source();
+        let should_fetch = app.tick();
+
+        if app.grid.is_some() {
+            let template = app.fetch_request(0, 0);
+            let may_start = app.grid_view && app.input_mode == InputMode::Normal;
+            let resize_settled = app.resize_settled();
+            if let Some(grid) = app.grid.as_mut()
+                && grid.poll(&template, app.refresh_interval, may_start, resize_settled, &app.picker)
+            {
+                app.dirty = true;
+            }
+        }
+
+        if app.may_start_fetch(should_fetch) {
+            let (w, h) = app.current_image_area_size;
+            let w_arg = if w > 0 { w } else { 100 };
+            let h_arg = if h > 0 { h } else { 40 };
+
+            let req = app.fetch_request(w_arg, h_arg);
+            let symbol = req.symbol.clone();
+            if let Some(fetcher) = app.source.fetcher() {
+                fetcher.spawn(req, (w, h));
+                // Settings, the symbol or 'r' asked for this one, so the chart
+                // waits on it; a timed refresh updates it quietly
+                if app.refresh_requested {
+                    app.reset_fetch_state();
+                    app.waiting = Some((symbol, app.clock.now_instant()));
+                }
+                app.refresh_requested = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use clap::Parser;
+    use ratatui::backend::TestBackend;
+    use ratatui_image::picker::Picker;
+
+    use super::*;
+    use crate::{clock::MockClock, export::Series};
+
+    /// 2026-03-02 15:00 UTC, a Monday at 10:00 in New York
+    const MONDAY_10AM: u64 = 1_772_463_600;
+
+    /// An app watching AAPL, as if started with `tmp2 AAPL`, that hasn't
+    /// fetched anything yet.
+    pub(crate) fn test_app() -> App {
+        app_with_clock(&Rc::new(MockClock::new(MONDAY_10AM)))
+    }
+
+    /// Like `test_app`, on a clock the test advances.
+    pub(crate) fn app_with_clock(clock: &Rc<MockClock>) -> App {
+        let cli = Cli::try_parse_from(["tmp2", "AAPL"]).unwrap();
+        let source = DataSource::from_cli(&cli).unwrap();
+        let theme = Theme::by_name("default").unwrap();
+        App::new(&cli, Config::default(), theme, Vec::new(), Picker::halfblocks(), source, clock.clone())
+    }
+
+    /// Ticks once a second for `secs` seconds, as the loop would, returning
+    /// the seconds after which a fetch was due.
+    fn run_for(app: &mut App, clock: &MockClock, secs: u64) -> Vec<u64> {
+        let mut due = Vec::new();
+        for second in 1..=secs {
+            clock.advance(Duration::from_secs(1));
+            if app.tick() {
+                due.push(second);
+            }
+        }
+        due
+    }
+
+    /// As if a fetch of `market_state` data, with a bar stamped now, had
+    /// just completed.
+    fn fetched(app: &mut App, clock: &MockClock, market_state: &str) {
+        app.last_fetch_time = clock.now_instant();
+        app.refresh_requested = false;
+        app.stats.symbol = "AAPL".to_string();
+        app.stats.market_state = market_state.to_string();
+        app.stats.utc_offset = Some(-5 * 3600);
+        app.stats.last_bar = Some(clock.unix_secs());
+    }
+
+    /// Runs the loop's timing for a minute with no keys pressed: sleeps
+    /// for `poll_timeout`, ticks, draws when dirty, and completes each fetch
+    /// as soon as it is due. Returns how many times it woke and drew.
+    fn wakes_and_draws_in_a_minute(app: &mut App, clock: &MockClock) -> (u32, u32) {
+        let (mut wakes, mut draws) = (0, 0);
+        let end = clock.now_instant() + Duration::from_secs(60);
+        while clock.now_instant() < end {
+            clock.advance(app.poll_timeout());
+            wakes += 1;
+            if app.tick() {
+                fetched(app, clock, "REGULAR");
+                app.dirty = true;
+            }
+            if app.dirty {
+                draws += 1;
+                app.dirty = false;
+            }
+        }
+        (wakes, draws)
+    }
+
+    #[test]
+    fn low_power_wakes_about_every_ten_seconds() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        // Every UI_TICK, drawing each second for the header clock and once
+        // more for the fetch
+        assert_eq!(wakes_and_draws_in_a_minute(&mut app, &clock), (300, 61));
+
+        app.low_power = true;
+        // Every LOW_POWER_TICK, drawing for the fetch and the header clock's
+        // minute
+        assert_eq!(wakes_and_draws_in_a_minute(&mut app, &clock), (6, 2));
+    }
+
+    #[test]
+    fn first_tick_is_due() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        assert!(app.tick());
+    }
+
+    #[test]
+    fn fetch_is_due_exactly_at_the_refresh_interval() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        assert_eq!(run_for(&mut app, &clock, 59), Vec::<u64>::new());
+        assert_eq!(run_for(&mut app, &clock, 1), vec![1]);
+    }
+
+    #[test]
+    fn countdown_rolls_over_to_zero_and_restarts() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        assert_eq!(app.seconds_until_refresh(), 60);
+        run_for(&mut app, &clock, 59);
+        assert_eq!(app.seconds_until_refresh(), 1);
+        run_for(&mut app, &clock, 1);
+        assert_eq!(app.seconds_until_refresh(), 0);
+        // A slow fetch leaves it at 0 rather than wrapping
+        run_for(&mut app, &clock, 5);
+        assert_eq!(app.seconds_until_refresh(), 0);
+        fetched(&mut app, &clock, "REGULAR");
+        assert_eq!(app.seconds_until_refresh(), 60);
+    }
+
+    #[test]
+    fn halt_shows_one_second_past_the_limit_while_the_market_is_open() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        // One-minute bars: a bar is late after HALT_AFTER plus a bar
+        let limit = (HALT_AFTER + Duration::from_secs(60)).as_secs();
+        run_for(&mut app, &clock, limit);
+        assert_eq!(app.possible_halt(), None);
+        run_for(&mut app, &clock, 1);
+        assert_eq!(app.possible_halt().as_deref(), Some("10:00"));
+    }
+
+    #[test]
+    fn closing_and_opening_toggle_the_live_checks() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        let stale_limit = (STALE_AFTER + Duration::from_secs(60)).as_secs();
+        run_for(&mut app, &clock, stale_limit);
+        assert_eq!(app.staleness(), None);
+        run_for(&mut app, &clock, 1);
+        assert!(app.staleness().is_some());
+
+        // After the close, old bars are expected
+        app.stats.market_state = "POST".to_string();
+        assert_eq!(app.staleness(), None);
+        assert_eq!(app.possible_halt(), None);
+        assert!(!app.bar_lagging());
+
+        // At the open they're measured again, from the newest bar
+        fetched(&mut app, &clock, "REGULAR");
+        assert_eq!(app.staleness(), None);
+        run_for(&mut app, &clock, stale_limit + 1);
+        assert!(app.staleness().is_some());
+    }
+
+    #[test]
+    fn waking_from_sleep_fetches_once_and_shows_stale_until_it_succeeds() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        run_for(&mut app, &clock, 10);
+
+        clock.advance(Duration::from_secs(8 * 3600));
+        let mut started = 0;
+        for _ in 0..5 {
+            let due = app.tick();
+            if app.may_start_fetch(due)
+                && let DataSource::Live { fetcher, .. } = &mut app.source
+            {
+                fetcher.begin();
+                app.refresh_requested = false;
+                started += 1;
+            }
+            assert!(app.staleness().is_some());
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(started, 1);
+
+        let stats = StockStats { symbol: "AAPL".to_string(), price: 200.0, ..StockStats::default() };
+        if let DataSource::Live { fetcher, .. } = &mut app.source {
+            fetcher.finish(Ok(stats));
+        }
+        app.poll_source();
+        assert_eq!(app.staleness(), None);
+    }
+
+    pub(crate) fn ticker(symbol: &str, name: &str) -> TickerRecord {
+        TickerRecord { ticker: symbol.to_string(), name: name.to_string(), kind: "EQUITY".to_string(), exchange: String::new() }
+    }
+
+    fn quote(symbol: &str) -> StockStats {
+        StockStats { symbol: symbol.to_string(), price: 100.0, ..StockStats::default() }
+    }
+
+    #[test]
+    fn symbol_name_is_resolved_when_new_data_names_another_symbol() {
+        let mut app = test_app();
+        app.tickers_db = vec![ticker("AAPL", "Apple Inc."), ticker("MSFT", "Microsoft Corporation")];
+        app.apply_stats(quote("AAPL"));
+        assert_eq!(app.symbol_info.0, "Apple Inc.");
+
+        // A refresh of the same symbol keeps what was resolved, without
+        // searching again
+        app.tickers_db.clear();
+        app.apply_stats(quote("AAPL"));
+        assert_eq!(app.symbol_info.0, "Apple Inc.");
+
+        app.apply_stats(quote("MSFT"));
+        assert_eq!(app.symbol_info, ("Unknown".to_string(), "Unknown".to_string()));
+    }
+
+    #[test]
+    fn cross_check_runs_every_few_minutes_while_trading_once_enabled() {
+        let clock = Rc::new(MockClock::new(MONDAY_10AM));
+        let mut app = app_with_clock(&clock);
+        fetched(&mut app, &clock, "REGULAR");
+        app.stats.price = 100.0;
+        assert!(!app.cross_check_due());
+
+        app.cross_check_tolerance = Some(0.5);
+        assert!(app.cross_check_due());
+        app.cross_checked = Some(clock.now_instant());
+        clock.advance(CROSS_CHECK_INTERVAL - Duration::from_secs(1));
+        assert!(!app.cross_check_due());
+        clock.advance(Duration::from_secs(1));
+        assert!(app.cross_check_due());
+
+        // The batch quotes hold the previous close outside the session,
+        // except for crypto, which has none
+        app.stats.market_state = "CLOSED".to_string();
+        assert!(!app.cross_check_due());
+        app.stats.instrument_type = "CRYPTOCURRENCY".to_string();
+        assert!(app.cross_check_due());
+    }
+
+    #[test]
+    fn providers_disagree_past_the_tolerance() {
+        let mut app = test_app();
+        app.cross_check_tolerance = Some(0.5);
+        app.stats = quote("AAPL");
+        app.apply_cross_check("AAPL".to_string(), Ok(100.5));
+        assert_eq!(app.providers_disagree(), None);
+        app.apply_cross_check("AAPL".to_string(), Ok(99.4));
+        assert!(app.providers_disagree().is_some_and(|gap_pct| (gap_pct + 0.6).abs() < 1e-9));
+
+        // A failed check, or one for a symbol no longer shown, says nothing
+        app.apply_cross_check("MSFT".to_string(), Ok(250.0));
+        assert!(app.providers_disagree().is_some());
+        app.apply_cross_check("AAPL".to_string(), Err("no quote for AAPL".to_string()));
+        assert_eq!(app.providers_disagree(), None);
+        app.apply_cross_check("AAPL".to_string(), Ok(99.4));
+        app.stats = quote("MSFT");
+        assert_eq!(app.providers_disagree(), None);
+    }
+
+    #[test]
+    fn ragged_series_is_shown_with_a_warning() {
+        let mut app = test_app();
+        let mut stats = quote("AAPL");
+        stats.series = Some(Series {
+            utc: vec!["2026-03-02T15:00:00Z".to_string(); 3],
+            et: vec!["2026-03-02 10:00".to_string(); 3],
+            price: vec![100.0; 3],
+            pct_change: vec![0.0; 2],
+            volume: vec![10; 3],
+        });
+        app.apply_stats(stats);
+        assert_eq!(app.stats.series.as_ref().map(Series::len), Some(2));
+        let toast = app.toasts.back().unwrap();
+        assert_eq!(toast.text, "1 chart points ignored: columns differ in length");
+    }
+
+    #[test]
+    fn resize_redraws_with_the_new_layout() {
+        let mut app = test_app();
+        app.stats = StockStats { change: 1.2, pct_change: 0.52, ..quote("AAPL") };
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        let frame = terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(frame.buffer.content.iter().map(|cell| cell.symbol()).collect::<String>().contains("Price: $100.00"));
+        let wide_chart = app.current_image_area_size;
+        app.dirty = false;
+
+        terminal.backend_mut().resize(60, 20);
+        resized(&mut terminal, &mut app).unwrap();
+        assert!(app.dirty);
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..20).flat_map(|y| (0..60).map(move |x| (x, y))).map(|at| buffer[at].symbol()).collect();
+        // Narrower than 80 columns, the header drops its labels
+        assert!(screen.contains("$100.00 ▲ +1.20 (0.52%)") && !screen.contains("Price:"), "{}", screen);
+        assert_ne!(app.current_image_area_size, wide_chart);
+        assert!(app.current_image_area_size.0 < 60 && app.current_image_area_size.1 < 20);
+    }
+}
@@ -0,0 +1,1584 @@
+//! Drawing the app into a frame.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    symbols::border,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+use ratatui_image::StatefulImage;
+
+use super::{
+    App, InputMode, KnownNews, Toast, ToastLevel, EARNINGS_SOON_DAYS, EX_DIVIDEND_SOON_DAYS, FAR_FROM_HIGH_PCT,
+    LIVE_PRICE_GAP_PCT, NEAR_HIGH_PCT, SHOWN_TOASTS,
+};
+use crate::{
+    api::yahoo::FetchError,
+    billboard,
+    command::COMMANDS,
+    export::Series,
+    fetch::{interval_allowed, DataSource},
+    format::{fit_line, fit_title, format_age, format_clock, format_day, format_pct, Formatter, Segment},
+    keymap::{self, Action, KEYMAP, SECTIONS},
+    model::StockStats,
+    portfolio,
+    theme::{change_arrow, color_for_change, Theme},
+};
+
+/// Plain ASCII box characters for terminals that can't draw line art.
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+fn bordered(theme: &Theme, ascii: bool) -> Block<'static> {
+    let block = Block::default().borders(Borders::ALL).border_style(theme.border_style());
+    if ascii { block.border_set(ASCII_BORDER) } else { block }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1]);
+
+    layout[1]
+}
+
+/// Display names for the usual index symbols.
+fn tape_label(symbol: &str) -> &str {
+    match symbol {
+        "^GSPC" => "S&P 500",
+        "^IXIC" => "Nasdaq",
+        "^DJI" => "Dow",
+        "^VIX" => "VIX",
+        _ => symbol,
+    }
+}
+
+/// One line of index quotes. Quotes that don't fit are cut off with an
+/// ellipsis rather than wrapped.
+fn draw_tape(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    if app.tape.is_empty() {
+        f.render_widget(Paragraph::new(Span::styled("Loading market indices...", theme.dim_style())), area);
+        return;
+    }
+    let ellipsis = if ascii { "..." } else { "…" };
+    let mut spans = Vec::new();
+    let mut used = 0;
+    for quote in &app.tape {
+        let item = [
+            Span::styled(format!("{} ", tape_label(&quote.symbol)), Style::default().bold()),
+            Span::raw(format!("{} ", app.formatter.price(quote.price))),
+            Span::styled(
+                format!("{}{}", change_arrow(quote.pct_change, ascii), format_pct(quote.pct_change)),
+                color_for_change(quote.pct_change, theme),
+            ),
+        ];
+        let gap = if spans.is_empty() { 0 } else { 2 };
+        let width = gap + item.iter().map(Span::width).sum::<usize>();
+        if used + width > area.width as usize {
+            spans.push(Span::styled(format!("{:gap$}{}", "", ellipsis), theme.dim_style()));
+            break;
+        }
+        if gap > 0 {
+            spans.push(Span::raw("  "));
+        }
+        spans.extend(item);
+        used += width;
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_header(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    let compact = area.width < COMPACT_WIDTH;
+    let (name, kind) = (&app.symbol_info.0, &app.symbol_info.1);
+
+    let title = if compact {
+        format!("{} | {}", app.stats.symbol, name)
+    } else {
+        format!("Stock Stats: {} | {} ({})", app.stats.symbol, name, kind)
+    };
+    let historical = app.date.as_ref().map(|day| Span::styled(format!(" {} historical ", day.date), theme.warn_style()));
+    let mut clock = Vec::new();
+    if let Some(offset) = app.stats.utc_offset
+        && app.stats.error.is_none()
+    {
+        let time = format_clock(app.clock.now_utc(), offset);
+        let time = if app.low_power { &time[..5] } else { &time[..] };
+        clock.push(Span::raw(format!(" {} ET ", time)));
+        if !compact && !app.stats.as_of.is_empty() {
+            let dot = if ascii { "|" } else { "·" };
+            clock.insert(0, Span::styled(format!(" {dot}"), theme.dim_style()));
+            let lag_style = if app.bar_lagging() { theme.warn_style() } else { theme.dim_style() };
+            clock.insert(0, Span::styled(format!(" last bar {}", app.stats.as_of), lag_style));
+        }
+    }
+    let border = area.width.saturating_sub(2) as usize;
+    let historical_width = historical.as_ref().map_or(0, Span::width);
+    let (title, clock) = fit_title(title, &app.stats.symbol, historical_width, clock, border);
+    let mut header_block = bordered(theme, ascii).title(title);
+    if let Some(historical) = historical {
+        header_block = header_block.title(historical);
+    }
+    if !clock.is_empty() {
+        header_block = header_block.title(Line::from(clock).right_aligned());
+    }
+
+    let stats_text = if let Some(err) = &app.stats.error {
+        let mut lines = vec![Line::from(Span::styled(
+            format!("Error: {}", err),
+            Style::default().fg(theme.down),
+        ))];
+        let suggestions = app.shown_suggestions();
+        if let [only] = suggestions {
+            lines.push(Line::from(format!("Did you mean {} ({})? Press 1", only.ticker, only.name)));
+        } else if !suggestions.is_empty() {
+            let mut line = vec![Span::raw("Did you mean:")];
+            for (i, r) in suggestions.iter().enumerate() {
+                line.push(Span::styled(format!(" {}", i + 1), Style::default().bold()));
+                line.push(Span::raw(format!(" {} ({})", r.ticker, r.name)));
+            }
+            lines.push(Line::from(line));
+        }
+        lines
+    } else if app.stats.symbol.is_empty() {
+        let text = if app.fetch_failure.is_some() { format!("No data for {}", app.ticker) } else { format!("Loading {}...", app.ticker) };
+        vec![Line::from(Span::styled(text, theme.dim_style()))]
+    } else {
+        quote_lines(app, area.width.saturating_sub(2) as usize, compact, theme, ascii)
+    };
+
+    let mut paragraph = Paragraph::new(stats_text).block(header_block);
+    if app.flash_active() {
+        paragraph = paragraph.add_modifier(Modifier::REVERSED);
+    }
+    f.render_widget(paragraph, area);
+}
+
+/// The header's price line and range line for `room` columns, shortened
+/// when `compact`, each leaving out its least important figures when they
+/// don't fit.
+fn quote_lines(app: &App, room: usize, compact: bool, theme: &Theme, ascii: bool) -> Vec<Line<'static>> {
+    let (change, pct_change) = app.change_from_baseline();
+    let change_style = color_for_change(pct_change, theme);
+    let sep = if compact { " " } else { " | " };
+
+    // Most important first: what's left out on a narrow terminal or for
+    // a six-figure price goes from the bottom of this list
+    const PRICE: u8 = 9;
+    const CHANGE: u8 = 8;
+    const STALE: u8 = 7;
+    const DISAGREE: u8 = 6;
+    const TICK: u8 = 5;
+    const EARNINGS: u8 = 4;
+    const HOME_CURRENCY: u8 = 3;
+    const BID_ASK: u8 = 2;
+    const DIVIDEND: u8 = 1;
+
+    let mut price_line = Vec::new();
+    let stale = app.staleness();
+    let price_style = match stale {
+        Some(_) => theme.warn_style().bold(),
+        None => Style::default().bold(),
+    };
+    let price = Span::styled(app.formatter.money(app.stats.price, &app.stats.currency), price_style);
+    price_line.push(Segment::new(PRICE, "", if compact { vec![price] } else { vec![Span::raw("Price: "), price] }));
+    if let (Some(home), Some(converted)) = (app.home_currency.as_deref(), app.in_home_currency(app.stats.price)) {
+        price_line.push(Segment::new(
+            HOME_CURRENCY,
+            " ",
+            vec![Span::styled(format!("(≈{})", app.formatter.money(converted, home)), theme.dim_style())],
+        ));
+    }
+    if let Some((previous, _)) = app.last_tick {
+        let tick = app.stats.price - previous;
+        let tick_style = match app.tick_stage() {
+            0 => theme.change_style(tick),
+            1 => Style::default().fg(theme.change_color(tick)),
+            _ => theme.dim_style(),
+        };
+        price_line.push(Segment::new(TICK, " ", vec![Span::styled(app.formatter.change(tick, app.stats.price), tick_style)]));
+    }
+    if let Some(age) = stale {
+        price_line.push(Segment::new(STALE, " ", vec![Span::styled(format!("stale {}", format_age(age)), theme.warn_style())]));
+    }
+    if app.providers_disagree().is_some() {
+        let marker = if ascii { "! providers disagree" } else { "⚠ providers disagree" };
+        price_line.push(Segment::new(DISAGREE, " ", vec![Span::styled(marker, theme.warn_style())]));
+    }
+    if !compact
+        && let (Some(bid), Some(ask)) = (app.stats.bid, app.stats.ask)
+    {
+        price_line.push(Segment::new(
+            BID_ASK,
+            " ",
+            vec![Span::styled(
+                format!(
+                    "B: {}{times}{} A: {}{times}{} S: {}",
+                    app.formatter.price(bid),
+                    app.stats.bid_size.unwrap_or(0),
+                    app.formatter.price(ask),
+                    app.stats.ask_size.unwrap_or(0),
+                    app.formatter.spread(ask - bid, ask),
+                    times = if ascii { "x" } else { "×" },
+                ),
+                theme.dim_style(),
+            )],
+        ));
+    }
+    let mut change_spans = vec![Span::raw(match (compact, app.stats.rolling_24h) {
+        (true, false) => "",
+        (true, true) => "24h ",
+        (false, false) => "Change: ",
+        (false, true) => "24h: ",
+    })];
+    change_spans.push(Span::styled(
+        format!(
+            "{} {} ({})",
+            change_arrow(change, ascii),
+            app.formatter.change(change, app.stats.price),
+            format_pct(pct_change)
+        ),
+        change_style,
+    ));
+    if app.vs_open {
+        change_spans.push(Span::styled(" (vs open)", theme.dim_style()));
+    }
+    price_line.push(Segment::new(CHANGE, sep, change_spans));
+    let calendar = app.calendar().copied().unwrap_or_default();
+    if let Some(days) = calendar.next_earnings.and_then(|day| app.days_until(day)) {
+        let (text, style) = match days {
+            0 => ("ER today".to_string(), Style::default().fg(theme.down).bold()),
+            d if d <= EARNINGS_SOON_DAYS => (format!("ER in {}d", d), theme.warn_style()),
+            d => (format!("ER in {}d", d), theme.dim_style()),
+        };
+        price_line.push(Segment::new(EARNINGS, sep, vec![Span::styled(text, style)]));
+    }
+    if !compact && let Some(dividend_yield) = calendar.dividend_yield {
+        price_line.push(Segment::new(DIVIDEND, sep, vec![Span::raw(format!("Yield: {:.2}%", dividend_yield))]));
+    }
+    if !compact
+        && let Some(day) = calendar.ex_dividend
+        && let (Some(days), Some(offset)) = (app.days_until(day), app.stats.utc_offset)
+    {
+        let style = if days <= EX_DIVIDEND_SOON_DAYS { theme.hot_style() } else { theme.dim_style() };
+        price_line.push(Segment::new(
+            DIVIDEND,
+            sep,
+            vec![Span::styled(format!("ex-div {} ({}d)", format_day(day, offset), days), style)],
+        ));
+    }
+
+    // The day's range goes before the volume, the open before the high
+    // and low, and the extras before any of them
+    const VOLUME: u8 = 5;
+    const HIGH_LOW: u8 = 4;
+    const OPEN: u8 = 3;
+    const RVOL: u8 = 2;
+    const EXTREMES: u8 = 1;
+    const SPARKLINE: u8 = 0;
+
+    // A figure the fetch didn't get is left out rather than shown as 0
+    let mut range_line = Vec::new();
+    for (label, value, priority) in
+        [("O", app.stats.open, OPEN), ("H", app.stats.high, HIGH_LOW), ("L", app.stats.low, HIGH_LOW)]
+    {
+        if value > 0.0 {
+            range_line.push(Segment::new(priority, sep, vec![Span::raw(format!("{}: {}", label, app.formatter.price(value)))]));
+        }
+    }
+    // Indices (and some futures) report no volume at all
+    if app.stats.volume > 0 {
+        let label = if compact { "V: " } else { "Vol: " };
+        range_line.push(Segment::new(VOLUME, sep, vec![Span::raw(format!("{}{}", label, app.formatter.volume(app.stats.volume)))]));
+    }
+    if !compact
+        && app.stats.volume > 0
+        && let Some((rvol, adjusted)) = app.stats.relative_volume(app.rvol_time_adjusted)
+    {
+        let style = if rvol > 2.0 {
+            theme.hot_style()
+        } else if rvol < 1.0 {
+            theme.dim_style()
+        } else {
+            Style::default()
+        };
+        let text = format!(
+            "(RVOL {:.1}{}{})",
+            rvol,
+            if ascii { "x" } else { "×" },
+            if adjusted { " adj" } else { "" }
+        );
+        range_line.push(Segment::new(RVOL, " ", vec![Span::styled(text, style)]));
+    }
+    if !compact && let Some((from_high, from_low)) = app.stats.distance_from_extremes() {
+        let high_style = if from_high <= NEAR_HIGH_PCT {
+            Style::default().fg(theme.up)
+        } else if from_high >= FAR_FROM_HIGH_PCT {
+            Style::default().fg(theme.down)
+        } else {
+            Style::default()
+        };
+        let dot = if ascii { "|" } else { "·" };
+        let (high, low) = if app.stats.rolling_24h { ("24h H", "24h L") } else { ("HOD", "LOD") };
+        range_line.push(Segment::new(
+            EXTREMES,
+            " | ",
+            vec![
+                Span::styled(format!("{}{} from {high}", change_arrow(-1.0, ascii), format_pct(from_high)), high_style),
+                Span::styled(format!(" {dot} "), theme.dim_style()),
+                Span::raw(format!("{}{} from {low}", change_arrow(1.0, ascii), format_pct(from_low))),
+            ],
+        ));
+    }
+    if let Some(sparkline) = app.recent_prices.sparkline(&app.stats.symbol, ascii) {
+        range_line.push(Segment::new(SPARKLINE, " ", vec![Span::styled(sparkline, Style::default().fg(theme.accent))]));
+    }
+    let mut range_line = fit_line(range_line, room);
+    if app.stats.rolling_24h && !range_line.spans.is_empty() {
+        range_line.spans.insert(0, Span::styled("24h ", theme.dim_style()));
+    }
+
+    vec![fit_line(price_line, room), range_line]
+}
+
+/// The settings menu's name for `chart_type`.
+fn chart_type_label(chart_type: &str) -> &'static str {
+    match chart_type {
+        "candle" => "Candle",
+        "heikin-ashi" => "Heikin-Ashi",
+        _ => "Line",
+    }
+}
+
+fn draw_chart(f: &mut Frame, app: &mut App, area: Rect, theme: &Theme, ascii: bool) {
+    // A past day is named by its date instead of the range
+    let range = app.date.as_ref().map_or(app.timeframe.clone(), |day| format!("{} {}", day.weekday(), day.date));
+    let chart_title = if area.width < COMPACT_WIDTH {
+        format!("{} {}", range, if app.price_view { "$" } else { "%" })
+    } else {
+        format!("{} {} ({})", range, if app.price_view { "Price" } else { "% Change" }, app.interval)
+    };
+    // HA closes and smoothed lines aren't traded prices, so say so in every width
+    let chart_title = match app.chart_type.as_str() {
+        "heikin-ashi" => format!("{} Heikin-Ashi", chart_title),
+        "line" if app.smoothing > 0 => {
+            format!("{} smoothed {}{}", chart_title, if ascii { "x" } else { "×" }, app.smoothing)
+        }
+        _ => chart_title,
+    };
+    let mut title = Vec::new();
+    if app.fullscreen && !app.stats.symbol.is_empty() {
+        title.push(Span::styled(format!("{} ", app.stats.symbol), Style::default().bold()));
+        title.push(Span::raw(format!("{} ", app.formatter.money(app.stats.price, &app.stats.currency))));
+        let (change, pct_change) = app.change_from_baseline();
+        title.push(Span::styled(
+            format!("{}{}", change_arrow(change, ascii), format_pct(pct_change)),
+            theme.change_style(change),
+        ));
+        if app.vs_open {
+            title.push(Span::styled(" vs open", theme.dim_style()));
+        }
+        let dot = if ascii { "|" } else { "·" };
+        let next = match (app.source.replay(), &app.date) {
+            (None, None) => format!(" {} {dot}", app.countdown_label()),
+            _ => String::new(),
+        };
+        title.push(Span::styled(format!(" {dot}{next} "), theme.dim_style()));
+    }
+    title.push(Span::styled(chart_title, Style::default().fg(theme.axis)));
+    let mut image_block = bordered(theme, ascii).title(Line::from(title));
+    if let Some(extremes) = extremes_label(&app.stats, ascii, &app.formatter)
+        && area.width >= COMPACT_WIDTH
+    {
+        image_block = image_block.title(Line::from(Span::styled(extremes, theme.dim_style())).right_aligned());
+    }
+    if let Some(replay) = app.source.replay() {
+        image_block = image_block.title_bottom(Line::from(Span::styled(replay.label(), Style::default().fg(theme.accent))));
+    }
+    // With a quote already showing, the spinner keeps out of its way
+    let spinner = app.spinner_label(ascii);
+    if let Some(label) = &spinner
+        && !app.stats.symbol.is_empty()
+    {
+        image_block = image_block.title_bottom(Line::from(Span::styled(format!(" {} ", label), theme.dim_style())));
+    }
+    let mut inner_image_area = image_block.inner(area);
+    f.render_widget(image_block, area);
+
+    if let Some(since) = app.possible_halt()
+        && inner_image_area.height > 1
+    {
+        let dash = if ascii { "-" } else { "—" };
+        let banner = Paragraph::new(format!("possible halt {dash} no trades since {} ET", since))
+            .alignment(Alignment::Center)
+            .style(theme.warn_style().add_modifier(Modifier::REVERSED | Modifier::BOLD));
+        f.render_widget(banner, Rect { height: 1, ..inner_image_area });
+        inner_image_area.y += 1;
+        inner_image_area.height -= 1;
+    }
+    
+    // Capture size for resizing logic
+    let new_size = (inner_image_area.width, inner_image_area.height);
+    if new_size != app.current_image_area_size {
+        app.current_image_area_size = new_size;
+        app.last_size_change_time = app.clock.now_instant();
+    }
+
+    if let Some(error) = &app.fetch_failure {
+        draw_fetch_failure(f, &app.ticker, error, inner_image_area, theme, ascii);
+    } else if let Some(label) = spinner
+        && app.stats.symbol.is_empty()
+    {
+        let y = inner_image_area.y + inner_image_area.height / 2;
+        let message = Paragraph::new(Span::styled(label, theme.dim_style())).alignment(Alignment::Center);
+        f.render_widget(message, Rect { y, height: inner_image_area.height.min(1), ..inner_image_area });
+    } else if app.billboard {
+        draw_billboard(f, app, inner_image_area, theme, ascii);
+    } else if app.stats.no_trades && app.stats.error.is_none() {
+        let y = inner_image_area.y + inner_image_area.height / 2;
+        let message = Paragraph::new(Span::styled("No trades yet", theme.dim_style())).alignment(Alignment::Center);
+        f.render_widget(message, Rect { y, height: inner_image_area.height.min(1), ..inner_image_area });
+    } else if let Some(protocol) = &mut app.image_protocol {
+        let image_widget = StatefulImage::default();
+        f.render_stateful_widget(image_widget, inner_image_area, protocol);
+    }
+}
+
+/// Why the chart has nothing to show after every attempt failed, and the
+/// keys that get it going again.
+fn draw_fetch_failure(f: &mut Frame, symbol: &str, error: &FetchError, area: Rect, theme: &Theme, ascii: bool) {
+    let dot = if ascii { "|" } else { "·" };
+    let lines = vec![
+        Line::from(Span::styled(format!("Couldn't fetch {}", symbol), Style::default().fg(theme.down).bold())),
+        Line::from(error.to_string()),
+        Line::default(),
+        Line::from(vec![
+            Span::styled(keymap::label_for(Action::Refresh), Style::default().bold()),
+            Span::styled(format!(" retry {dot} "), theme.dim_style()),
+            Span::styled(keymap::label_for(Action::OpenTicker), Style::default().bold()),
+            Span::styled(" change ticker", theme.dim_style()),
+        ]),
+    ];
+    let message = Paragraph::new(lines).alignment(Alignment::Center).wrap(Wrap { trim: true });
+    // Centered as if the reason fits on one line; a long one wraps below
+    let top = area.height.saturating_sub(4) / 2;
+    f.render_widget(message, Rect { y: area.y + top, height: area.height - top, ..area });
+}
+
+/// The price in block digits filling `area`, colored by direction, with the
+/// symbol and change in normal text underneath. When even the smallest digits
+/// don't fit, the price is written in normal text instead.
+fn draw_billboard(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    if app.stats.symbol.is_empty() {
+        return;
+    }
+    let (change, pct_change) = app.change_from_baseline();
+    let style = theme.change_style(change);
+    let mut caption = vec![
+        Span::styled(format!("{} ", app.stats.symbol), Style::default().bold()),
+        Span::styled(format!("{}{}", change_arrow(change, ascii), format_pct(pct_change)), style),
+        Span::styled(format!(" ({})", app.formatter.change(change, app.stats.price)), style),
+    ];
+    if app.vs_open {
+        caption.push(Span::styled(" vs open", theme.dim_style()));
+    }
+    let price = app.formatter.price(app.stats.price);
+    // A blank row and the caption go under the digits
+    let mut lines: Vec<Line> = match billboard::render(&price, area.width, area.height.saturating_sub(2), ascii) {
+        Some(rows) => rows.into_iter().map(|row| Line::from(Span::styled(row, style))).collect(),
+        None => vec![Line::from(Span::styled(app.formatter.money(app.stats.price, &app.stats.currency), style.bold()))],
+    };
+    lines.push(Line::default());
+    lines.push(Line::from(caption));
+    let height = (lines.len() as u16).min(area.height);
+    let y = area.y + (area.height - height) / 2;
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), Rect { y, height, ..area });
+}
+
+/// The day's low to high as a bar, with the open as a tick and the price as
+/// a marker in the change's color. The high and low are the header's, so
+/// pre-market prints count only when the chart shows them. Before there's
+/// any range, the marker sits in the middle.
+fn draw_range_gauge(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    let (low, high, open, price) = (app.stats.low, app.stats.high, app.stats.open, app.stats.price);
+    let low_label = format!(" L {} ", app.formatter.price(low));
+    let high_label = format!(" H {} ", app.formatter.price(high));
+    let width = (area.width as usize).saturating_sub(low_label.chars().count() + high_label.chars().count());
+    if width < 3 || low <= 0.0 || high <= 0.0 {
+        return;
+    }
+    let ranged = high > low;
+    let column = |value: f64| ((value - low) / (high - low)).clamp(0.0, 1.0) * (width - 1) as f64;
+    let marker = if ranged { column(price).round() as usize } else { width / 2 };
+    let tick = (ranged && open > 0.0).then(|| column(open).round() as usize);
+    let (line, tick_char, marker_char) = if ascii { ('-', '|', 'o') } else { ('─', '┃', '●') };
+
+    let (change, _) = app.change_from_baseline();
+    let mut spans = vec![Span::styled(low_label, theme.dim_style())];
+    let before: String = (0..marker).map(|i| if Some(i) == tick { tick_char } else { line }).collect();
+    let after: String = (marker + 1..width).map(|i| if Some(i) == tick { tick_char } else { line }).collect();
+    spans.push(Span::styled(before, theme.dim_style()));
+    spans.push(Span::styled(marker_char.to_string(), theme.change_style(change)));
+    spans.push(Span::styled(after, theme.dim_style()));
+    spans.push(Span::styled(high_label, theme.dim_style()));
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// The session statistics line under the chart, e.g.
+/// "VWAP $231.12 · Range 3.45 (1.52%) · σ 0.041%/1m · Bars 214/231".
+fn draw_footer(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    let Some(session) = &app.session_stats else {
+        let message = Span::styled(" Session statistics are for intraday 1d charts", theme.dim_style());
+        f.render_widget(Paragraph::new(message), area);
+        return;
+    };
+    let dot = if ascii { " | " } else { " · " };
+    let label = |text: &str| Span::styled(format!("{} ", text), theme.dim_style());
+    let mut spans = vec![Span::raw(" ")];
+    if let Some(vwap) = session.vwap {
+        spans.push(label("VWAP"));
+        spans.push(Span::raw(app.formatter.money(vwap, &app.stats.currency)));
+        spans.push(Span::styled(dot, theme.dim_style()));
+    }
+    spans.push(label("Range"));
+    spans.push(Span::raw(format!("{} ({})", app.formatter.price(session.range), format_pct(session.range_pct))));
+    if let Some(volatility) = session.volatility {
+        spans.push(Span::styled(dot, theme.dim_style()));
+        spans.push(label(if ascii { "sd" } else { "σ" }));
+        spans.push(Span::raw(format!("{:.3}%/{}", volatility, app.interval)));
+        if app.annualize_volatility
+            && let Some(annualized) = session.annualized
+        {
+            spans.push(Span::raw(format!(" (ann. {:.1}%)", annualized)));
+        }
+    }
+    if let Some((received, expected)) = session.bars {
+        spans.push(Span::styled(dot, theme.dim_style()));
+        spans.push(label("Bars"));
+        spans.push(Span::raw(format!("{}/{}", received, expected)));
+    }
+    if let Some(up_pct) = session.up_pct {
+        // Mostly rising bars is a grind up, mostly falling a grind down;
+        // an even split is chop
+        let style = if up_pct == 50.0 { Style::default() } else { Style::default().fg(theme.change_color(up_pct - 50.0)) };
+        spans.push(Span::styled(dot, theme.dim_style()));
+        spans.push(Span::styled(format!("up {:.0}% of bars", up_pct), style));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Keys named by the hint line, with what they do and how long they stay
+/// as the line narrows (see `draw_hint_line`).
+const HINT_KEYS: [(Action, &str, u8); 4] = [
+    (Action::ShowHelp, "help", 9),
+    (Action::Quit, "quit", 5),
+    (Action::OpenTicker, "ticker", 2),
+    (Action::OpenSettings, "settings", 1),
+];
+
+/// Where the quotes come from and how often, then the keys to start with,
+/// so a screenshot explains itself. A narrow terminal loses the later keys
+/// and the refresh before the source and the help key.
+fn draw_hint_line(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    let dot = if ascii { " | " } else { " · " };
+    let mut segments = vec![];
+    match app.source.replay() {
+        Some(_) => segments.push(Segment::new(8, " ", vec![Span::raw("src: replay")])),
+        None => {
+            segments.push(Segment::new(8, " ", vec![Span::raw(format!("src: {}", app.provider.name()))]));
+            segments.push(Segment::new(6, dot, vec![Span::raw(format!("{} bars", app.interval))]));
+            let refresh = match app.date {
+                Some(_) => "no refresh".to_string(),
+                None => format!("refresh {}s", app.refresh_interval.as_secs()),
+            };
+            segments.push(Segment::new(4, dot, vec![Span::raw(refresh)]));
+        }
+    }
+    for (i, (action, what, priority)) in HINT_KEYS.into_iter().enumerate() {
+        // Just the first of several keys, e.g. "q" of "q / Esc"
+        let key = keymap::label_for(action).split(" / ").next().unwrap_or_default().replace(" + ", "+");
+        let spans = vec![Span::styled(key, Style::default().bold()), Span::raw(format!(":{}", what))];
+        segments.push(Segment::new(priority, if i == 0 { dot } else { " " }, spans));
+    }
+    // One column of margin, like the footer
+    let mut line = fit_line(segments, (area.width as usize).saturating_sub(1));
+    line.spans.insert(0, Span::raw(" "));
+    f.render_widget(Paragraph::new(line).style(theme.dim_style()), area);
+}
+
+/// Where the day's high and low printed, e.g. "H 232.10 @ 10:04 · L 227.95 @ 13:42".
+/// Nothing when either is unknown, rather than a 0.
+fn extremes_label(stats: &StockStats, ascii: bool, fmt: &Formatter) -> Option<String> {
+    if stats.high_at.is_empty() || stats.low_at.is_empty() || stats.high <= 0.0 || stats.low <= 0.0 {
+        return None;
+    }
+    let dot = if ascii { "|" } else { "·" };
+    Some(format!(
+        " H {} @ {} {dot} L {} @ {} ",
+        fmt.price(stats.high),
+        stats.high_at,
+        fmt.price(stats.low),
+        stats.low_at
+    ))
+}
+
+/// The grid dashboard: one bordered percent chart per symbol, titled with its
+/// quote. The focused cell's border is highlighted.
+fn draw_grid(f: &mut Frame, app: &mut App, area: Rect, theme: &Theme, ascii: bool) {
+    let Some(grid) = app.grid.as_mut() else { return };
+    for (i, cell_area) in grid.cell_areas(area).into_iter().enumerate() {
+        let focused = i == grid.focus;
+        let cell = &mut grid.cells[i];
+        let stats = &cell.stats;
+        let mut title = vec![Span::styled(format!("{} ", cell.symbol), cell.accent.style.bold())];
+        if let Some(err) = &stats.error {
+            title.push(Span::styled(format!("Error: {}", err), Style::default().fg(theme.down)));
+        } else if stats.symbol.is_empty() {
+            title.push(Span::styled("Loading...", theme.dim_style()));
+        } else {
+            title.push(Span::raw(format!("{} ", app.formatter.money(stats.price, &stats.currency))));
+            title.push(Span::styled(
+                format!("{}{}", change_arrow(stats.change, ascii), format_pct(stats.pct_change)),
+                color_for_change(stats.pct_change, theme),
+            ));
+            if let Some(v) = &cell.versus {
+                let readout = if ascii {
+                    format!(" beta {:.1} rho {:.2}", v.beta, v.correlation)
+                } else {
+                    format!(" β {:.1} · ρ {:.2}", v.beta, v.correlation)
+                };
+                title.push(Span::styled(readout, theme.dim_style()));
+            }
+        }
+
+        let mut block = bordered(theme, ascii).title(Line::from(title));
+        if focused {
+            block = block.border_style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+        }
+        let inner = block.inner(cell_area);
+        f.render_widget(block, cell_area);
+
+        let size = (inner.width, inner.height);
+        if size != cell.area_size {
+            cell.area_size = size;
+            app.last_size_change_time = app.clock.now_instant();
+        }
+        if let Some(protocol) = &mut cell.image_protocol {
+            f.render_stateful_widget(StatefulImage::default(), inner, protocol);
+        }
+    }
+}
+
+/// Fetch latency and data freshness, in the top-right corner of the chart.
+fn draw_debug(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    let timings = &app.fetch_timings;
+    let latency = match (timings.latencies.back(), timings.summary()) {
+        (Some(last), Some((min, avg, max))) => format!(
+            "{} ms (min {} / avg {} / max {})",
+            last.as_millis(),
+            min.as_millis(),
+            avg.as_millis(),
+            max.as_millis()
+        ),
+        _ => "--".to_string(),
+    };
+    let last_ok = timings
+        .last_success
+        .map_or_else(|| "never".to_string(), |at| format!("{}s ago", app.clock.elapsed(at).as_secs()));
+    let points = app.stats.series.as_ref().map_or(0, Series::len);
+    let mut lines = vec![
+        Line::from(format!("Fetch:   {}", latency)),
+        Line::from(format!("Last OK: {}", last_ok)),
+        Line::from(format!("Points:  {} ({} null dropped)", points, app.stats.dropped)),
+        Line::from(match &app.date {
+            Some(day) => format!("Refresh: off, showing {}", day.date),
+            None => format!(
+                "Refresh: every {}s, next in {}{}",
+                app.refresh_interval.as_secs(),
+                app.countdown_label(),
+                if app.low_power { " (low power)" } else { "" }
+            ),
+        }),
+    ];
+    if let Some(live) = app.stats.live_price
+        && app.stats.price > 0.0
+    {
+        let gap_pct = (live - app.stats.price) / app.stats.price * 100.0;
+        let style = if gap_pct.abs() > LIVE_PRICE_GAP_PCT { theme.warn_style() } else { Style::default() };
+        lines.push(Line::styled(
+            format!(
+                "Live:    {} vs bar {} ({:+.2}%)",
+                app.formatter.price(live),
+                app.formatter.price(app.stats.price),
+                gap_pct
+            ),
+            style,
+        ));
+    }
+    if app.cross_check_tolerance.is_some() {
+        let check = app.cross_check.as_ref().filter(|check| check.symbol == app.stats.symbol);
+        let text = match check.map(|check| (check, &check.theirs)) {
+            None => "not yet".to_string(),
+            Some((check, Ok(theirs))) => format!(
+                "{} vs chart {} ({:+.2}%), {}s ago",
+                app.formatter.price(*theirs),
+                app.formatter.price(check.ours),
+                check.gap_pct().unwrap_or(0.0),
+                app.clock.elapsed(check.at).as_secs()
+            ),
+            Some((check, Err(e))) => format!("failed {}s ago: {}", app.clock.elapsed(check.at).as_secs(), e),
+        };
+        let style = if app.providers_disagree().is_some() { theme.warn_style() } else { Style::default() };
+        lines.push(Line::styled(format!("Check:   {}", text), style));
+    }
+
+    let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2).min(area.width.saturating_sub(2));
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let rect = Rect {
+        x: (area.right().saturating_sub(width + 1)).max(area.x),
+        y: area.y + 1,
+        width,
+        height,
+    };
+    f.render_widget(Clear, rect);
+    f.render_widget(Paragraph::new(lines).block(bordered(theme, ascii).title("Debug")), rect);
+}
+
+/// The newest toasts, newest at the bottom, in the bottom-right corner of
+/// `area` above everything else.
+fn draw_toasts(f: &mut Frame, app: &App, area: Rect, theme: &Theme, ascii: bool) {
+    let shown: Vec<&Toast> = app.toasts.iter().rev().take(SHOWN_TOASTS).rev().collect();
+    if shown.is_empty() || area.width < 4 || area.height < 3 {
+        return;
+    }
+    let lines: Vec<Line> = shown
+        .iter()
+        .map(|toast| {
+            let style = match toast.level {
+                ToastLevel::Info => Style::default(),
+                ToastLevel::Warn => theme.warn_style(),
+                ToastLevel::Error => Style::default().fg(theme.down),
+            };
+            Line::styled(toast.text.clone(), style)
+        })
+        .collect();
+    let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2).min(area.width.saturating_sub(2));
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let rect = Rect {
+        x: (area.right().saturating_sub(width + 1)).max(area.x),
+        y: area.bottom().saturating_sub(height),
+        width,
+        height,
+    };
+    let border = match shown.iter().map(|toast| toast.level).max() {
+        Some(ToastLevel::Error) => Style::default().fg(theme.down),
+        Some(ToastLevel::Warn) => theme.warn_style(),
+        _ => theme.dim_style(),
+    };
+    f.render_widget(Clear, rect);
+    f.render_widget(Paragraph::new(lines).block(bordered(theme, ascii).border_style(border)), rect);
+}
+
+fn help_lines(theme: &Theme) -> Vec<Line<'static>> {
+    let heading = Style::default().fg(theme.accent).bold();
+    let mut lines = Vec::new();
+    for section in SECTIONS {
+        lines.push(Line::from(Span::styled(section, heading)));
+        for binding in KEYMAP.iter().filter(|b| b.section == section) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<12}", binding.label), Style::default().bold()),
+                Span::raw(binding.description),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+    // Popup and grid keys are handled per view rather than through the keymap
+    let local_keys: [(&str, &[(&str, &str)]); 2] = [
+        ("In popups", &[("Up / Down", "Move selection"), ("Enter", "Select"), ("Esc", "Back / close")]),
+        ("In the grid", &[("Arrows", "Move focus"), ("Enter", "Open the focused symbol")]),
+    ];
+    for (heading_text, keys) in local_keys.iter() {
+        lines.push(Line::from(Span::styled(*heading_text, heading)));
+        for (keys, description) in keys.iter() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<12}", keys), Style::default().bold()),
+                Span::raw(*description),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled("Commands (: then Tab to complete)", heading)));
+    for (name, args, description) in COMMANDS {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  :{:<18}", format!("{} {}", name, args).trim_end()), Style::default().bold()),
+            Span::raw(*description),
+        ]));
+    }
+    lines
+}
+
+fn draw_help(f: &mut Frame, app: &mut App, theme: &Theme, ascii: bool) {
+    let popup_area = centered_rect(70, 80, f.area());
+    f.render_widget(Clear, popup_area);
+
+    let lines = help_lines(theme);
+    let visible = popup_area.height.saturating_sub(2);
+    let max_scroll = (lines.len() as u16).saturating_sub(visible);
+    app.help_scroll = app.help_scroll.min(max_scroll);
+
+    let title = if max_scroll > 0 { "Help (Up/Down to scroll)" } else { "Help" };
+    let help = Paragraph::new(lines)
+        .block(bordered(theme, ascii).title(title))
+        .scroll((app.help_scroll, 0));
+    f.render_widget(help, popup_area);
+}
+
+/// Recently viewed symbols with their last known quote.
+fn draw_history(f: &mut Frame, app: &mut App, theme: &Theme, ascii: bool) {
+    let popup_area = centered_rect(40, 50, f.area());
+    f.render_widget(Clear, popup_area);
+    let items: Vec<ListItem> = app
+        .history
+        .recent()
+        .into_iter()
+        .map(|symbol| {
+            let mut spans = vec![Span::styled(format!("{: <10}", symbol), Style::default().bold())];
+            if let Some(cached) = app.quote_cache.get(symbol) {
+                let stats = &cached.stats;
+                spans.push(Span::raw(format!("{} ", app.formatter.money(stats.price, &stats.currency))));
+                spans.push(Span::styled(
+                    format!("{}{}", change_arrow(stats.change, ascii), format_pct(stats.pct_change)),
+                    color_for_change(stats.pct_change, theme),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(bordered(theme, ascii).title("Recent Symbols"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, popup_area, &mut app.history_state);
+}
+
+/// Today's triggered alerts from the alert log, newest first.
+fn draw_alert_log(f: &mut Frame, app: &mut App, theme: &Theme, ascii: bool) {
+    let popup_area = centered_rect(70, 60, f.area());
+    f.render_widget(Clear, popup_area);
+    let block = bordered(theme, ascii).title(format!("Alerts today ({})", app.alert_log.len()));
+    if app.alert_log.is_empty() {
+        let empty = Paragraph::new(Span::styled("No alerts have triggered today", theme.dim_style())).block(block);
+        f.render_widget(empty, popup_area);
+        return;
+    }
+    let items: Vec<ListItem> = app
+        .alert_log
+        .iter()
+        .map(|trigger| {
+            let at = UNIX_EPOCH + Duration::from_secs(trigger.at);
+            let clock = match trigger.utc_offset {
+                Some(offset) => format!("{} ET ", format_clock(at, offset)),
+                None => format!("{} UTC ", format_clock(at, 0)),
+            };
+            ListItem::new(Line::from(vec![Span::styled(clock, theme.dim_style()), Span::raw(trigger.message.clone())]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, popup_area, &mut app.alert_log_state);
+}
+
+fn draw_news(f: &mut Frame, app: &mut App, theme: &Theme, ascii: bool) {
+    let popup_area = centered_rect(70, 60, f.area());
+    f.render_widget(Clear, popup_area);
+    let block = bordered(theme, ascii).title(format!("News: {}", app.ticker));
+    let known = app.news.get(&app.ticker);
+    let headlines = known.map(|known| known.headlines.as_slice()).unwrap_or_default();
+    if headlines.is_empty() {
+        let message = match known {
+            _ if app.news_pending.as_deref() == Some(app.ticker.as_str()) => "Loading news...".to_string(),
+            Some(KnownNews { error: Some(e), .. }) => format!("Couldn't fetch news: {}", e),
+            Some(_) => "No recent news".to_string(),
+            None => "Loading news...".to_string(),
+        };
+        let empty = Paragraph::new(Span::styled(message, theme.dim_style())).block(block);
+        f.render_widget(empty, popup_area);
+        return;
+    }
+    let now = app.clock.unix_secs() as i64;
+    let items: Vec<ListItem> = headlines
+        .iter()
+        .map(|headline| {
+            let mut source = headline.publisher.clone();
+            if let Some(published) = headline.published {
+                let age = format_age(Duration::from_secs(now.saturating_sub(published).max(0) as u64));
+                source = if source.is_empty() { format!("{} ago", age) } else { format!("{} · {} ago", source, age) };
+            }
+            ListItem::new(vec![
+                Line::from(headline.title.clone()),
+                Line::from(Span::styled(format!("  {}", source), theme.dim_style())),
+            ])
+        })
+        .collect();
+    let mut block = block;
+    if let Some(KnownNews { error: Some(e), .. }) = known {
+        block = block.title_bottom(Span::styled(format!(" Refresh failed: {} ", e), theme.dim_style()));
+    }
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, popup_area, &mut app.news_state);
+}
+
+fn draw_levels(f: &mut Frame, app: &mut App, theme: &Theme, ascii: bool) {
+    let popup_area = centered_rect(50, 50, f.area());
+    f.render_widget(Clear, popup_area);
+    let block = bordered(theme, ascii)
+        .title(format!("Levels: {}", app.ticker))
+        .title_bottom(Span::styled(" a: mark current price  d: remove ", theme.dim_style()));
+    let levels = app.levels.get(&app.ticker);
+    if levels.is_empty() {
+        let empty = Paragraph::new(vec![
+            Line::from(Span::styled("No levels marked", theme.dim_style())),
+            Line::from(Span::styled("Add one with :level 225.50 buy-zone", theme.dim_style())),
+        ])
+        .block(block);
+        f.render_widget(empty, popup_area);
+        return;
+    }
+    let items: Vec<ListItem> = levels
+        .iter()
+        .map(|level| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:>12} ", app.formatter.money(level.price, &app.stats.currency))),
+                Span::styled(level.label.clone(), theme.dim_style()),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, popup_area, &mut app.levels_state);
+}
+
+fn draw_portfolio(f: &mut Frame, app: &mut App, theme: &Theme, ascii: bool) {
+    let popup_area = centered_rect(90, 80, f.area());
+    f.render_widget(Clear, popup_area);
+    let rows = app.portfolio_rows();
+    let totals = portfolio::totals(&rows);
+    let fmt = &app.formatter;
+    let signed = |value: f64, currency: &str| {
+        format!("{}{}", if value < 0.0 { "-" } else { "+" }, fmt.money(value.abs(), currency))
+    };
+    let signed_pct = |pct: f64| format!("{}{}", if pct < 0.0 { "" } else { "+" }, format_pct(pct));
+    let colored = |text: String, value: f64| Cell::from(text).style(Style::default().fg(theme.change_color(value)));
+    let blank = || Cell::from("");
+    let accounts = app.portfolio.holdings.iter().any(|h| h.account.is_some());
+
+    // Each symbol's accent, as in the grid
+    let marker = if ascii { "|" } else { "▌" };
+    let mut header = vec!["", "Symbol"];
+    if accounts {
+        header.push("Account");
+    }
+    header.extend(["Shares", "Price", "Day", "Day P/L", "Value", "P/L", "P/L %", "Weight"]);
+    let header = Row::new(header).style(Style::default().add_modifier(Modifier::BOLD));
+
+    // By symbol, so a holding kept in two accounts has one color
+    let mut symbols: Vec<String> = rows.iter().map(|row| row.symbol.clone()).collect();
+    symbols.sort();
+    symbols.dedup();
+    let accents: HashMap<String, Style> = symbols
+        .iter()
+        .cloned()
+        .zip(theme.accents.for_symbols(&symbols, &theme.chart.line).into_iter().map(|accent| accent.style))
+        .collect();
+    let mut table_rows: Vec<Row> = rows
+        .iter()
+        .map(|row| {
+            let accent = accents.get(&row.symbol).copied().unwrap_or_default();
+            let mut cells = vec![Cell::from(marker).style(accent), Cell::from(row.symbol.clone())];
+            if accounts {
+                cells.push(Cell::from(row.account.clone().unwrap_or_default()));
+            }
+            cells.push(row.shares.map_or_else(blank, |shares| Cell::from(shares.to_string())));
+            cells.push(row.price.map_or_else(blank, |price| Cell::from(fmt.money(price, &row.quote_currency))));
+            cells.push(row.day_pct.map_or_else(blank, |pct| Cell::from(signed_pct(pct)).style(color_for_change(pct, theme))));
+            cells.push(row.day_change.map_or_else(blank, |change| colored(signed(change, &row.currency), change)));
+            cells.push(row.value.map_or_else(blank, |value| Cell::from(fmt.money(value, &row.currency))));
+            cells.push(row.pl.map_or_else(blank, |pl| colored(signed(pl, &row.currency), pl)));
+            cells.push(row.pl_pct.map_or_else(blank, |pct| colored(signed_pct(pct), pct)));
+            cells.push(row.weight.map_or_else(blank, |weight| Cell::from(format_pct(weight))));
+            Row::new(cells)
+        })
+        .collect();
+    // One totals row per currency, so mixed currencies are never summed
+    for total in &totals {
+        let label = match totals.len() {
+            1 => "Total".to_string(),
+            _ => format!("Total {}", if total.currency.is_empty() { "USD" } else { &total.currency }),
+        };
+        let mut cells = vec![blank(), Cell::from(label)];
+        if accounts {
+            cells.push(blank());
+        }
+        cells.extend([blank(), blank(), blank()]);
+        cells.push(colored(signed(total.day_change, &total.currency), total.day_change));
+        cells.push(Cell::from(fmt.money(total.value, &total.currency)));
+        cells.push(colored(signed(total.pl, &total.currency), total.pl));
+        cells.push(total.pl_pct.map_or_else(blank, |pct| colored(signed_pct(pct), pct)));
+        cells.push(Cell::from(format_pct(100.0)));
+        table_rows.push(Row::new(cells).style(Style::default().add_modifier(Modifier::BOLD)));
+    }
+
+    let mut widths = vec![Constraint::Length(1), Constraint::Length(10)];
+    if accounts {
+        widths.push(Constraint::Length(10));
+    }
+    widths.extend([
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(13),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(9),
+        Constraint::Length(8),
+    ]);
+
+    let mut block = bordered(theme, ascii)
+        .title(format!("Portfolio (sorted by {}, s to change)", app.portfolio_sort.label()));
+    if app.portfolio_quotes.is_empty() && app.portfolio_busy {
+        block = block.title_bottom(Span::styled(" Loading quotes... ", theme.dim_style()));
+    } else if let Some(e) = &app.portfolio_error {
+        block = block.title_bottom(Span::styled(format!(" Refresh failed: {} ", e), theme.warn_style()));
+    } else if totals.len() > 1 {
+        let note = match app.home_currency.as_deref() {
+            Some(home) => format!(" Waiting for rates into {}: currencies are totalled apart ", home),
+            None => " Mixed currencies are totalled apart (--home-currency converts them) ".to_string(),
+        };
+        block = block.title_bottom(Span::styled(note, theme.dim_style()));
+    }
+    let table = Table::new(table_rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(table, popup_area, &mut app.portfolio_state);
+}
+
+/// Below this size the layout can't fit anything meaningful.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+/// Under this width the header drops labels and secondary stats.
+const COMPACT_WIDTH: u16 = 80;
+
+pub(super) fn ui(f: &mut Frame, app: &mut App) {
+    let theme = app.theme.clone();
+    let ascii = app.ascii;
+    let area = f.area();
+
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        let msg = Paragraph::new(vec![
+            Line::from("Terminal too small"),
+            Line::from(Span::styled(format!("need {}x{}", MIN_WIDTH, MIN_HEIGHT), theme.dim_style())),
+        ])
+        .alignment(Alignment::Center);
+        let y = area.height.saturating_sub(2) / 2;
+        f.render_widget(msg, Rect { y, height: area.height.min(2), ..area });
+        return;
+    }
+
+    let header_visible = app.show_header && !app.fullscreen && !app.grid_view;
+    let header_height = if header_visible { 4 } else { 0 };
+    let tape_visible = app.show_tape && !app.fullscreen && matches!(app.source, DataSource::Live { .. });
+    let gauge_visible = header_visible
+        && app.show_range_gauge
+        && app.stats.error.is_none()
+        && app.stats.price > 0.0
+        && !app.stats.no_trades;
+    let footer_visible = app.show_footer && !app.grid_view;
+    let hint_line_visible = app.show_hint_line && !app.fullscreen;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if tape_visible { 1 } else { 0 }),
+            Constraint::Length(header_height),
+            Constraint::Length(if gauge_visible { 1 } else { 0 }),
+            Constraint::Min(0),
+            Constraint::Length(if footer_visible { 1 } else { 0 }),
+            Constraint::Length(if hint_line_visible { 1 } else { 0 }),
+        ])
+        .split(area);
+
+    if tape_visible {
+        draw_tape(f, app, chunks[0], &theme, ascii);
+    }
+    if header_visible {
+        draw_header(f, app, chunks[1], &theme, ascii);
+    }
+    if gauge_visible {
+        draw_range_gauge(f, app, chunks[2], &theme, ascii);
+    }
+    if app.grid_view {
+        draw_grid(f, app, chunks[3], &theme, ascii);
+    } else {
+        draw_chart(f, app, chunks[3], &theme, ascii);
+    }
+    if footer_visible {
+        draw_footer(f, app, chunks[4], &theme, ascii);
+    }
+    if hint_line_visible {
+        draw_hint_line(f, app, chunks[5], &theme, ascii);
+    }
+    if app.show_debug {
+        draw_debug(f, app, chunks[3], &theme, ascii);
+    }
+
+    // Popup Logic
+    if app.input_mode == InputMode::Editing {
+        let popup_area = centered_rect(60, 50, f.area());
+        f.render_widget(Clear, popup_area); // clear background
+        
+        // Popup block with borders
+        let popup_block = bordered(&theme, ascii).title("Select Ticker");
+        f.render_widget(popup_block.clone(), popup_area);
+        
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(1)])
+            .margin(1) // margin inside the borders
+            .split(popup_area);
+
+        let input_block = bordered(&theme, ascii).title("Search");
+        let input_paragraph = Paragraph::new(app.input.as_str())
+            .style(Style::default().fg(theme.accent))
+            .block(input_block);
+        f.render_widget(input_paragraph, popup_layout[0]);
+
+        // Suggestions List
+        let items: Vec<ListItem> = app.filtered_tickers
+            .iter()
+            .map(|t| {
+                let mut spans = vec![
+                    Span::styled(format!("{: <6}", t.ticker), Style::default().bold()),
+                    Span::raw(format!(" {} ({})", t.name, t.kind)),
+                ];
+                if !t.exchange.is_empty() {
+                    spans.push(Span::styled(format!(" {}", t.exchange), theme.dim_style()));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        // Use a stateful widget for the list to handle selection highlighting
+        let list = List::new(items)
+            .block(bordered(&theme, ascii).title("Results"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        
+        f.render_stateful_widget(list, popup_layout[1], &mut app.list_state);
+        
+        // Ensure cursor is visible in input (optional, can be tricky with layout)
+    }
+
+    if app.input_mode == InputMode::SettingsMain {
+        let popup_area = centered_rect(50, 60, f.area());
+        f.render_widget(Clear, popup_area);
+
+        let popup_block = bordered(&theme, ascii).title("Settings");
+        f.render_widget(popup_block, popup_area);
+
+        let inner = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1)])
+            .margin(1)
+            .split(popup_area)[0];
+
+        let items: Vec<ListItem> = app.settings_items
+            .iter()
+            .enumerate()
+            .map(|(i, &label)| {
+                // Dynamic labels
+                let text = match i {
+                    1 => format!("Timeframe: {}", app.timeframe),
+                    2 => format!("Interval: {}", app.interval),
+                    3 => format!("View: {}", if app.price_view { "Price" } else { "% Change" }),
+                    4 => format!("Type: {}", chart_type_label(&app.chart_type)),
+                    5 => format!("Time: {}", if app.use_24h_time { "24h" } else { "12h" }),
+                    6 => format!("Header: {}", if app.show_header { "Show" } else { "Hide" }),
+                    _ => label.to_string(),
+                };
+                
+                ListItem::new(Line::from(text))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+
+        f.render_stateful_widget(list, inner, &mut app.settings_main_state);
+    }
+    
+    if app.input_mode == InputMode::SettingsIndicators {
+        let popup_area = centered_rect(50, 60, f.area());
+        f.render_widget(Clear, popup_area);
+
+        let popup_block = bordered(&theme, ascii).title("Indicators (* Requires Price)");
+        f.render_widget(popup_block, popup_area);
+
+        let inner = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1)])
+            .margin(1)
+            .split(popup_area)[0];
+
+        let mut items: Vec<ListItem> = app.available_indicators
+            .iter()
+            .map(|ind| {
+                let checkbox = if app.enabled_indicators.contains(&ind.name) {
+                    "[x] "
+                } else {
+                    "[ ] "
+                };
+                let suffix = if ind.requires_price { " (*)" } else { "" };
+                ListItem::new(Line::from(vec![
+                    Span::styled(checkbox, Style::default().fg(theme.up)),
+                    Span::raw(&ind.name),
+                    Span::styled(suffix, theme.dim_style().italic()),
+                ]))
+            })
+            .collect();
+            
+        // Add Back button
+        items.push(ListItem::new(Line::from(Span::styled("<< Back", Style::default().fg(theme.accent)))));
+
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+
+        f.render_stateful_widget(list, inner, &mut app.settings_ind_state);
+    }
+    
+    if app.input_mode == InputMode::SettingsTimeframe {
+        let popup_area = centered_rect(50, 60, f.area());
+        f.render_widget(Clear, popup_area);
+
+        let popup_block = bordered(&theme, ascii).title("Select Timeframe");
+        f.render_widget(popup_block, popup_area);
+
+        let inner = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1)])
+            .margin(1)
+            .split(popup_area)[0];
+
+        let items: Vec<ListItem> = app.available_timeframes
+            .iter()
+            .map(|tf| {
+                let prefix = if *tf == app.timeframe { "[*] " } else { "[ ] " };
+                ListItem::new(Line::from(vec![
+                    Span::styled(prefix, Style::default().fg(theme.up)),
+                    Span::raw(*tf),
+                ]))
+            })
+            .collect();
+            
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+
+        f.render_stateful_widget(list, inner, &mut app.settings_tf_state);
+    }
+
+    if app.input_mode == InputMode::Help {
+        draw_help(f, app, &theme, ascii);
+    }
+
+    if app.input_mode == InputMode::History {
+        draw_history(f, app, &theme, ascii);
+    }
+
+    if app.input_mode == InputMode::AlertLog {
+        draw_alert_log(f, app, &theme, ascii);
+    }
+
+    if app.input_mode == InputMode::News {
+        draw_news(f, app, &theme, ascii);
+    }
+
+    if app.input_mode == InputMode::Portfolio {
+        draw_portfolio(f, app, &theme, ascii);
+    }
+
+    if app.input_mode == InputMode::Levels {
+        draw_levels(f, app, &theme, ascii);
+    }
+
+    if app.input_mode == InputMode::Command {
+        let line_area = Rect { y: area.bottom().saturating_sub(1), height: 1, ..area };
+        f.render_widget(Clear, line_area);
+        let line = Line::from(vec![Span::styled(":", Style::default().fg(theme.accent)), Span::raw(app.command_input.as_str())]);
+        f.render_widget(Paragraph::new(line), line_area);
+        let cursor_x = line_area.x.saturating_add(1 + app.command_input.chars().count() as u16);
+        f.set_cursor_position((cursor_x.min(line_area.right().saturating_sub(1)), line_area.y));
+    }
+
+    if app.input_mode == InputMode::SettingsInterval {
+        let popup_area = centered_rect(50, 60, f.area());
+        f.render_widget(Clear, popup_area);
+
+        let popup_block = bordered(&theme, ascii).title("Select Interval");
+        f.render_widget(popup_block, popup_area);
+
+        let inner = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1)])
+            .margin(1)
+            .split(popup_area)[0];
+
+        let items: Vec<ListItem> = app.available_intervals
+            .iter()
+            .map(|intv| {
+                let prefix = if *intv == app.interval { "[*] " } else { "[ ] " };
+                // Ones the range doesn't allow are snapped when picked
+                let style = if interval_allowed(&app.timeframe, intv) { Style::default() } else { theme.dim_style() };
+                ListItem::new(Line::from(vec![
+                    Span::styled(prefix, Style::default().fg(theme.up)),
+                    Span::styled(*intv, style),
+                ]))
+            })
+            .collect();
+            
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+
+        f.render_stateful_widget(list, inner, &mut app.settings_int_state);
+    }
+
+    // Over any popup, so messages from its actions show
+    draw_toasts(f, app, chunks[3], &theme, ascii);
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, style::Color, Terminal};
+
+    use super::*;
+    use crate::app::tests::test_app;
+
+    /// BRK-A-sized figures: a six-figure price and range, and volume in the
+    /// hundreds of thousands.
+    fn brk_a() -> App {
+        let mut app = test_app();
+        app.ticker = "BRK-A".to_string();
+        app.stats = StockStats {
+            symbol: "BRK-A".to_string(),
+            price: 640_123.45,
+            open: 638_000.0,
+            high: 641_500.0,
+            low: 637_250.0,
+            volume: 312_456,
+            change: 2_100.5,
+            pct_change: 0.33,
+            currency: "USD".to_string(),
+            bid: Some(640_100.0),
+            ask: Some(640_150.0),
+            bid_size: Some(1),
+            ask_size: Some(2),
+            ..StockStats::default()
+        };
+        app
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    fn lines_at(app: &App, room: usize) -> Vec<String> {
+        let theme = Theme::by_name("default").unwrap();
+        let lines = quote_lines(app, room, room + 2 < COMPACT_WIDTH as usize, &theme, false);
+        for line in &lines {
+            assert!(line.width() <= room, "{:?} is {} wide", text(line), line.width());
+        }
+        lines.iter().map(text).collect()
+    }
+
+    #[test]
+    fn brk_a_fits_80_columns_whole() {
+        assert_eq!(
+            lines_at(&brk_a(), 78),
+            [
+                "Price: $640,123 B: 640,100×1 A: 640,150×2 S: 50 | Change: ▲ +2,101 (0.33%)",
+                "O: 638,000 | H: 641,500 | L: 637,250 | Vol: 312.5K",
+            ]
+        );
+    }
+
+    #[test]
+    fn narrow_header_drops_the_open_and_low_before_the_change() {
+        assert_eq!(lines_at(&brk_a(), 28), ["$640,123 ▲ +2,101 (0.33%)", "H: 641,500 V: 312.5K"]);
+    }
+
+    #[test]
+    fn price_outlasts_everything_else() {
+        assert_eq!(lines_at(&brk_a(), 20), ["$640,123", "H: 641,500 V: 312.5K"]);
+        // Too narrow for even the volume: it's kept, and cut off at the border
+        let theme = Theme::by_name("default").unwrap();
+        let lines = quote_lines(&brk_a(), 8, true, &theme, false);
+        assert_eq!(lines.iter().map(text).collect::<Vec<_>>(), ["$640,123", "V: 312.5K"]);
+    }
+
+    #[test]
+    fn index_and_futures_leave_out_volume_they_dont_report() {
+        let mut app = test_app();
+        // ^GSPC as the fetcher reports it: no volume, no bid or ask
+        let index = r#"{"symbol": "^GSPC", "price": 6812.4, "open": 6790.1, "high": 6820.75, "low": 6785.3, "volume": 0,
+            "change": 22.3, "pct_change": 0.33, "currency": "USD", "instrument_type": "INDEX"}"#;
+        app.stats = serde_json::from_str(index).unwrap();
+        assert_eq!(
+            lines_at(&app, 78),
+            [
+                "Price: $6,812.40 | Change: ▲ +22.30 (0.33%)",
+                "O: 6,790.10 | H: 6,820.75 | L: 6,785.30 | ▼0.12% from HOD · ▲0.40% from LOD",
+            ]
+        );
+
+        // ES=F trades nearly around the clock, and does report volume
+        let futures = r#"{"symbol": "ES=F", "price": 6830.25, "open": 6801.0, "high": 6835.5, "low": 6798.75, "volume": 1204311,
+            "change": -4.5, "pct_change": -0.07, "currency": "USD", "instrument_type": "FUTURE"}"#;
+        app.stats = serde_json::from_str(futures).unwrap();
+        assert_eq!(lines_at(&app, 78)[1], "O: 6,801.00 | H: 6,835.50 | L: 6,798.75 | Vol: 1.20M");
+    }
+
+    #[test]
+    fn disagreeing_providers_are_flagged_with_details_in_the_debug_overlay() {
+        let mut app = test_app();
+        app.cross_check_tolerance = Some(0.5);
+        app.stats = serde_json::from_str(r#"{"symbol": "AAPL", "price": 231.5, "change": 1.2, "pct_change": 0.52, "currency": "USD"}"#).unwrap();
+        app.apply_cross_check("AAPL".to_string(), Ok(233.1));
+        assert_eq!(lines_at(&app, 78)[0], "Price: $231.50 ⚠ providers disagree | Change: ▲ +1.20 (0.52%)");
+
+        app.show_debug = true;
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..30).flat_map(|y| (0..100).map(move |x| (x, y))).map(|at| buffer[at].symbol()).collect();
+        assert!(screen.contains("Check:   233.10 vs chart 231.50 (+0.69%), 0s ago"), "{}", screen);
+
+        app.apply_cross_check("AAPL".to_string(), Ok(231.6));
+        assert_eq!(lines_at(&app, 78)[0], "Price: $231.50 | Change: ▲ +1.20 (0.52%)");
+    }
+
+    #[test]
+    fn crypto_header_labels_its_rolling_24_hours() {
+        let mut app = test_app();
+        // BTC-USD as the fetcher reports it once it has a bar from 24 hours ago
+        let btc = r#"{"symbol": "BTC-USD", "price": 97250.0, "open": 95210.0, "high": 97800.0, "low": 94900.0, "volume": 31250000000,
+            "change": 2040.0, "pct_change": 2.14, "currency": "USD", "instrument_type": "CRYPTOCURRENCY", "rolling_24h": true}"#;
+        app.stats = serde_json::from_str(btc).unwrap();
+        assert_eq!(
+            lines_at(&app, 120),
+            [
+                "Price: $97,250 | 24h: ▲ +2,040 (2.14%)",
+                "24h O: 95,210 | H: 97,800 | L: 94,900 | Vol: 31.25B | ▼0.56% from 24h H · ▲2.48% from 24h L",
+            ]
+        );
+    }
+
+    #[test]
+    fn header_leaves_out_figures_the_payload_lacked() {
+        let mut app = test_app();
+        app.stats = serde_json::from_str(r#"{"symbol": "AAPL", "price": 231.5, "change": 1.2, "pct_change": 0.52, "volume": 41234567}"#).unwrap();
+        assert_eq!(lines_at(&app, 78), ["Price: $231.50 | Change: ▲ +1.20 (0.52%)", "Vol: 41.23M"]);
+    }
+
+    #[test]
+    fn header_renders_inside_its_border_at_80_columns() {
+        let mut app = brk_a();
+        app.symbol_info = ("Berkshire Hathaway Inc.".to_string(), "EQUITY".to_string());
+        let theme = Theme::by_name("default").unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(80, 4)).unwrap();
+        terminal.draw(|f| draw_header(f, &app, f.area(), &theme, false)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| (0..80).map(|x| buffer[(x, y)].symbol()).collect::<String>();
+        assert!(row(0).starts_with("┌Stock Stats: BRK-A | Berkshire Hathaway Inc. (EQUITY)"), "{}", row(0));
+        assert!(row(1).contains("Change: ▲ +2,101 (0.33%)"), "{}", row(1));
+        assert!(row(2).ends_with("Vol: 312.5K                            │"), "{}", row(2));
+    }
+
+    #[test]
+    fn halted_stock_says_no_trades_yet() {
+        let mut app = test_app();
+        // An earlier chart, which no longer shows a trade
+        app.image_protocol = Some(app.picker.new_resize_protocol(image::DynamicImage::new_rgb8(4, 4)));
+        // What the fetcher sends when every bar lacks a close
+        let halted = r#"{"symbol": "AAPL", "price": 231.5, "change": 0, "pct_change": 0, "currency": "USD", "no_trades": true}"#;
+        app.apply_stats(serde_json::from_str(halted).unwrap());
+        assert!(app.image_protocol.is_none());
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..30).flat_map(|y| (0..100).map(move |x| (x, y))).map(|at| buffer[at].symbol()).collect();
+        assert!(screen.contains("No trades yet"), "{}", screen);
+    }
+
+    /// Colors set anywhere on a 100×30 screen, as (x, y).
+    fn colored_cells(app: &mut App) -> Vec<(u16, u16)> {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..30)
+            .flat_map(|y| (0..100).map(move |x| (x, y)))
+            .filter(|at| buffer[*at].fg != Color::Reset || buffer[*at].bg != Color::Reset)
+            .collect()
+    }
+
+    #[test]
+    fn no_color_sets_no_colors() {
+        let mut app = brk_a();
+        app.theme = Theme::by_name("mono").unwrap();
+        app.stats.change = -2_100.5;
+        app.stats.pct_change = -0.33;
+        app.toast(ToastLevel::Warn, "Index strip fetch failed".to_string());
+        assert_eq!(colored_cells(&mut app), []);
+
+        app.input_mode = InputMode::Help;
+        assert_eq!(colored_cells(&mut app), []);
+
+        // The default theme does color the same screen
+        app.theme = Theme::by_name("default").unwrap();
+        assert!(!colored_cells(&mut app).is_empty());
+    }
+}
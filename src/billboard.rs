@@ -0,0 +1,60 @@
+use std::iter::repeat_n;
+
+/// Rows in every glyph, before scaling.
+const ROWS: usize = 5;
+
+/// The smallest block pattern of each character a price or change can hold;
+/// `#` is filled. None for anything else.
+fn glyph(c: char) -> Option<[&'static str; ROWS]> {
+    Some(match c {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        '.' => [" ", " ", " ", " ", "#"],
+        ',' => [" ", " ", " ", "#", "#"],
+        '-' => ["   ", "   ", "###", "   ", "   "],
+        '+' => ["   ", " # ", "###", " # ", "   "],
+        '%' => ["# #", "  #", " # ", "#  ", "# #"],
+        _ => return None,
+    })
+}
+
+/// `text` in block digits, one string per row, scaled up as far as fits in
+/// `width` x `height` cells. Each step up is twice as wide as it is tall,
+/// since terminal cells are about twice as tall as they are wide. None when
+/// a character has no glyph or even the smallest size doesn't fit.
+pub fn render(text: &str, width: u16, height: u16, ascii: bool) -> Option<Vec<String>> {
+    let glyphs = text.chars().map(glyph).collect::<Option<Vec<_>>>()?;
+    if glyphs.is_empty() {
+        return None;
+    }
+    // One blank column between glyphs
+    let natural = glyphs.iter().map(|g| g[0].len()).sum::<usize>() + glyphs.len() - 1;
+    let sx = (usize::from(width) / natural).min(usize::from(height) / ROWS * 2);
+    let sy = (usize::from(height) / ROWS).min(sx.div_ceil(2));
+    if sx == 0 || sy == 0 {
+        return None;
+    }
+    let fill = if ascii { '#' } else { '█' };
+    let mut lines = Vec::with_capacity(ROWS * sy);
+    for row in 0..ROWS {
+        let mut line = String::new();
+        for (i, g) in glyphs.iter().enumerate() {
+            if i > 0 {
+                line.extend(repeat_n(' ', sx));
+            }
+            for c in g[row].chars() {
+                line.extend(repeat_n(if c == '#' { fill } else { ' ' }, sx));
+            }
+        }
+        lines.extend(repeat_n(line, sy));
+    }
+    Some(lines)
+}
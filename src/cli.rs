@@ -0,0 +1,327 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::{builder::PossibleValuesParser, Parser, ValueEnum};
+
+use crate::{
+    alert::AlertRule,
+    format::{civil_from_days, days_from_civil},
+    symbol::normalize_symbol,
+    theme::THEME_NAMES,
+};
+
+pub const TIMEFRAMES: [&str; 11] = ["1d", "5d", "1mo", "3mo", "6mo", "1y", "2y", "5y", "10y", "ytd", "max"];
+
+/// Bar sizes Yahoo serves, finest first.
+pub const INTERVALS: [&str; 9] = ["1m", "2m", "5m", "15m", "1h", "1d", "1wk", "1mo", "3mo"];
+
+/// Where quotes and charts come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Provider {
+    /// Yahoo Finance, through the yfinance-based `fetch_stock.py`
+    #[default]
+    Yahoo,
+}
+
+impl Provider {
+    /// Script that implements the provider's fetch protocol.
+    pub fn script(self) -> &'static str {
+        match self {
+            Provider::Yahoo => "fetch_stock.py",
+        }
+    }
+
+    /// Name shown in the hint line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Yahoo => "yahoo",
+        }
+    }
+}
+
+/// How the chart image is drawn in the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Graphics {
+    /// Ask the terminal what it supports, falling back to half blocks
+    #[default]
+    Auto,
+    /// Full-resolution image (kitty, WezTerm, Ghostty)
+    Kitty,
+    /// Full-resolution image (foot, xterm -ti vt340, mlterm, ...)
+    Sixel,
+    /// Full-resolution image (iTerm2, WezTerm)
+    Iterm2,
+    /// Two pixels per cell with colored half blocks; works everywhere
+    Halfblocks,
+}
+
+/// Live stock quotes and charts in the terminal.
+#[derive(Debug, Parser)]
+#[command(name = "stock-tui", version, about)]
+pub struct Cli {
+    /// Symbols to open (e.g. TSLA, AMD, SPY); the first one is charted
+    #[arg(value_name = "TICKER", default_value = "AAPL", value_parser = normalize_symbol)]
+    pub tickers: Vec<String>,
+
+    /// Seconds between automatic refreshes
+    #[arg(long, value_name = "SECS", default_value_t = 60, value_parser = clap::value_parser!(u64).range(5..))]
+    pub refresh: u64,
+
+    /// Initial chart timeframe
+    #[arg(long, value_name = "RANGE", default_value = "1d", value_parser = PossibleValuesParser::new(TIMEFRAMES))]
+    pub range: String,
+
+    /// Chart a past trading day (YYYY-MM-DD) instead of today. Nothing is
+    /// refreshed; bars are as fine as Yahoo still keeps for that day
+    #[arg(long, value_name = "DATE", value_parser = PastDay::parse,
+          conflicts_with_all = ["range", "once", "export", "grid", "replay", "record", "stream", "daemon"])]
+    pub date: Option<PastDay>,
+
+    /// Include pre-market and after-hours trading
+    #[arg(long)]
+    pub pre_market: bool,
+
+    /// Color theme [default: from config, else "default"]
+    #[arg(long, value_name = "NAME", value_parser = PossibleValuesParser::new(THEME_NAMES))]
+    pub theme: Option<String>,
+
+    /// Use the mono theme whatever the theme setting (also when NO_COLOR is set)
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Quote provider
+    #[arg(long, value_enum, default_value_t)]
+    pub provider: Provider,
+
+    /// Config file to read instead of ~/.config/stock-tui/config.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Portfolio file to read instead of ~/.config/stock-tui/portfolio.toml
+    #[arg(long, value_name = "PATH")]
+    pub portfolio: Option<PathBuf>,
+
+    /// Watch the symbols in this file (one per line, `#` comments) instead
+    /// of the portfolio file's watchlist, starting in the portfolio table and
+    /// charting the first of them
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["stdin", "once", "export", "grid", "replay", "stream", "daemon"])]
+    pub watchlist: Option<PathBuf>,
+
+    /// Like --watchlist, reading the symbols from standard input
+    #[arg(long, conflicts_with_all = ["once", "export", "grid", "replay", "stream", "daemon"])]
+    pub stdin: bool,
+
+    /// Wake the UI about every 10 seconds instead of several times a second,
+    /// showing the refresh countdown and clock coarsely (toggle with w)
+    #[arg(long)]
+    pub low_power: bool,
+
+    /// Print the current quote and exit without starting the TUI
+    #[arg(long)]
+    pub once: bool,
+
+    /// With --once, print the quote as JSON
+    #[arg(long, requires = "once")]
+    pub json: bool,
+
+    /// Write the first ticker's chart data to a CSV file and exit. `{symbol}`,
+    /// `{range}` and `{date}` in PATH are expanded
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["once", "record", "replay"])]
+    pub export: Option<PathBuf>,
+
+    /// How to draw the chart: detected from the terminal, or a given
+    /// graphics protocol
+    #[arg(long, value_enum, value_name = "PROTOCOL", default_value_t)]
+    pub graphics: Graphics,
+
+    /// Draw borders and arrows with plain ASCII (automatic on non-UTF-8 locales)
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Disable desktop notifications
+    #[arg(long)]
+    pub no_notify: bool,
+
+    /// Don't ring the terminal bell when the header flashes
+    #[arg(long)]
+    pub no_bell: bool,
+
+    /// Don't set the terminal window title to the live quote
+    #[arg(long)]
+    pub no_title: bool,
+
+    /// Show the price in large digits instead of the chart (toggle with B)
+    #[arg(long)]
+    pub billboard: bool,
+
+    /// Also show the price converted to this currency, e.g. EUR [default:
+    /// from config, else none]
+    #[arg(long, value_name = "CODE", value_parser = parse_currency)]
+    pub home_currency: Option<String>,
+
+    /// Log to ~/.local/state/stock-tui/ (RUST_LOG overrides the level)
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Show 2 to 4 tickers side by side, each with its own chart
+    #[arg(long, conflicts_with_all = ["once", "export", "replay", "record"])]
+    pub grid: bool,
+
+    /// Append every fetched quote and chart to this JSONL session file
+    #[arg(long, value_name = "PATH", conflicts_with = "once")]
+    pub record: Option<PathBuf>,
+
+    /// Play back a session recorded with --record instead of fetching
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["once", "record"])]
+    pub replay: Option<PathBuf>,
+
+    /// With --replay, play back this many times faster than recorded
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, requires = "replay", value_parser = parse_speed)]
+    pub speed: f64,
+
+    /// Print one line per fetch instead of starting the TUI (automatic when
+    /// stdout isn't a terminal)
+    #[arg(long, conflicts_with_all = ["once", "export", "grid", "replay", "record"])]
+    pub stream: bool,
+
+    /// When streaming, print each quote as a JSON object
+    #[arg(long)]
+    pub json_lines: bool,
+
+    /// Alert on the first ticker: a price level ("price > 200", "below 180"),
+    /// the day's change ("change < -3%"), a move from the first price
+    /// ("down 2%", "up 5%"), a trailing stop below the running high
+    /// ("trail 1.5%") or a volume spike ("volume > 2x avg"). May be repeated.
+    /// Each fires once unless followed by "repeat"
+    #[arg(long = "alert", value_name = "RULE", value_parser = parse_alert)]
+    pub alerts: Vec<AlertRule>,
+
+    /// Watch the --alert rules without the TUI, printing each trigger to
+    /// stdout, and exit after the first one
+    #[arg(long, requires = "alerts", conflicts_with_all = ["once", "export", "grid", "replay", "record", "stream"])]
+    pub daemon: bool,
+
+    /// With --daemon, keep running after an alert triggers
+    #[arg(long, requires = "daemon")]
+    pub keep_alive: bool,
+}
+
+/// A weekday before today, for --date.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PastDay {
+    /// YYYY-MM-DD
+    pub date: String,
+    /// Days since 1970-01-01
+    days: i64,
+    /// Days before today (UTC)
+    pub age: i64,
+}
+
+impl PastDay {
+    pub fn parse(s: &str) -> Result<PastDay, String> {
+        let bad = || format!("'{}': expected a date as YYYY-MM-DD, e.g. 2024-03-15", s);
+        let mut parts = s.trim().splitn(3, '-').map(|part| part.parse::<i64>().ok());
+        let (Some(Some(year)), Some(Some(month)), Some(Some(day))) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(bad());
+        };
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(bad());
+        }
+        PastDay::from_days(days_from_civil(year, month, day))
+    }
+
+    fn from_days(days: i64) -> Result<PastDay, String> {
+        let (year, month, day) = civil_from_days(days);
+        let date = format!("{:04}-{:02}-{:02}", year, month, day);
+        // 1970-01-01 was a Thursday
+        match (days + 4).rem_euclid(7) {
+            0 => return Err(format!("{} is a Sunday: markets are closed", date)),
+            6 => return Err(format!("{} is a Saturday: markets are closed", date)),
+            _ => {}
+        }
+        let age = today() - days;
+        if age <= 0 {
+            return Err(format!("{} isn't in the past: leave out --date for the live chart", date));
+        }
+        if age > HOURLY_DAYS {
+            return Err(format!("{} is too long ago: Yahoo keeps intraday bars for about two years", date));
+        }
+        Ok(PastDay { date, days, age })
+    }
+
+    /// The weekday before today.
+    pub fn latest() -> Option<PastDay> {
+        PastDay { date: String::new(), days: today(), age: 0 }.step(-1)
+    }
+
+    /// The next weekday in the direction of `step` (negative for earlier),
+    /// or None when that's today or later, or older than Yahoo's intraday
+    /// history.
+    pub fn step(&self, step: i64) -> Option<PastDay> {
+        let mut days = self.days;
+        loop {
+            days += step.signum();
+            if !matches!((days + 4).rem_euclid(7), 0 | 6) {
+                return PastDay::from_days(days).ok();
+            }
+        }
+    }
+    /// "Mon" to "Fri".
+    pub fn weekday(&self) -> &'static str {
+        ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"][self.days.rem_euclid(7) as usize]
+    }
+
+    /// The finest bars Yahoo still serves for the day. The fetch starts a few
+    /// days earlier, so the limits are kept a little short of Yahoo's 30
+    /// days for 1m bars, 60 for other minute bars and 730 for hourly ones.
+    pub fn interval(&self) -> &'static str {
+        if self.age <= 25 {
+            "1m"
+        } else if self.age <= 55 {
+            "5m"
+        } else {
+            "1h"
+        }
+    }
+}
+
+/// Today's UTC day, counted from 1970-01-01.
+fn today() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64).div_euclid(86_400)
+}
+
+/// Oldest --date with hourly bars; see `PastDay::interval`.
+const HOURLY_DAYS: i64 = 725;
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A three-letter ISO currency code, in upper case.
+pub fn parse_currency(s: &str) -> Result<String, String> {
+    let code = s.trim().to_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code)
+    } else {
+        Err("expected a three-letter currency code, e.g. EUR".to_string())
+    }
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err("expected a positive number".to_string()),
+    }
+}
+
+/// An alert rule, with the part that doesn't parse underlined.
+fn parse_alert(s: &str) -> Result<AlertRule, String> {
+    AlertRule::parse(s).map_err(|e| e.highlighted())
+}
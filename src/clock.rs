@@ -0,0 +1,168 @@
+//! Where the app reads the time, so timers can be driven by hand.
+
+use std::{
+    cell::Cell,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub trait Clock {
+    /// Monotonic time, for intervals: countdowns, fades, debounces.
+    fn now_instant(&self) -> Instant;
+
+    /// Wall-clock time, for comparing with bar timestamps and showing the
+    /// time of day.
+    fn now_utc(&self) -> SystemTime;
+
+    /// Time since `since`; zero if it is still ahead.
+    fn elapsed(&self, since: Instant) -> Duration {
+        self.now_instant().saturating_duration_since(since)
+    }
+
+    /// Whole seconds since the Unix epoch.
+    fn unix_secs(&self) -> u64 {
+        self.now_utc().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+}
+
+/// The system's clocks.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now_utc(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that stands still until advanced. Both times move together.
+/// Share it through an `Rc` to advance the clock an app reads.
+#[derive(Debug)]
+pub struct MockClock {
+    instant: Cell<Instant>,
+    utc: Cell<SystemTime>,
+}
+
+impl MockClock {
+    /// Starts at `unix_secs` seconds since the Unix epoch.
+    pub fn new(unix_secs: u64) -> MockClock {
+        MockClock { instant: Cell::new(Instant::now()), utc: Cell::new(UNIX_EPOCH + Duration::from_secs(unix_secs)) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.instant.set(self.instant.get() + by);
+        self.utc.set(self.utc.get() + by);
+    }
+
+    /// Sets the wall clock alone, as when the system time is changed or a
+    /// sleep isn't counted by the monotonic clock.
+    pub fn set_utc(&self, utc: SystemTime) {
+        self.utc.set(utc);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_instant(&self) -> Instant {
+        self.instant.get()
+    }
+
+    fn now_utc(&self) -> SystemTime {
+        self.utc.get()
+    }
+}
+
+/// A gap between two checks of a `JumpDetector`, with both clocks as they
+/// were at the earlier check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jump {
+    pub instant: Instant,
+    pub utc: SystemTime,
+    /// Time passed on the monotonic clock
+    pub gap: Duration,
+    /// How far the wall clock moved apart from the monotonic one
+    pub wall_gap: Duration,
+}
+
+/// Notices the machine waking from sleep, or the wall clock being set, from
+/// a gap between checks on either clock: the monotonic clock may not count
+/// the sleep, the wall clock always does.
+#[derive(Debug, Clone, Copy)]
+pub struct JumpDetector {
+    last: (Instant, SystemTime),
+    threshold: Duration,
+}
+
+impl JumpDetector {
+    /// Detects gaps of at least `threshold`, counting from now.
+    pub fn new(clock: &dyn Clock, threshold: Duration) -> JumpDetector {
+        JumpDetector { last: (clock.now_instant(), clock.now_utc()), threshold }
+    }
+
+    /// The jump since the last check, if there was one.
+    pub fn check(&mut self, clock: &dyn Clock) -> Option<Jump> {
+        let (instant, utc) = (clock.now_instant(), clock.now_utc());
+        let (last_instant, last_utc) = std::mem::replace(&mut self.last, (instant, utc));
+        let gap = instant.saturating_duration_since(last_instant);
+        let wall_gap = match utc.duration_since(last_utc) {
+            Ok(wall) => wall.abs_diff(gap),
+            // Set back
+            Err(e) => e.duration() + gap,
+        };
+        if gap < self.threshold && wall_gap < self.threshold {
+            return None;
+        }
+        Some(Jump { instant: last_instant, utc: last_utc, gap, wall_gap })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JUMP: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn steady_ticks_are_not_jumps() {
+        let clock = MockClock::new(1_000_000);
+        let mut detector = JumpDetector::new(&clock, JUMP);
+        for _ in 0..120 {
+            clock.advance(Duration::from_secs(1));
+            assert_eq!(detector.check(&clock), None);
+        }
+        clock.advance(JUMP - Duration::from_secs(1));
+        assert_eq!(detector.check(&clock), None);
+    }
+
+    #[test]
+    fn wall_clock_set_forward_or_back_is_a_jump() {
+        let clock = MockClock::new(1_000_000);
+        let mut detector = JumpDetector::new(&clock, JUMP);
+        let before = clock.now_utc();
+        clock.advance(Duration::from_secs(1));
+        clock.set_utc(before + Duration::from_secs(2 * 3600));
+        let jump = detector.check(&clock).unwrap();
+        assert_eq!(jump.gap, Duration::from_secs(1));
+        assert_eq!(jump.wall_gap, Duration::from_secs(2 * 3600 - 1));
+        assert_eq!(jump.utc, before);
+
+        clock.set_utc(before);
+        assert_eq!(detector.check(&clock).unwrap().wall_gap, Duration::from_secs(2 * 3600));
+        assert_eq!(detector.check(&clock), None);
+    }
+
+    #[test]
+    fn monotonic_gap_after_sleep_is_a_jump() {
+        let clock = MockClock::new(1_000_000);
+        let mut detector = JumpDetector::new(&clock, JUMP);
+        let before = clock.now_instant();
+        clock.advance(Duration::from_secs(8 * 3600));
+        let jump = detector.check(&clock).unwrap();
+        assert_eq!(jump.gap, Duration::from_secs(8 * 3600));
+        assert_eq!(jump.wall_gap, Duration::ZERO);
+        assert_eq!(jump.instant, before);
+        assert_eq!(detector.check(&clock), None);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{alert::AlertRule, cli::{INTERVALS, TIMEFRAMES}, keymap::Action, levels::Level, symbol::normalize_symbol};
+
+/// What a `:` command line asks for.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Anything a key can do
+    Action(Action),
+    Ticker(String),
+    Range(String),
+    /// Bar size; snapped to one the range allows
+    Interval(String),
+    /// New refresh interval, in seconds
+    Refresh(u64),
+    Alert(AlertRule),
+    Level(Level),
+    /// `:export PATH`; without a path it's `Action::ExportCsv`
+    Export(String),
+    /// `:snapshot [--ansi] [PATH]`; colors are kept with `--ansi`
+    Snapshot { path: Option<String>, ansi: bool },
+}
+
+/// Command names and their arguments, for completion and the help popup.
+pub const COMMANDS: &[(&str, &str, &str)] = &[
+    ("ticker", "SYMBOL", "Open a symbol"),
+    ("range", "RANGE", "Chart timeframe (1d, 5d, 1mo, ... max)"),
+    ("interval", "[INTERVAL]", "Bar size (1m, 5m, 1h, 1d, ...); without one, the next that suits the range"),
+    ("refresh", "[SECS]", "Refresh now, or set the interval (at least 5)"),
+    ("alert", "RULE", "Alert on this symbol, e.g. above 500, down 2%, trail 1.5%, volume 3x [repeat]"),
+    ("export", "[PATH]", "Export the chart data to CSV"),
+    ("snapshot", "[--ansi] [PATH]", "Save the screen as text, with --ansi in color"),
+    ("level", "PRICE [LABEL]", "Mark a price level on this symbol's chart; without a price, list them"),
+    ("fullscreen", "", "Toggle full-screen chart"),
+    ("billboard", "", "Toggle the big-number price display"),
+    ("colors", "", "Toggle direction colors"),
+    ("yesterday", "", "Toggle the previous day overlay"),
+    ("scale", "", "Toggle y axis scaling: tight / anchored to the baseline"),
+    ("log", "", "Toggle a logarithmic price axis"),
+    ("tape", "", "Toggle market index strip"),
+    ("copy", "", "Copy quote to clipboard"),
+    ("browser", "", "Open quote page in browser"),
+    ("help", "", "Show this help"),
+    ("quit", "", "Quit (also :q)"),
+];
+
+/// Minimum refresh interval, matching `--refresh`.
+const MIN_REFRESH_SECS: u64 = 5;
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, arg) = line.split_once(char::is_whitespace).map_or((line, ""), |(name, arg)| (name, arg.trim()));
+    let action = |action| match arg {
+        "" => Ok(Command::Action(action)),
+        _ => Err(format!(":{} takes no argument", name)),
+    };
+    let needs = |what: &str| format!(":{} needs {}", name, what);
+    match name {
+        "q" | "quit" => action(Action::Quit),
+        "help" => action(Action::ShowHelp),
+        "fullscreen" => action(Action::ToggleFullscreen),
+        "billboard" => action(Action::ToggleBillboard),
+        "colors" => action(Action::ToggleDirectionColors),
+        "yesterday" => action(Action::TogglePreviousDay),
+        "scale" => action(Action::ToggleYScale),
+        "log" => action(Action::ToggleLogScale),
+        "tape" => action(Action::ToggleTape),
+        "copy" => action(Action::CopyQuote),
+        "browser" => action(Action::OpenInBrowser),
+        "ticker" if arg.is_empty() => Err(needs("a symbol")),
+        "ticker" => normalize_symbol(arg).map(Command::Ticker),
+        "range" if TIMEFRAMES.contains(&arg) => Ok(Command::Range(arg.to_string())),
+        "range" => Err(needs(&format!("one of: {}", TIMEFRAMES.join(", ")))),
+        "interval" if arg.is_empty() => Ok(Command::Action(Action::CycleInterval)),
+        "interval" if INTERVALS.contains(&arg) => Ok(Command::Interval(arg.to_string())),
+        "interval" => Err(needs(&format!("one of: {}", INTERVALS.join(", ")))),
+        "refresh" if arg.is_empty() => Ok(Command::Action(Action::Refresh)),
+        "refresh" => match arg.parse::<u64>() {
+            Ok(secs) if secs >= MIN_REFRESH_SECS => Ok(Command::Refresh(secs)),
+            _ => Err(needs(&format!("a whole number of seconds, at least {}", MIN_REFRESH_SECS))),
+        },
+        "alert" => AlertRule::parse(arg).map(Command::Alert).map_err(|e| e.to_string()),
+        "level" if arg.is_empty() => Ok(Command::Action(Action::ShowLevels)),
+        "level" => Level::parse(arg).map(Command::Level),
+        "export" if arg.is_empty() => Ok(Command::Action(Action::ExportCsv)),
+        "export" => Ok(Command::Export(arg.to_string())),
+        "snapshot" => {
+            let (ansi, path) = match arg.strip_prefix("--ansi") {
+                Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => (true, rest.trim()),
+                _ => (false, arg),
+            };
+            Ok(Command::Snapshot { path: (!path.is_empty()).then(|| path.to_string()), ansi })
+        }
+        _ => Err(format!("unknown command :{} (see ? for the list)", name)),
+    }
+}
+
+/// Completes a partly typed command name as far as it's unambiguous,
+/// adding a space once a single command matches.
+pub fn complete(input: &str) -> Option<String> {
+    if input.contains(char::is_whitespace) {
+        return None;
+    }
+    let mut matches = COMMANDS.iter().map(|(name, _, _)| *name).filter(|name| name.starts_with(input));
+    let first = matches.next()?;
+    let mut common = first.len();
+    let mut unique = true;
+    for name in matches {
+        unique = false;
+        common = first.bytes().zip(name.bytes()).take(common).take_while(|(a, b)| a == b).count();
+    }
+    Some(if unique { format!("{} ", first) } else { first[..common].to_string() })
+}
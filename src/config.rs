@@ -0,0 +1,274 @@
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{format::Formatter, theme::ChangeGrades};
+
+/// Settings read from `~/.config/stock-tui/config.toml`. Every field is
+/// optional so a partial (or missing) file falls back to the defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub theme: Option<String>,
+    pub ascii: Option<bool>,
+    /// Page opened by `o`; `{symbol}` is replaced with the current ticker.
+    pub quote_url: Option<String>,
+    /// File written by `e`; `{symbol}`, `{range}` and `{date}` are expanded.
+    pub export_path: Option<String>,
+    /// File written by `E`; `{symbol}`, `{date}` and `{time}` are expanded.
+    pub snapshot_path: Option<String>,
+    /// Faint horizontal and vertical lines at the chart's axis labels.
+    pub gridlines: Option<bool>,
+    /// How line charts draw the bars: "line", "points" or "line+points".
+    pub line_style: Option<LineStyle>,
+    /// How the y axis is bounded: "tight" to the data in view, or "anchored"
+    /// to keep the baseline (0% or the previous close) in range.
+    pub y_scale: Option<YScale>,
+    /// The 1-day chart's time axis: "session" for the whole trading day,
+    /// "elapsed" from the open to just past the latest bar, or "sliding"
+    /// for the last `sliding_minutes`.
+    pub x_axis: Option<XAxis>,
+    /// Minutes shown by the sliding time axis.
+    pub sliding_minutes: Option<u32>,
+    /// Compare today's volume with what an average day has traded by this
+    /// time of the session, rather than with a whole day.
+    pub rvol_time_adjusted: Option<bool>,
+    /// Fixed decimals for prices, 0 to 8. Unset, they follow the price's
+    /// magnitude: 2 from 1.00 up, more for sub-dollar prices.
+    pub price_decimals: Option<usize>,
+    /// Group large numbers with commas, e.g. 67,123.45.
+    pub thousands_separators: Option<bool>,
+    /// Drop the cents from prices of 10,000 and up, e.g. 640,123 for BRK-A,
+    /// unless `price_decimals` is set.
+    pub compact_prices: Option<bool>,
+    /// Currency the header also shows the price in, e.g. "EUR".
+    pub home_currency: Option<String>,
+    /// A line at the bottom naming the data source, bar interval and
+    /// refresh interval, and the keys to start with.
+    pub hint_line: Option<bool>,
+    /// Percent moves between faint, normal and bold change colors.
+    pub change_grades: ChangeGrades,
+    /// Accent colors in the grid and the watchlist, by symbol: a palette
+    /// name ("blue", "orange", ...) or "#rrggbb". Others get one hashed
+    /// from the symbol.
+    pub accents: HashMap<String, String>,
+    pub notifications: NotificationConfig,
+    pub flash: FlashConfig,
+    pub tape: TapeConfig,
+    pub alerts: AlertConfig,
+    pub footer: FooterConfig,
+    pub cross_check: CrossCheckConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Percent move between two consecutive fetches that triggers a notification.
+    pub move_threshold: f64,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        NotificationConfig { enabled: true, move_threshold: 1.0 }
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("stock-tui"))
+}
+
+impl Config {
+    /// Reads `path` if given (it must exist), otherwise the default location
+    /// if a file is there.
+    pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match config_dir().map(|dir| dir.join("config.toml")) {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    pub fn formatter(&self) -> Formatter {
+        Formatter::new(
+            self.price_decimals,
+            self.thousands_separators.unwrap_or(true),
+            self.compact_prices.unwrap_or(true),
+        )
+    }
+}
+
+/// How the chart's y axis is bounded. Tight fits the data, so a steady +6%
+/// day fills the chart; anchored keeps the baseline in view so the distance
+/// from it can be read off at a glance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum YScale {
+    #[default]
+    Tight,
+    Anchored,
+}
+
+impl YScale {
+    pub fn toggled(self) -> YScale {
+        match self {
+            YScale::Tight => YScale::Anchored,
+            YScale::Anchored => YScale::Tight,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            YScale::Tight => "tight",
+            YScale::Anchored => "anchored",
+        }
+    }
+}
+
+/// How much of the day the 1-day chart's time axis spans. Session shows
+/// where the day will end, at the cost of an empty right side until the
+/// afternoon; elapsed and sliding spend the width on the bars there are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum XAxis {
+    #[default]
+    Session,
+    Elapsed,
+    Sliding,
+}
+
+impl XAxis {
+    /// The next mode for the cycling key binding.
+    pub fn next(self) -> XAxis {
+        match self {
+            XAxis::Session => XAxis::Elapsed,
+            XAxis::Elapsed => XAxis::Sliding,
+            XAxis::Sliding => XAxis::Session,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            XAxis::Session => "whole session",
+            XAxis::Elapsed => "session so far",
+            XAxis::Sliding => "sliding window",
+        }
+    }
+}
+
+/// How a line chart draws its bars. Dots show where prints are sparse rather
+/// than implying trading in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStyle {
+    #[default]
+    Line,
+    Points,
+    #[serde(rename = "line+points")]
+    LinePoints,
+}
+
+impl LineStyle {
+    /// The next style for the cycling key binding.
+    pub fn next(self) -> LineStyle {
+        match self {
+            LineStyle::Line => LineStyle::Points,
+            LineStyle::Points => LineStyle::LinePoints,
+            LineStyle::LinePoints => LineStyle::Line,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineStyle::Line => "line",
+            LineStyle::Points => "points",
+            LineStyle::LinePoints => "line+points",
+        }
+    }
+}
+
+/// The market index strip above the header.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct TapeConfig {
+    pub enabled: bool,
+    /// Yahoo symbols to show, in order.
+    pub symbols: Vec<String>,
+}
+
+impl Default for TapeConfig {
+    fn default() -> Self {
+        TapeConfig {
+            enabled: true,
+            symbols: ["^GSPC", "^IXIC", "^DJI", "^VIX"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// The session statistics line under the chart.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FooterConfig {
+    pub enabled: bool,
+    /// Also show the bar-to-bar volatility scaled to a year.
+    pub annualize: bool,
+}
+
+/// Checking the chart's price against the provider's batch quotes now and
+/// then, to catch a fetch stuck on stale cached data.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CrossCheckConfig {
+    pub enabled: bool,
+    /// Percent the two prices may differ by before the header warns.
+    pub tolerance: f64,
+}
+
+impl Default for CrossCheckConfig {
+    fn default() -> Self {
+        CrossCheckConfig { enabled: false, tolerance: 0.5 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Percent a repeating alert's value must move back past its threshold
+    /// before it can fire again.
+    pub hysteresis: f64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        AlertConfig { hysteresis: 0.5 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct FlashConfig {
+    pub enabled: bool,
+    /// Percent move between two consecutive fetches that flashes the header.
+    pub threshold: f64,
+    /// Also ring the terminal bell.
+    pub bell: bool,
+}
+
+impl Default for FlashConfig {
+    fn default() -> Self {
+        FlashConfig { enabled: true, threshold: 0.5, bell: true }
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    error::Error,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Default name for exports made with `e`.
+pub const DEFAULT_EXPORT_PATH: &str = "{symbol}-{range}-{date}.csv";
+
+/// The bars behind the chart, restricted to the visible window. Columns are
+/// parallel; percentages use the same baseline as the percent chart.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Series {
+    pub utc: Vec<String>,
+    pub et: Vec<String>,
+    pub price: Vec<f64>,
+    pub pct_change: Vec<f64>,
+    pub volume: Vec<u64>,
+}
+
+impl Series {
+    /// Rows with every column present. Partial data can leave columns of
+    /// different lengths; the extra entries are ignored.
+    pub fn len(&self) -> usize {
+        self.column_lengths().into_iter().min().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entries past `len` in the longest column, which have no partner in
+    /// some other column; 0 when the columns are all the same length.
+    pub fn unmatched(&self) -> usize {
+        self.column_lengths().into_iter().max().unwrap_or(0) - self.len()
+    }
+
+    fn column_lengths(&self) -> [usize; 5] {
+        [self.utc.len(), self.et.len(), self.price.len(), self.pct_change.len(), self.volume.len()]
+    }
+}
+
+/// Expands `{symbol}`, `{range}` and `{date}` (the New York date of the last
+/// bar) in an export path. Also returns whether anything was expanded: only
+/// templated names may replace an existing file.
+pub fn expand_path(template: &str, symbol: &str, range: &str, series: &Series) -> (PathBuf, bool) {
+    let date = series.et.last().map(|t| t.get(..10).unwrap_or(t)).unwrap_or_default();
+    let expanded = template
+        .replace("{symbol}", symbol)
+        .replace("{range}", range)
+        .replace("{date}", date);
+    let templated = expanded != template;
+    (PathBuf::from(expanded), templated)
+}
+
+/// Writes `series` to `path`, with prices rounded to `decimals` places when
+/// given. Numbers are never grouped with separators, so they stay numeric.
+pub fn write_csv(path: &Path, series: &Series, overwrite: bool, decimals: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let file = create(path, overwrite)?;
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record(["timestamp_utc", "time_et", "price", "pct_change", "volume"])?;
+    let rows = series
+        .utc
+        .iter()
+        .zip(&series.et)
+        .zip(&series.price)
+        .zip(&series.pct_change)
+        .zip(&series.volume);
+    for ((((utc, et), price), pct), volume) in rows {
+        let price = match decimals {
+            Some(decimals) => format!("{:.*}", decimals, price),
+            None => price.to_string(),
+        };
+        writer.write_record([utc.clone(), et.clone(), price, pct.to_string(), volume.to_string()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes `text` to `path` as is.
+pub fn write_text(path: &Path, text: &str, overwrite: bool) -> Result<(), Box<dyn Error>> {
+    let mut file = create(path, overwrite)?;
+    file.write_all(text.as_bytes()).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Opens `path` for writing; unless `overwrite`, only if it doesn't exist.
+fn create(path: &Path, overwrite: bool) -> Result<File, String> {
+    let file = if overwrite {
+        File::create(path)
+    } else {
+        OpenOptions::new().write(true).create_new(true).open(path)
+    };
+    file.map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => format!("{} already exists", path.display()),
+        _ => format!("{}: {}", path.display(), e),
+    })
+}
+
+/// Writes exports on a background thread so a slow disk can't stall the UI.
+/// Each export reports the written path, or why it failed.
+pub struct Exporter {
+    tx: Sender<Result<PathBuf, String>>,
+    rx: Receiver<Result<PathBuf, String>>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Exporter {
+    pub fn new() -> Exporter {
+        let (tx, rx) = mpsc::channel();
+        Exporter { tx, rx }
+    }
+
+    pub fn spawn(&self, path: PathBuf, series: Series, overwrite: bool, decimals: Option<usize>) {
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = write_csv(&path, &series, overwrite, decimals).map(|()| path).map_err(|e| e.to_string());
+            let _ = tx.send(result);
+        });
+    }
+
+    pub fn spawn_text(&self, path: PathBuf, text: String, overwrite: bool) {
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = write_text(&path, &text, overwrite).map(|()| path).map_err(|e| e.to_string());
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Returns the outcome of the next finished export, if any.
+    pub fn take_result(&self) -> Option<Result<PathBuf, String>> {
+        self.rx.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+    use crate::model::StockStats;
+
+    /// A fetch whose series lost a price and a volume to partial data.
+    const RAGGED: &str = r#"{"symbol": "AAPL", "price": 231.5, "series": {
+        "utc": ["2026-03-02T14:30:00Z", "2026-03-02T14:31:00Z", "2026-03-02T14:32:00Z"],
+        "et": ["2026-03-02 09:30", "2026-03-02 09:31", "2026-03-02 09:32"],
+        "price": [231.1, 231.5],
+        "pct_change": [0.1, 0.3, 0.2],
+        "volume": [1200, 900]
+    }}"#;
+
+    #[test]
+    fn ragged_series_keeps_only_whole_rows() {
+        let stats: StockStats = serde_json::from_str(RAGGED).unwrap();
+        let series = stats.series.unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series.unmatched(), 1);
+
+        let path = env::temp_dir().join(format!("tmp2-ragged-{}.csv", std::process::id()));
+        write_csv(&path, &series, true, None).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(
+            written,
+            "timestamp_utc,time_et,price,pct_change,volume\n\
+             2026-03-02T14:30:00Z,2026-03-02 09:30,231.1,0.1,1200\n\
+             2026-03-02T14:31:00Z,2026-03-02 09:31,231.5,0.3,900\n"
+        );
+    }
+
+    #[test]
+    fn exported_prices_are_rounded_but_never_grouped() {
+        let series = Series {
+            utc: vec!["2026-03-02T14:30:00Z".into()],
+            et: vec!["2026-03-02 09:30".into()],
+            price: vec![640_123.456],
+            pct_change: vec![0.5],
+            volume: vec![1_234_567],
+        };
+        let path = env::temp_dir().join(format!("tmp2-decimals-{}.csv", std::process::id()));
+        write_csv(&path, &series, true, Some(1)).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(written.lines().nth(1), Some("2026-03-02T14:30:00Z,2026-03-02 09:30,640123.5,0.5,1234567"));
+    }
+
+    #[test]
+    fn matching_columns_have_nothing_unmatched() {
+        let series = Series { utc: vec!["a".into()], et: vec!["b".into()], price: vec![1.0], pct_change: vec![0.0], volume: vec![5] };
+        assert_eq!((series.len(), series.unmatched()), (1, 0));
+        assert!(Series::default().is_empty());
+    }
+}
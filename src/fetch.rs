@@ -0,0 +1,493 @@
+//! Running the provider script: the main fetch, in the background and
+//! cancellable, and the lookups beside it.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    error::Error,
+    io::{self, Cursor, Read},
+    process::{Child, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use image::ImageReader;
+use tracing::{debug, info, warn};
+
+use crate::{
+    api::yahoo::{
+        parse_fetch_output, run_script, Calendar, CalendarResponse, FetchError, Headline, MiniQuote, NewsResponse,
+        QuotesResponse, SearchResponse, TickerRecord,
+    },
+    cli::{Cli, PastDay, Provider, INTERVALS},
+    config::{LineStyle, XAxis, YScale},
+    export::Series,
+    format::Formatter,
+    levels::Level,
+    model::StockStats,
+    replay::{Recorder, Replay},
+    theme::{ChangeGrades, ChartPalette},
+};
+
+/// Fetch durations kept for the debug overlay's min/avg/max.
+const LATENCY_SAMPLES: usize = 20;
+
+/// Minutes the sliding time axis spans unless configured.
+pub(crate) const DEFAULT_SLIDING_MINUTES: u32 = 60;
+
+/// Everything the Python fetcher needs to produce stats and a chart image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FetchRequest {
+    pub(crate) symbol: String,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    pub(crate) indicators: HashSet<String>,
+    pub(crate) use_24h: bool,
+    pub(crate) price_view: bool,
+    pub(crate) period: String,
+    pub(crate) interval: String,
+    pub(crate) chart_type: String,
+    pub(crate) palette: ChartPalette,
+    // The price line is drawn fainter or bolder by the size of the day's move
+    pub(crate) change_grades: ChangeGrades,
+    // Small chart areas get fewer, smaller axis labels
+    pub(crate) compact: bool,
+    pub(crate) gridlines: bool,
+    pub(crate) line_style: LineStyle,
+    // Display only: the header, stats and alerts use the raw closes
+    pub(crate) smoothing: usize,
+    pub(crate) y_scale: YScale,
+    // The 1-day chart's time axis, and the minutes the sliding one spans
+    pub(crate) x_axis: XAxis,
+    pub(crate) sliding_minutes: u32,
+    // Logarithmic price axis; the percent chart ignores it
+    pub(crate) log_scale: bool,
+    pub(crate) pre_market: bool,
+    // Intraday percent chart measured from today's open
+    pub(crate) vs_open: bool,
+    pub(crate) direction_colors: bool,
+    // Intraday 1-day chart overlaid with the previous trading day
+    pub(crate) prev_day: bool,
+    // The symbol's marked price levels, drawn where they fall inside the chart
+    pub(crate) levels: Vec<Level>,
+    // (fetch time, price) pairs drawn over the 1-day chart, empty when off
+    pub(crate) observed: Vec<(u64, f64)>,
+    // A past day (YYYY-MM-DD) to chart instead of the latest one
+    pub(crate) date: Option<String>,
+    // Axis labels are written the same way as the header's prices
+    pub(crate) formatter: Formatter,
+    pub(crate) provider: Provider,
+    // False when only the quote is wanted (--once)
+    pub(crate) chart: bool,
+}
+
+/// Finest interval Yahoo serves for the whole of `timeframe`.
+pub(crate) fn suggested_interval(timeframe: &str) -> &'static str {
+    match timeframe {
+        "1d" => "1m",
+        "5d" => "5m",
+        "2y" => "1wk",
+        "5y" | "10y" => "1mo",
+        _ => "1d",
+    }
+}
+
+/// Roughly how many calendar days `timeframe` spans.
+fn timeframe_days(timeframe: &str) -> u32 {
+    match timeframe {
+        "1d" => 1,
+        "5d" => 5,
+        "1mo" => 30,
+        "3mo" => 90,
+        "6mo" => 180,
+        "1y" | "ytd" => 365,
+        "2y" => 730,
+        "5y" => 1825,
+        "10y" => 3650,
+        _ => u32::MAX,
+    }
+}
+
+/// Whether Yahoo serves `interval` bars across the whole of `timeframe`, and
+/// they're short enough to draw more than a handful. 1m bars only go back 7
+/// days, other minute bars 60 and hourly bars 730.
+pub(crate) fn interval_allowed(timeframe: &str, interval: &str) -> bool {
+    let (min_days, max_days) = match interval {
+        "1m" => (0, 7),
+        "2m" | "5m" | "15m" => (0, 60),
+        "1h" => (0, 730),
+        "1d" => (5, u32::MAX),
+        "1wk" => (90, u32::MAX),
+        "1mo" => (365, u32::MAX),
+        "3mo" => (730, u32::MAX),
+        _ => return false,
+    };
+    (min_days..=max_days).contains(&timeframe_days(timeframe))
+}
+
+/// `interval` if `timeframe` allows it, otherwise the allowed interval
+/// closest in size, the finer one on a tie.
+pub(crate) fn snap_interval(timeframe: &str, interval: &str) -> &'static str {
+    let wanted = INTERVALS.iter().position(|i| *i == interval).unwrap_or(0);
+    INTERVALS
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| interval_allowed(timeframe, i))
+        .min_by_key(|(index, _)| (index.abs_diff(wanted), *index))
+        .map_or(suggested_interval(timeframe), |(_, i)| *i)
+}
+
+/// Length of one bar for intraday intervals ("5m", "1h"); None for daily
+/// and longer.
+pub(crate) fn intraday_bar(interval: &str) -> Option<Duration> {
+    let (count, unit) = interval.split_at(interval.len().checked_sub(1)?);
+    let count: u64 = count.parse().ok()?;
+    match unit {
+        "m" => Some(Duration::from_secs(count * 60)),
+        "h" => Some(Duration::from_secs(count * 3600)),
+        _ => None,
+    }
+}
+
+fn fetch_command(req: &FetchRequest) -> Command {
+    let indicators_str = if req.indicators.is_empty() {
+        "None".to_string()
+    } else {
+        req.indicators
+            .iter()
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    
+    let time_fmt = if req.use_24h { "24h" } else { "12h" };
+    let chart_mode = if req.price_view { "price" } else { "percent" };
+    // Rendering options without a positional slot of their own travel as JSON
+    let options = serde_json::json!({
+        "palette": req.palette,
+        "change_grades": req.change_grades,
+        "compact": req.compact,
+        "gridlines": req.gridlines,
+        "line_style": req.line_style,
+        "smoothing": req.smoothing,
+        "y_scale": req.y_scale,
+        "x_axis": req.x_axis,
+        "sliding_minutes": req.sliding_minutes,
+        "log_scale": req.log_scale,
+        "prepost": req.pre_market,
+        "baseline": if req.vs_open { "open" } else { "close" },
+        "direction_colors": req.direction_colors,
+        "prev_day": req.prev_day,
+        "levels": req.levels,
+        "observed": req.observed,
+        "date": req.date,
+        "decimals": req.formatter.decimals,
+        "thousands": req.formatter.thousands,
+        "compact_prices": req.formatter.compact,
+        "chart": req.chart,
+    });
+
+    let mut cmd = Command::new("python3");
+    cmd.arg(req.provider.script())
+        .arg(&req.symbol)
+        .arg(req.width.to_string())
+        .arg(req.height.to_string())
+        .arg(indicators_str)
+        .arg(time_fmt)
+        .arg(chart_mode)
+        .arg(&req.period)
+        .arg(&req.interval)
+        .arg(&req.chart_type)
+        .arg(options.to_string());
+    cmd
+}
+
+/// Runs the fetcher and parses its output, logging how it went.
+pub(crate) fn run_fetch(req: &FetchRequest, slot: &Mutex<Option<Child>>) -> Result<StockStats, FetchError> {
+    let started = Instant::now();
+    debug!(symbol = %req.symbol, period = %req.period, interval = %req.interval, "fetch started");
+    let (success, out, err) = run_fetch_process(req, slot)
+        .inspect_err(|e| {
+            warn!(symbol = %req.symbol, error = %e, "couldn't run the fetcher");
+        })
+        .map_err(FetchError::Launch)?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if !err.trim().is_empty() {
+        warn!(symbol = %req.symbol, stderr = %err.trim(), "fetcher wrote to stderr");
+    }
+
+    let result = parse_fetch_output(success, &out, &err);
+    match &result {
+        Ok(stats) => match &stats.error {
+            Some(e) => warn!(symbol = %req.symbol, elapsed_ms, bytes = out.len(), error = %e, "fetch returned an error"),
+            None => info!(
+                symbol = %req.symbol,
+                elapsed_ms,
+                bytes = out.len(),
+                points = stats.series.as_ref().map_or(0, Series::len),
+                "fetch succeeded"
+            ),
+        },
+        Err(e) => warn!(symbol = %req.symbol, elapsed_ms, bytes = out.len(), success, error = %e, "fetch failed"),
+    }
+    result
+}
+
+/// Runs several fetches at once, one thread per request so the whole list
+/// takes about as long as its slowest symbol, and returns one result per
+/// request in the same order. A symbol that fails, or whose fetch reports an
+/// error, leaves the others untouched.
+pub(crate) fn fetch_all(reqs: &[FetchRequest]) -> Vec<Result<StockStats, String>> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = reqs
+            .iter()
+            .map(|req| {
+                scope.spawn(move || match run_fetch(req, &Mutex::new(None)) {
+                    Ok(stats) => match &stats.error {
+                        Some(e) => Err(e.clone()),
+                        None => Ok(stats),
+                    },
+                    Err(e) => Err(e.to_string()),
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err("the fetch panicked".to_string())))
+            .collect()
+    })
+}
+
+/// Runs the fetcher process, publishing the child in `slot` so another thread
+/// can kill it while we block on its output. Returns whether it exited
+/// successfully along with its stdout and stderr.
+fn run_fetch_process(req: &FetchRequest, slot: &Mutex<Option<Child>>) -> io::Result<(bool, String, String)> {
+    let mut child = fetch_command(req)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    if let Ok(mut guard) = slot.lock() {
+        *guard = Some(child);
+    }
+
+    // Drain stderr separately so a chatty script can't fill the pipe and stall
+    let stderr_reader = thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(mut pipe) = stderr {
+            let _ = pipe.read_to_string(&mut buf);
+        }
+        buf
+    });
+    let mut out = String::new();
+    if let Some(mut pipe) = stdout {
+        pipe.read_to_string(&mut out)?;
+    }
+    let err = stderr_reader.join().unwrap_or_default();
+
+    let child = slot.lock().ok().and_then(|mut guard| guard.take());
+    let success = match child {
+        Some(mut child) => child.wait()?.success(),
+        None => false,
+    };
+    Ok((success, out, err))
+}
+
+/// Where quotes come from: live fetches, optionally recorded to a session
+/// file, or a recorded session played back.
+pub enum DataSource {
+    Live { fetcher: Fetcher, recorder: Option<Recorder> },
+    Replay(Replay),
+}
+
+impl DataSource {
+    pub fn from_cli(cli: &Cli) -> Result<DataSource, Box<dyn Error>> {
+        if let Some(path) = &cli.replay {
+            return Ok(DataSource::Replay(Replay::load(path, cli.speed)?));
+        }
+        let recorder = match &cli.record {
+            Some(path) => Some(Recorder::create(path).map_err(|e| format!("{}: {}", path.display(), e))?),
+            None => None,
+        };
+        Ok(DataSource::Live { fetcher: Fetcher::new(), recorder })
+    }
+
+    /// The live fetcher; None while replaying.
+    pub(crate) fn fetcher(&mut self) -> Option<&mut Fetcher> {
+        match self {
+            DataSource::Live { fetcher, .. } => Some(fetcher),
+            DataSource::Replay(_) => None,
+        }
+    }
+
+    pub(crate) fn replay(&self) -> Option<&Replay> {
+        match self {
+            DataSource::Live { .. } => None,
+            DataSource::Replay(replay) => Some(replay),
+        }
+    }
+}
+
+/// Result of a background fetch, tagged with the chart area it was rendered for.
+pub(crate) struct FetchOutcome {
+    pub(crate) generation: u64,
+    pub(crate) area_size: (u16, u16),
+    pub(crate) elapsed: Duration,
+    pub(crate) result: Result<StockStats, FetchError>,
+}
+
+/// Recent fetch health, shown by the debug overlay.
+#[derive(Default)]
+pub(crate) struct FetchTimings {
+    // Oldest first, at most LATENCY_SAMPLES
+    pub(crate) latencies: VecDeque<Duration>,
+    pub(crate) last_success: Option<Instant>,
+}
+
+impl FetchTimings {
+    pub(crate) fn record(&mut self, latency: Duration) {
+        if self.latencies.len() == LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    /// Min, average and max of the kept latencies.
+    pub(crate) fn summary(&self) -> Option<(Duration, Duration, Duration)> {
+        let min = *self.latencies.iter().min()?;
+        let max = *self.latencies.iter().max()?;
+        let avg = self.latencies.iter().sum::<Duration>() / self.latencies.len() as u32;
+        Some((min, avg, max))
+    }
+}
+
+/// Runs fetches on a worker thread so the event loop never blocks on the
+/// network. At most one fetch is in flight; starting a new one or cancelling
+/// makes any older result stale.
+pub struct Fetcher {
+    tx: Sender<FetchOutcome>,
+    rx: Receiver<FetchOutcome>,
+    generation: u64,
+    in_flight: Option<Arc<Mutex<Option<Child>>>>,
+}
+
+impl Fetcher {
+    pub(crate) fn new() -> Fetcher {
+        let (tx, rx) = mpsc::channel();
+        Fetcher { tx, rx, generation: 0, in_flight: None }
+    }
+
+    pub(crate) fn is_busy(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    pub(crate) fn spawn(&mut self, req: FetchRequest, area_size: (u16, u16)) {
+        self.generation += 1;
+        let generation = self.generation;
+        let slot = Arc::new(Mutex::new(None));
+        let thread_slot = Arc::clone(&slot);
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let started = Instant::now();
+            let result = run_fetch(&req, &thread_slot);
+            let elapsed = started.elapsed();
+            let _ = tx.send(FetchOutcome { generation, area_size, elapsed, result });
+        });
+        self.in_flight = Some(slot);
+    }
+
+    /// Returns the outcome of the current fetch once it has finished.
+    pub(crate) fn try_recv(&mut self) -> Option<FetchOutcome> {
+        while let Ok(outcome) = self.rx.try_recv() {
+            if outcome.generation == self.generation {
+                self.in_flight = None;
+                return Some(outcome);
+            }
+        }
+        None
+    }
+
+    /// Marks a fetch as running without starting the script.
+    #[cfg(test)]
+    pub(crate) fn begin(&mut self) {
+        self.generation += 1;
+        self.in_flight = Some(Arc::new(Mutex::new(None)));
+    }
+
+    /// Finishes the current fetch with `result` without running the script.
+    #[cfg(test)]
+    pub(crate) fn finish(&mut self, result: Result<StockStats, FetchError>) {
+        let outcome = FetchOutcome { generation: self.generation, area_size: (0, 0), elapsed: Duration::ZERO, result };
+        let _ = self.tx.send(outcome);
+    }
+
+    /// Kills the in-flight fetch process, if any, and discards its result.
+    pub(crate) fn cancel(&mut self) {
+        if let Some(slot) = self.in_flight.take() {
+            debug!("cancelling in-flight fetch");
+            self.generation += 1;
+            if let Ok(mut guard) = slot.lock()
+                && let Some(child) = guard.as_mut()
+            {
+                let _ = child.kill();
+            }
+        }
+    }
+}
+
+pub(crate) fn search_symbols(provider: Provider, query: &str) -> Result<Vec<TickerRecord>, FetchError> {
+    let response: SearchResponse = run_script(provider, &["--search", query])?;
+    Ok(response.results)
+}
+
+/// `interval`, or the finest bars Yahoo still has for `day` if it's finer.
+pub(crate) fn interval_for_day(day: &PastDay, interval: &str) -> String {
+    let position = |i: &str| INTERVALS.iter().position(|&x| x == i);
+    if position(interval) < position(day.interval()) {
+        day.interval().to_string()
+    } else {
+        interval.to_string()
+    }
+}
+
+/// Daily quotes for several symbols in one request, with each one's
+/// currency if `with_currency` (an extra request per symbol).
+pub(crate) fn fetch_mini_quotes(provider: Provider, symbols: &[String], with_currency: bool) -> Result<Vec<MiniQuote>, FetchError> {
+    let symbols = symbols.join(",");
+    let mut args = vec!["--quotes", symbols.as_str()];
+    if with_currency {
+        args.push("--currency");
+    }
+    let response: QuotesResponse = run_script(provider, &args)?;
+    match response.error {
+        Some(e) => Err(FetchError::Script(e)),
+        None => Ok(response.quotes),
+    }
+}
+
+pub(crate) fn fetch_calendar(provider: Provider, symbol: &str) -> Result<Calendar, FetchError> {
+    let response: CalendarResponse = run_script(provider, &["--calendar", symbol])?;
+    match response.error {
+        Some(e) => Err(FetchError::Script(e)),
+        None => Ok(response.calendar),
+    }
+}
+
+pub(crate) fn fetch_news(provider: Provider, symbol: &str) -> Result<Vec<Headline>, FetchError> {
+    let response: NewsResponse = run_script(provider, &["--news", symbol])?;
+    match response.error {
+        Some(e) => Err(FetchError::Script(e)),
+        None => Ok(response.headlines),
+    }
+}
+
+pub(crate) fn decode_image(b64_data: &str) -> Option<image::DynamicImage> {
+    let bytes = general_purpose::STANDARD.decode(b64_data).ok()?;
+    let reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format().ok()?;
+    reader.decode().ok()
+}
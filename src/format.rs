@@ -0,0 +1,463 @@
+use std::{
+    cmp::Reverse,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::text::{Line, Span};
+
+/// Rounds to `decimals` places, turning a result of -0 into 0 so it isn't
+/// printed as "-0.00".
+fn round_clean(value: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    let rounded = (value * scale).round() / scale;
+    if rounded == 0.0 { 0.0 } else { rounded }
+}
+
+/// Decimals needed to show a price meaningfully: 2 from 1.00 up, 4 from 0.01,
+/// and three significant figures below that.
+pub fn price_decimals(price: f64) -> usize {
+    let magnitude = price.abs();
+    if magnitude >= 1.0 || magnitude == 0.0 || !magnitude.is_finite() {
+        2
+    } else if magnitude >= 0.01 {
+        4
+    } else {
+        ((-magnitude.log10()).floor() as usize + 3).min(10)
+    }
+}
+
+/// Most decimals `price_decimals` may be set to.
+pub const MAX_DECIMALS: usize = 8;
+
+/// From this price up, compact prices drop the cents (BRK-A at 640,123).
+pub const WHOLE_PRICE_FROM: f64 = 10_000.0;
+
+/// Inserts a comma every three digits of a formatted number's integer part,
+/// e.g. "-1234567.89" -> "-1,234,567.89".
+fn group_thousands(number: &str) -> String {
+    let start = number.find(|c: char| c.is_ascii_digit()).unwrap_or(number.len());
+    let end = number[start..].find(|c: char| !c.is_ascii_digit()).map_or(number.len(), |i| start + i);
+    let digits = &number[start..end];
+    let mut grouped = String::with_capacity(number.len() + digits.len() / 3);
+    grouped.push_str(&number[..start]);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(&number[end..]);
+    grouped
+}
+
+/// How prices and volumes are written, from the config's `price_decimals`,
+/// `thousands_separators` and `compact_prices`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Formatter {
+    /// Fixed decimals for prices; None picks them from the price's magnitude
+    pub decimals: Option<usize>,
+    pub thousands: bool,
+    /// Without fixed decimals, whole numbers from `WHOLE_PRICE_FROM` up
+    pub compact: bool,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter { decimals: None, thousands: true, compact: true }
+    }
+}
+
+impl Formatter {
+    pub fn new(decimals: Option<usize>, thousands: bool, compact: bool) -> Formatter {
+        Formatter { decimals: decimals.map(|d| d.min(MAX_DECIMALS)), thousands, compact }
+    }
+
+    fn decimals_for(&self, price: f64) -> usize {
+        match self.decimals {
+            Some(decimals) => decimals,
+            None if self.compact && price.abs() >= WHOLE_PRICE_FROM => 0,
+            None => price_decimals(price),
+        }
+    }
+
+    fn group(&self, number: String) -> String {
+        if self.thousands { group_thousands(&number) } else { number }
+    }
+
+    /// Price, e.g. "231.45", "0.4312", "0.000213", "67,123.45", or
+    /// "640,123" when compact.
+    pub fn price(&self, price: f64) -> String {
+        let decimals = self.decimals_for(price);
+        self.group(format!("{:.*}", decimals, round_clean(price, decimals)))
+    }
+
+    /// Signed absolute change, e.g. "+2.84" / "-0.0312", using the precision
+    /// of the instrument's price so penny moves don't all read as 0.00.
+    pub fn change(&self, change: f64, price: f64) -> String {
+        let decimals = self.decimals_for(price);
+        self.group(format!("{:+.*}", decimals, round_clean(change, decimals)))
+    }
+
+    /// Unsigned difference between two prices near `price`, e.g. the
+    /// bid/ask spread, at the instrument's precision: "0.03", not "0.0300".
+    pub fn spread(&self, spread: f64, price: f64) -> String {
+        let decimals = self.decimals_for(price);
+        self.group(format!("{:.*}", decimals, round_clean(spread.abs(), decimals)))
+    }
+
+    /// Price with its currency, e.g. "£74.12", falling back to "74.12 CHF"
+    /// for currencies without a well-known symbol. An empty code is treated
+    /// as USD.
+    pub fn money(&self, value: f64, currency: &str) -> String {
+        let code = if currency.is_empty() { "USD" } else { currency };
+        match currency_symbol(code) {
+            Some(symbol) => format!("{}{}", symbol, self.price(value)),
+            None => format!("{} {}", self.price(value), code),
+        }
+    }
+
+    /// Share volume with a K/M/B suffix, e.g. "41.23M" or "1,204.50B".
+    pub fn volume(&self, volume: u64) -> String {
+        let v = volume as f64;
+        let (scaled, suffix) = if v >= 1e9 {
+            (format!("{:.2}", v / 1e9), "B")
+        } else if v >= 1e6 {
+            (format!("{:.2}", v / 1e6), "M")
+        } else if v >= 1e3 {
+            (format!("{:.1}", v / 1e3), "K")
+        } else {
+            (volume.to_string(), "")
+        };
+        format!("{}{}", self.group(scaled), suffix)
+    }
+}
+/// Wall-clock time at `utc_offset` seconds from UTC, e.g. "14:32:07".
+pub fn format_clock(now: SystemTime, utc_offset: i32) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64) + utc_offset as i64;
+    let of_day = secs.rem_euclid(86_400);
+    format!("{:02}:{:02}:{:02}", of_day / 3600, of_day / 60 % 60, of_day % 60)
+}
+
+/// Calendar day at `utc_offset` seconds from UTC, e.g. "Mar 14".
+pub fn format_day(epoch_secs: i64, utc_offset: i32) -> String {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let (_, month, day) = civil_from_days((epoch_secs + utc_offset as i64).div_euclid(86_400));
+    format!("{} {}", MONTHS[(month - 1) as usize], day)
+}
+
+/// Days since 1970-01-01 to (year, month, day), and back below (Howard
+/// Hinnant's algorithms, with years starting in March).
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = (month + 2) % 12 + 1;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Seconds since the Unix epoch of a series timestamp, "2024-03-14T13:30:00Z".
+pub fn parse_utc(stamp: &str) -> Option<i64> {
+    let field = |range: std::ops::Range<usize>| stamp.get(range)?.parse::<i64>().ok();
+    let days = days_from_civil(field(0..4)?, field(5..7)?, field(8..10)?);
+    Some(days * 86_400 + field(11..13)? * 3600 + field(14..16)? * 60 + field(17..19)?)
+}
+
+/// Percent change, e.g. "1.23%" / "-0.45%". Undefined values (a zero
+/// previous close) render as "--%".
+pub fn format_pct(pct: f64) -> String {
+    if !pct.is_finite() {
+        return "--%".to_string();
+    }
+    format!("{:.2}%", round_clean(pct, 2))
+}
+
+pub fn currency_symbol(code: &str) -> Option<&'static str> {
+    match code {
+        "USD" => Some("$"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        "JPY" | "CNY" => Some("¥"),
+        "INR" => Some("₹"),
+        "KRW" => Some("₩"),
+        "CAD" => Some("C$"),
+        "AUD" => Some("A$"),
+        "HKD" => Some("HK$"),
+        _ => None,
+    }
+}
+
+/// Compact age, e.g. "4m" or "2h".
+pub fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    if minutes < 60 { format!("{}m", minutes) } else { format!("{}h", minutes / 60) }
+}
+
+/// A piece of a header line that can be left out when the line is too
+/// long: its spans, the separator before it when something precedes it, and
+/// how important it is.
+pub struct Segment {
+    priority: u8,
+    sep: &'static str,
+    spans: Vec<Span<'static>>,
+}
+
+impl Segment {
+    pub fn new(priority: u8, sep: &'static str, spans: Vec<Span<'static>>) -> Segment {
+        Segment { priority, sep, spans }
+    }
+}
+
+/// Joins `segments` into a line at most `room` columns wide, leaving out the
+/// least important (the later of equals first) until it fits. The most
+/// important one is always kept.
+pub fn fit_line(mut segments: Vec<Segment>, room: usize) -> Line<'static> {
+    let width = |segments: &[Segment]| -> usize {
+        segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let sep = if i == 0 { 0 } else { segment.sep.chars().count() };
+                sep + segment.spans.iter().map(Span::width).sum::<usize>()
+            })
+            .sum()
+    };
+    while segments.len() > 1 && width(&segments) > room {
+        if let Some((drop, _)) = segments.iter().enumerate().min_by_key(|(i, segment)| (segment.priority, Reverse(*i))) {
+            segments.remove(drop);
+        }
+    }
+    let mut spans = Vec::new();
+    for (i, segment) in segments.into_iter().enumerate() {
+        if i > 0 && !segment.sep.is_empty() {
+            spans.push(Span::raw(segment.sep));
+        }
+        spans.extend(segment.spans);
+    }
+    Line::from(spans)
+}
+
+/// The header's title and right-aligned clock for a border `room` columns
+/// wide, next to `fixed` columns of other titles. The clock outlasts the
+/// time of the last bar before it, which outlasts the symbol's name in
+/// `title`; the symbol always stays.
+pub fn fit_title(
+    title: String,
+    symbol: &str,
+    fixed: usize,
+    mut clock: Vec<Span<'static>>,
+    room: usize,
+) -> (String, Vec<Span<'static>>) {
+    let used = |title: &str, clock: &[Span]| title.chars().count() + fixed + clock.iter().map(Span::width).sum::<usize>();
+    if used(&title, &clock) > room && clock.len() > 1 {
+        clock.drain(..clock.len() - 1);
+    }
+    if used(&title, &clock) > room {
+        return (symbol.to_string(), clock);
+    }
+    (title, clock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_precision_follows_magnitude() {
+        let fmt = Formatter::default();
+        let cases = [
+            (231.456, "231.46"),
+            (1.0, "1.00"),
+            (0.4312, "0.4312"),
+            (0.01, "0.0100"),
+            (0.009876, "0.00988"),
+            (0.000213, "0.000213"),
+            (0.0000001234, "0.000000123"),
+            (0.0, "0.00"),
+            (67_123.456, "67,123"),
+            (9_999.994, "9,999.99"),
+        ];
+        for (price, expected) in cases {
+            assert_eq!(fmt.price(price), expected, "{}", price);
+        }
+    }
+
+    #[test]
+    fn fixed_decimals_and_no_separators_are_honored() {
+        let fmt = Formatter::new(Some(3), false, true);
+        assert_eq!(fmt.price(67_123.456_7), "67123.457");
+        assert_eq!(fmt.price(0.000213), "0.000");
+        assert_eq!(Formatter::new(Some(12), true, false).decimals, Some(MAX_DECIMALS));
+    }
+
+    #[test]
+    fn configured_precision_from_zero_to_four() {
+        let cases = [
+            (0, "1,234,568", "1234568"),
+            (1, "1,234,567.9", "1234567.9"),
+            (2, "1,234,567.89", "1234567.89"),
+            (3, "1,234,567.891", "1234567.891"),
+            (4, "1,234,567.8912", "1234567.8912"),
+        ];
+        for (decimals, grouped, plain) in cases {
+            assert_eq!(Formatter::new(Some(decimals), true, true).price(1_234_567.891_2), grouped);
+            assert_eq!(Formatter::new(Some(decimals), false, true).price(1_234_567.891_2), plain);
+        }
+        // Precision set in the config applies to the change and small prices too
+        assert_eq!(Formatter::new(Some(0), true, true).change(-1_499.6, 640_123.0), "-1,500");
+        assert_eq!(Formatter::new(Some(4), true, true).money(0.4, "EUR"), "€0.4000");
+    }
+
+    #[test]
+    fn spread_takes_the_quotes_precision() {
+        let fmt = Formatter::default();
+        assert_eq!(fmt.spread(231.45 - 231.42, 231.45), "0.03");
+        assert_eq!(fmt.spread(0.000215 - 0.000213, 0.000215), "0.000002");
+        assert_eq!(fmt.spread(640_150.0 - 640_100.0, 640_150.0), "50");
+        assert_eq!(fmt.spread(-1_250.0, 67_123.45), "1,250");
+    }
+
+    #[test]
+    fn separators_switch_off_for_volumes_too() {
+        let plain = Formatter::new(None, false, true);
+        assert_eq!(plain.volume(1_204_500_000_000), "1204.50B");
+        assert_eq!(plain.price(67_123.45), "67123");
+        assert_eq!(Formatter::new(None, false, false).price(67_123.45), "67123.45");
+    }
+
+    #[test]
+    fn volume_is_abbreviated() {
+        let fmt = Formatter::default();
+        let cases = [
+            (0, "0"),
+            (999, "999"),
+            (1_500, "1.5K"),
+            (312_456, "312.5K"),
+            (41_234_567, "41.23M"),
+            (2_500_000_000, "2.50B"),
+            (1_204_500_000_000, "1,204.50B"),
+        ];
+        for (volume, expected) in cases {
+            assert_eq!(fmt.volume(volume), expected, "{}", volume);
+        }
+    }
+
+    #[test]
+    fn change_is_signed_at_the_price_precision() {
+        let fmt = Formatter::default();
+        assert_eq!(fmt.change(2.84, 231.45), "+2.84");
+        assert_eq!(fmt.change(-2.84, 231.45), "-2.84");
+        assert_eq!(fmt.change(-3_120.5, 640_123.45), "-3,121");
+    }
+
+    #[test]
+    fn penny_stock_changes_keep_their_decimals() {
+        let fmt = Formatter::default();
+        assert_eq!(fmt.change(-0.0312, 0.4312), "-0.0312");
+        assert_eq!(fmt.change(0.000012, 0.000213), "+0.000012");
+    }
+
+    #[test]
+    fn change_that_rounds_to_nothing_is_not_negative() {
+        let fmt = Formatter::default();
+        assert_eq!(fmt.change(-0.001, 50.0), "+0.00");
+        assert_eq!(format_pct(-0.001), "0.00%");
+    }
+
+    #[test]
+    fn zero_previous_close_has_no_percent_change() {
+        // The fetcher reports 0% rather than dividing by a zero close
+        assert_eq!(format_pct(0.0), "0.00%");
+        assert_eq!(format_pct(f64::INFINITY), "--%");
+        assert_eq!(format_pct(f64::NAN), "--%");
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    fn segment(priority: u8, sep: &'static str, text: &str) -> Segment {
+        Segment::new(priority, sep, vec![Span::raw(text.to_string())])
+    }
+
+    /// "Price: 231.45 | Change: +1.20 (0.52%) | ER in 6d", most important first
+    fn price_line() -> Vec<Segment> {
+        vec![segment(9, "", "Price: 231.45"), segment(8, " | ", "Change: +1.20 (0.52%)"), segment(4, " | ", "ER in 6d")]
+    }
+
+    #[test]
+    fn fit_line_keeps_everything_that_fits() {
+        assert_eq!(text(&fit_line(price_line(), 80)), "Price: 231.45 | Change: +1.20 (0.52%) | ER in 6d");
+        assert_eq!(text(&fit_line(price_line(), 48)), "Price: 231.45 | Change: +1.20 (0.52%) | ER in 6d");
+    }
+
+    #[test]
+    fn fit_line_drops_the_least_important_first() {
+        assert_eq!(text(&fit_line(price_line(), 47)), "Price: 231.45 | Change: +1.20 (0.52%)");
+        assert_eq!(text(&fit_line(price_line(), 36)), "Price: 231.45");
+    }
+
+    #[test]
+    fn fit_line_drops_the_later_of_equals() {
+        let line = vec![segment(5, "", "O: 1.00"), segment(4, " ", "H: 2.00"), segment(4, " ", "L: 0.50")];
+        assert_eq!(text(&fit_line(line, 15)), "O: 1.00 H: 2.00");
+    }
+
+    #[test]
+    fn fit_line_keeps_the_most_important_even_if_it_overflows() {
+        assert_eq!(text(&fit_line(price_line(), 5)), "Price: 231.45");
+        assert_eq!(text(&fit_line(Vec::new(), 5)), "");
+    }
+
+    #[test]
+    fn fit_line_skips_the_separator_of_a_new_first_segment() {
+        let line = vec![segment(1, "", "extra"), segment(9, " | ", "Price: 231.45")];
+        assert_eq!(text(&fit_line(line, 13)), "Price: 231.45");
+    }
+
+    fn clock() -> Vec<Span<'static>> {
+        vec![Span::raw(" last bar 10:41"), Span::raw(" ·"), Span::raw(" 10:42:07 ET ")]
+    }
+
+    fn clock_text(clock: &[Span]) -> String {
+        clock.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn fit_title_keeps_everything_on_a_wide_header() {
+        let (title, clock) = fit_title("Stock Stats: AAPL | Apple Inc. (EQUITY)".to_string(), "AAPL", 0, clock(), 118);
+        assert_eq!(title, "Stock Stats: AAPL | Apple Inc. (EQUITY)");
+        assert_eq!(clock_text(&clock), " last bar 10:41 · 10:42:07 ET ");
+    }
+
+    #[test]
+    fn fit_title_drops_the_last_bar_then_the_name() {
+        let full = "Stock Stats: AAPL | Apple Inc. (EQUITY)";
+        let (title, clock) = fit_title(full.to_string(), "AAPL", 0, clock(), 60);
+        assert_eq!(title, full);
+        assert_eq!(clock_text(&clock), " 10:42:07 ET ");
+
+        let (title, clock) = fit_title(full.to_string(), "AAPL", 0, self::clock(), 38);
+        assert_eq!(title, "AAPL");
+        assert_eq!(clock_text(&clock), " 10:42:07 ET ");
+    }
+
+    #[test]
+    fn fit_title_counts_the_other_titles() {
+        let (title, _) = fit_title("AAPL | Apple Inc.".to_string(), "AAPL", 23, vec![Span::raw(" 10:42:07 ET ")], 50);
+        assert_eq!(title, "AAPL");
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::{Duration, Instant};
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
+use tracing::warn;
+
+use crate::{
+    fetch::{decode_image, FetchRequest, Fetcher},
+    indicators::{self, Versus},
+    model::StockStats,
+    theme::{Accent, ChartPalette, Theme},
+};
+
+/// One chart in the grid dashboard.
+pub struct GridCell {
+    pub symbol: String,
+    /// Colors the title and the chart line, so cells are told apart at a glance
+    pub accent: Accent,
+    pub stats: StockStats,
+    /// Beta and correlation against the first cell's symbol, for the others
+    pub versus: Option<Versus>,
+    pub image_protocol: Option<StatefulProtocol>,
+    // Inner chart area at the last draw
+    pub area_size: (u16, u16),
+    fetched_size: (u16, u16),
+    fetcher: Fetcher,
+}
+
+/// `--grid`: two to four symbols side by side, each with its own percent
+/// chart, refreshed together. Each cell fetches independently, so one slow
+/// or failing symbol doesn't hold up the others. The first symbol is the
+/// benchmark the others' beta and correlation are measured against.
+pub struct Grid {
+    pub cells: Vec<GridCell>,
+    pub focus: usize,
+    last_fetch: Option<Instant>,
+}
+
+impl Grid {
+    pub fn new(symbols: &[String], theme: &Theme) -> Grid {
+        let accents = theme.accents.for_symbols(symbols, &theme.chart.line);
+        let cells = symbols
+            .iter()
+            .zip(accents)
+            .map(|(symbol, accent)| GridCell {
+                symbol: symbol.clone(),
+                accent,
+                stats: StockStats::default(),
+                versus: None,
+                image_protocol: None,
+                area_size: (0, 0),
+                fetched_size: (0, 0),
+                fetcher: Fetcher::new(),
+            })
+            .collect();
+        Grid { cells, focus: 0, last_fetch: None }
+    }
+
+    pub fn focused(&self) -> &GridCell {
+        &self.cells[self.focus]
+    }
+
+    /// Splits `area` into two columns: one row for two symbols, two rows for
+    /// three or four.
+    pub fn cell_areas(&self, area: Rect) -> Vec<Rect> {
+        let rows = self.cells.len().div_ceil(2) as u32;
+        let row_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints((0..rows).map(|_| Constraint::Ratio(1, rows)))
+            .split(area);
+        row_areas
+            .iter()
+            .flat_map(|row| {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                    .split(*row)
+                    .to_vec()
+            })
+            .take(self.cells.len())
+            .collect()
+    }
+
+    /// Moves the focus by whole cells, staying put at the edges.
+    pub fn move_focus(&mut self, dx: isize, dy: isize) {
+        let (col, row) = ((self.focus % 2) as isize + dx, (self.focus / 2) as isize + dy);
+        if (0..2).contains(&col) && row >= 0 {
+            let index = (row * 2 + col) as usize;
+            if index < self.cells.len() {
+                self.focus = index;
+            }
+        }
+    }
+
+    /// Applies finished fetches and, when `may_start`, starts a fetch for
+    /// every cell once the refresh interval has passed or a settled resize
+    /// left its chart the wrong size. `template` carries the shared chart
+    /// settings. Returns whether anything visible changed.
+    pub fn poll(
+        &mut self,
+        template: &FetchRequest,
+        refresh_interval: Duration,
+        may_start: bool,
+        resize_settled: bool,
+        picker: &Picker,
+    ) -> bool {
+        let mut changed = false;
+        for cell in &mut self.cells {
+            let Some(outcome) = cell.fetcher.try_recv() else { continue };
+            changed = true;
+            match outcome.result {
+                Ok(stats) => {
+                    cell.fetched_size = outcome.area_size;
+                    if stats.no_trades {
+                        cell.image_protocol = None;
+                    }
+                    if let Some(ref data) = stats.image_data
+                        && let Some(img) = decode_image(data)
+                    {
+                        cell.image_protocol = Some(picker.new_resize_protocol(img));
+                    }
+                    cell.stats = stats;
+                }
+                Err(e) => {
+                    warn!(symbol = %cell.symbol, error = %e, "grid fetch failed");
+                    // Keep showing older data; only a cell with nothing yet shows the error
+                    if cell.stats.symbol.is_empty() {
+                        cell.stats.error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+
+        if changed {
+            self.compare_to_first();
+        }
+
+        if !may_start {
+            return changed;
+        }
+        let due = self.last_fetch.is_none_or(|at| at.elapsed() >= refresh_interval);
+        if due {
+            self.last_fetch = Some(Instant::now());
+        }
+        for cell in &mut self.cells {
+            let resized = cell.area_size != cell.fetched_size && cell.area_size.0 > 0 && resize_settled;
+            if (due || resized) && !cell.fetcher.is_busy() {
+                let (w, h) = cell.area_size;
+                let req = FetchRequest {
+                    symbol: cell.symbol.clone(),
+                    width: if w > 0 { w } else { 50 },
+                    height: if h > 0 { h } else { 20 },
+                    indicators: Default::default(),
+                    price_view: false,
+                    chart_type: "line".to_string(),
+                    compact: true,
+                    levels: Vec::new(),
+                    observed: Vec::new(),
+                    palette: ChartPalette { line: cell.accent.hex.clone(), ..template.palette.clone() },
+                    ..template.clone()
+                };
+                cell.fetcher.spawn(req, (w, h));
+            }
+        }
+        changed
+    }
+
+    /// Measures every cell after the first against it, from the latest bars.
+    fn compare_to_first(&mut self) {
+        let Some((first, rest)) = self.cells.split_first_mut() else { return };
+        for cell in rest {
+            cell.versus = match (&cell.stats.series, &first.stats.series) {
+                (Some(series), Some(benchmark)) => indicators::versus(series, benchmark),
+                _ => None,
+            };
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        for cell in &mut self.cells {
+            cell.fetcher.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Series;
+
+    fn minutes(prices: &[f64]) -> Series {
+        Series {
+            utc: (0..prices.len()).map(|i| format!("2026-03-02T14:{:02}:00Z", 30 + i)).collect(),
+            price: prices.to_vec(),
+            ..Series::default()
+        }
+    }
+
+    #[test]
+    fn cells_after_the_first_are_measured_against_it() {
+        let symbols = ["SPY", "QQQ", "AAPL"].map(String::from);
+        let mut grid = Grid::new(&symbols, &Theme::by_name("default").unwrap());
+        let spy: Vec<f64> = (0..15).map(|i| 500.0 + [0.0, 1.0, -0.5][i % 3] + i as f64 * 0.1).collect();
+        grid.cells[0].stats.series = Some(minutes(&spy));
+        grid.cells[1].stats.series = Some(minutes(&spy));
+        grid.compare_to_first();
+
+        assert_eq!(grid.cells[0].versus, None);
+        let qqq = grid.cells[1].versus.unwrap();
+        assert!((qqq.beta - 1.0).abs() < 1e-9 && (qqq.correlation - 1.0).abs() < 1e-9, "{:?}", qqq);
+        // Not fetched yet
+        assert_eq!(grid.cells[2].versus, None);
+    }
+}
@@ -0,0 +1,221 @@
+//! Modes that run without a TUI: `--once`, `--export`, `--stream` and
+//! `--daemon`.
+
+use std::{
+    collections::HashSet,
+    error::Error,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::{
+    alert::{self, Alert},
+    cli::Cli,
+    config::Config,
+    export,
+    fetch::{fetch_all, run_fetch, suggested_interval, FetchRequest, DEFAULT_SLIDING_MINUTES},
+    format::{format_clock, format_pct},
+    model::StockStats,
+    notify,
+    theme::Theme,
+};
+
+/// Fetch settings for the one-shot modes, which run without a TUI.
+fn one_shot_request(cli: &Cli, theme: &Theme, config: &Config, symbol: &str, chart: bool) -> FetchRequest {
+    FetchRequest {
+        symbol: symbol.to_string(),
+        width: if chart { 100 } else { 0 },
+        height: if chart { 40 } else { 0 },
+        indicators: HashSet::new(),
+        use_24h: true,
+        price_view: true,
+        period: cli.range.clone(),
+        interval: suggested_interval(&cli.range).to_string(),
+        chart_type: "line".to_string(),
+        palette: theme.chart.clone(),
+        change_grades: theme.grades,
+        compact: false,
+        gridlines: true,
+        line_style: config.line_style.unwrap_or_default(),
+        smoothing: 0,
+        y_scale: config.y_scale.unwrap_or_default(),
+        x_axis: config.x_axis.unwrap_or_default(),
+        sliding_minutes: config.sliding_minutes.unwrap_or(DEFAULT_SLIDING_MINUTES),
+        log_scale: false,
+        pre_market: cli.pre_market,
+        vs_open: false,
+        direction_colors: false,
+        prev_day: false,
+        levels: Vec::new(),
+        observed: Vec::new(),
+        date: None,
+        formatter: config.formatter(),
+        provider: cli.provider,
+        chart,
+    }
+}
+
+/// `--once`: fetches every ticker's quote (no chart) at once and prints them
+/// to stdout in order, one line per ticker, as text or JSON. Failed symbols
+/// are reported on stderr without holding up the rest.
+pub fn print_quotes(cli: &Cli, theme: &Theme, config: &Config) -> Result<(), Box<dyn Error>> {
+    let reqs: Vec<FetchRequest> = cli.tickers.iter().map(|symbol| one_shot_request(cli, theme, config, symbol, false)).collect();
+    let fmt = config.formatter();
+    let mut failed = 0;
+    for (symbol, result) in cli.tickers.iter().zip(fetch_all(&reqs)) {
+        let stats = match result {
+            Ok(stats) => stats,
+            Err(e) if cli.tickers.len() == 1 => return Err(format!("{}: {}", symbol, e).into()),
+            Err(e) => {
+                eprintln!("{}: {}", symbol, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if cli.json {
+            println!("{}", serde_json::to_string(&stats)?);
+        } else {
+            println!(
+                "{} {} {} ({})",
+                stats.symbol,
+                fmt.money(stats.price, &stats.currency),
+                fmt.change(stats.change, stats.price),
+                format_pct(stats.pct_change)
+            );
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} symbols failed", failed, cli.tickers.len()).into());
+    }
+    Ok(())
+}
+
+/// `--export`: fetches the first ticker's chart data and writes it to a CSV.
+pub fn export_once(cli: &Cli, theme: &Theme, config: &Config, template: &str) -> Result<(), Box<dyn Error>> {
+    let symbol = &cli.tickers[0];
+    // The series comes with the chart, so render one and discard it
+    let stats = run_fetch(&one_shot_request(cli, theme, config, symbol, true), &Mutex::new(None))?;
+    if let Some(err) = stats.error {
+        return Err(format!("{}: {}", symbol, err).into());
+    }
+    let series = stats.series.unwrap_or_default();
+    let (path, overwrite) = export::expand_path(template, &stats.symbol, &cli.range, &series);
+    export::write_csv(&path, &series, overwrite, config.formatter().decimals)?;
+    println!("Wrote {} rows to {}", series.len(), path.display());
+    Ok(())
+}
+
+/// One `--stream --json-lines` record: the quote plus when it was fetched.
+#[derive(Serialize)]
+struct StreamLine<'a> {
+    /// Seconds since the Unix epoch
+    fetched_at: u64,
+    #[serde(flatten)]
+    stats: &'a StockStats,
+}
+
+/// `--stream`, or stdout not being a terminal: prints a line per ticker on
+/// every refresh instead of drawing the TUI, as text or JSON. Ctrl+C, SIGTERM
+/// or the reader closing the pipe end it with a zero exit code.
+pub fn stream_quotes(cli: &Cli, theme: &Theme, config: &Config) -> Result<(), Box<dyn Error>> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&stop))?;
+    }
+    let interval = Duration::from_secs(cli.refresh);
+    let fmt = config.formatter();
+    let reqs: Vec<FetchRequest> = cli.tickers.iter().map(|symbol| one_shot_request(cli, theme, config, symbol, false)).collect();
+    let mut out = io::stdout().lock();
+    while !stop.load(Ordering::Relaxed) {
+        let started = Instant::now();
+        let results = fetch_all(&reqs);
+        // Ctrl+C reaches the fetchers too, so their failures aren't worth reporting
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        for (symbol, result) in cli.tickers.iter().zip(results) {
+            let stats = match result {
+                Ok(stats) => stats,
+                Err(e) => {
+                    eprintln!("{}: {}", symbol, e);
+                    continue;
+                }
+            };
+            let now = SystemTime::now();
+            let line = if cli.json_lines {
+                let fetched_at = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+                serde_json::to_string(&StreamLine { fetched_at, stats: &stats })?
+            } else {
+                let clock = match stats.utc_offset {
+                    Some(offset) => format!("{} ET", format_clock(now, offset)),
+                    None => format!("{} UTC", format_clock(now, 0)),
+                };
+                format!(
+                    "{} {} {} {} ({}) vol {}",
+                    clock,
+                    stats.symbol,
+                    fmt.money(stats.price, &stats.currency),
+                    fmt.change(stats.change, stats.price),
+                    format_pct(stats.pct_change),
+                    fmt.volume(stats.volume)
+                )
+            };
+            match writeln!(out, "{}", line) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+        while !stop.load(Ordering::Relaxed) && started.elapsed() < interval {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+    Ok(())
+}
+
+/// `--daemon`: polls the first ticker every refresh interval without a
+/// terminal UI and prints each `--alert` trigger as one line on stdout, which
+/// is line-buffered so it suits pipes and service logs. Exits after the first
+/// trigger unless `--keep-alive` is given.
+pub fn run_daemon(cli: &Cli, theme: &Theme, config: &Config) -> Result<(), Box<dyn Error>> {
+    let symbol = &cli.tickers[0];
+    let mut alerts: Vec<Alert> = cli.alerts.iter().map(|rule| Alert::new(symbol, *rule, config.alerts.hysteresis)).collect();
+    let fmt = config.formatter();
+    let notify = config.notifications.enabled && !cli.no_notify;
+    let req = one_shot_request(cli, theme, config, symbol, false);
+    let interval = Duration::from_secs(cli.refresh);
+    loop {
+        let started = Instant::now();
+        // Failures are reported and retried; the daemon is meant to run unattended
+        match run_fetch(&req, &Mutex::new(None)) {
+            Ok(stats) if stats.error.is_none() => {
+                let triggered = alert::check_all(&mut alerts, &stats, &fmt);
+                for trigger in &triggered {
+                    match stats.utc_offset {
+                        Some(offset) => println!("{} ET {}", format_clock(SystemTime::now(), offset), trigger.message),
+                        None => println!("{}", trigger.message),
+                    }
+                    if let Err(e) = alert::append_log(trigger) {
+                        eprintln!("{}", e);
+                    }
+                    if notify && let Err(e) = notify::deliver("stock-tui alert", &trigger.message) {
+                        eprintln!("{}", e);
+                    }
+                }
+                if !triggered.is_empty() && !cli.keep_alive {
+                    return Ok(());
+                }
+            }
+            Ok(stats) => eprintln!("{}: {}", symbol, stats.error.unwrap_or_default()),
+            Err(e) => eprintln!("{}: {}", symbol, e),
+        }
+        thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
@@ -0,0 +1,56 @@
+/// Most symbols remembered; the oldest are dropped first.
+const MAX_ENTRIES: usize = 20;
+
+/// Symbols viewed this session, oldest first, with a cursor for going back
+/// and forward like a browser. Visiting a symbol after going back drops the
+/// entries ahead of the cursor.
+pub struct History {
+    entries: Vec<String>,
+    position: usize,
+}
+
+impl History {
+    pub fn new(first: &str) -> History {
+        History { entries: vec![first.to_string()], position: 0 }
+    }
+
+    pub fn visit(&mut self, symbol: &str) {
+        if self.entries.get(self.position).is_some_and(|current| current == symbol) {
+            return;
+        }
+        self.entries.truncate(self.position + 1);
+        self.entries.push(symbol.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len() - 1;
+    }
+
+    pub fn back(&mut self) -> Option<&str> {
+        self.position = self.position.checked_sub(1)?;
+        Some(&self.entries[self.position])
+    }
+
+    pub fn forward(&mut self) -> Option<&str> {
+        if self.position + 1 >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        Some(&self.entries[self.position])
+    }
+
+    /// Each symbol once: the current one, then going back, then any ahead.
+    pub fn recent(&self) -> Vec<&str> {
+        let mut recent: Vec<&str> = Vec::new();
+        for symbol in self.entries[..=self.position].iter().rev().chain(&self.entries[self.position + 1..]) {
+            if !recent.contains(&symbol.as_str()) {
+                recent.push(symbol);
+            }
+        }
+        recent
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.entries.iter().any(|entry| entry == symbol)
+    }
+}
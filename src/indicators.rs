@@ -0,0 +1,139 @@
+//! Readouts computed from fetched series, beside the chart indicators the
+//! Python plugins in `indicators/` draw.
+
+use std::collections::HashMap;
+
+use crate::export::Series;
+
+/// Fewest paired returns a beta or correlation is shown for; a handful of
+/// bars after the open would say more about noise than the relationship.
+const MIN_PAIRED_RETURNS: usize = 10;
+
+/// How a symbol moved against a benchmark over the bars both have.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Versus {
+    /// The symbol's return per unit of the benchmark's
+    pub beta: f64,
+    /// Pearson correlation of the two sets of returns, -1 to 1
+    pub correlation: f64,
+    /// Returns the two were computed over
+    pub returns: usize,
+}
+
+/// Realized beta and correlation of `series` against `benchmark`, from bar
+/// to bar returns. Bars are paired by their UTC time, and a bar missing (or
+/// without a price) on either side is skipped, so each return spans the same
+/// stretch of time for both. None with too few pairs, or when either side
+/// didn't move at all.
+pub fn versus(series: &Series, benchmark: &Series) -> Option<Versus> {
+    let benchmark_prices: HashMap<&str, f64> = benchmark
+        .utc
+        .iter()
+        .zip(&benchmark.price)
+        .filter(|(_, price)| price.is_finite() && **price > 0.0)
+        .map(|(utc, price)| (utc.as_str(), *price))
+        .collect();
+    let paired: Vec<(f64, f64)> = series
+        .utc
+        .iter()
+        .zip(&series.price)
+        .filter(|(_, price)| price.is_finite() && **price > 0.0)
+        .filter_map(|(utc, price)| benchmark_prices.get(utc.as_str()).map(|bench| (*price, *bench)))
+        .collect();
+    let returns: Vec<(f64, f64)> = paired.windows(2).map(|w| (w[1].0 / w[0].0 - 1.0, w[1].1 / w[0].1 - 1.0)).collect();
+    if returns.len() < MIN_PAIRED_RETURNS {
+        return None;
+    }
+
+    let n = returns.len() as f64;
+    let mean_s = returns.iter().map(|r| r.0).sum::<f64>() / n;
+    let mean_b = returns.iter().map(|r| r.1).sum::<f64>() / n;
+    let (mut cov, mut var_s, mut var_b) = (0.0, 0.0, 0.0);
+    for (s, b) in &returns {
+        cov += (s - mean_s) * (b - mean_b);
+        var_s += (s - mean_s).powi(2);
+        var_b += (b - mean_b).powi(2);
+    }
+    if var_s <= 0.0 || var_b <= 0.0 {
+        return None;
+    }
+    Some(Versus { beta: cov / var_b, correlation: cov / (var_s * var_b).sqrt(), returns: returns.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A series of one-minute bars from 09:30 UTC, compounding `returns`
+    /// from 100.
+    fn bars(returns: &[f64]) -> Series {
+        let mut price = 100.0;
+        let mut prices = vec![price];
+        for r in returns {
+            price *= 1.0 + r;
+            prices.push(price);
+        }
+        Series {
+            utc: (0..prices.len()).map(|i| format!("2026-03-02T09:{:02}:00Z", 30 + i)).collect(),
+            price: prices,
+            ..Series::default()
+        }
+    }
+
+    /// Benchmark returns with some shape to them.
+    const MARKET: [f64; 12] = [0.001, -0.002, 0.0015, 0.003, -0.001, 0.0005, -0.0025, 0.002, 0.001, -0.0005, 0.0015, -0.001];
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn twice_the_moves_is_beta_two_and_perfectly_correlated() {
+        let doubled: Vec<f64> = MARKET.iter().map(|r| r * 2.0).collect();
+        let v = versus(&bars(&doubled), &bars(&MARKET)).unwrap();
+        assert!(close(v.beta, 2.0), "{:?}", v);
+        assert!(close(v.correlation, 1.0), "{:?}", v);
+        assert_eq!(v.returns, MARKET.len());
+    }
+
+    #[test]
+    fn opposite_moves_are_inversely_correlated() {
+        let inverse: Vec<f64> = MARKET.iter().map(|r| -r).collect();
+        let v = versus(&bars(&inverse), &bars(&MARKET)).unwrap();
+        assert!(close(v.beta, -1.0), "{:?}", v);
+        assert!(close(v.correlation, -1.0), "{:?}", v);
+    }
+
+    #[test]
+    fn unrelated_moves_are_uncorrelated() {
+        // Over every four bars each pattern's ups and downs cancel against
+        // the other's
+        let market = [0.001, -0.001, 0.001, -0.001].repeat(4);
+        let noise = [0.002, 0.002, -0.002, -0.002].repeat(4);
+        let v = versus(&bars(&noise), &bars(&market)).unwrap();
+        assert!(v.correlation.abs() < 0.01, "{:?}", v);
+        assert!(v.beta.abs() < 0.02, "{:?}", v);
+    }
+
+    #[test]
+    fn bars_missing_on_either_side_are_skipped() {
+        let doubled: Vec<f64> = MARKET.iter().map(|r| r * 2.0).collect();
+        let mut symbol = bars(&doubled);
+        let mut market = bars(&MARKET);
+        symbol.utc.remove(3);
+        symbol.price.remove(3);
+        market.price[7] = f64::NAN;
+        let v = versus(&symbol, &market).unwrap();
+        // Returns across a gap still span the same minutes on both sides,
+        // only compounded over two of them
+        assert!(v.correlation > 0.9999, "{:?}", v);
+        assert!((v.beta - 2.0).abs() < 0.01, "{:?}", v);
+        assert_eq!(v.returns, MARKET.len() - 2);
+    }
+
+    #[test]
+    fn too_few_or_flat_bars_show_nothing() {
+        assert_eq!(versus(&bars(&MARKET[..5]), &bars(&MARKET[..5])), None);
+        assert_eq!(versus(&bars(&MARKET), &bars(&[0.0; 12])), None);
+    }
+}
@@ -0,0 +1,394 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Everything a key can do from the main screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    OpenTicker,
+    OpenSettings,
+    OpenCommandLine,
+    HistoryBack,
+    HistoryForward,
+    PreviousDay,
+    NextDay,
+    ShowHistory,
+    ShowAlertLog,
+    ShowNews,
+    ShowPortfolio,
+    ShowLevels,
+    ShowHelp,
+    ShowGrid,
+    ToggleFullscreen,
+    ToggleDebug,
+    ToggleBaseline,
+    ToggleTape,
+    ToggleFooter,
+    ToggleRangeGauge,
+    ToggleBillboard,
+    ToggleDirectionColors,
+    CycleLineStyle,
+    CycleSmoothing,
+    ToggleYScale,
+    CycleXAxis,
+    ToggleLogScale,
+    CycleInterval,
+    TogglePreviousDay,
+    ToggleObserved,
+    Refresh,
+    ToggleLowPower,
+    OpenInBrowser,
+    CopyQuote,
+    ExportCsv,
+    Snapshot,
+    ReplayPause,
+    ReplayBack,
+    ReplayForward,
+}
+
+pub struct KeyBinding {
+    pub section: &'static str,
+    pub keys: &'static [(KeyCode, KeyModifiers)],
+    pub label: &'static str,
+    pub description: &'static str,
+    pub action: Action,
+}
+
+pub const SECTIONS: [&str; 4] = ["Navigation", "Chart", "Data", "Replay"];
+
+/// Main-screen key bindings. Both the key handler and the help popup read
+/// this table, so adding a binding here is all it takes to document it.
+pub const KEYMAP: &[KeyBinding] = &[
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char('o'), KeyModifiers::CONTROL)],
+        label: "Ctrl + o",
+        description: "Open a ticker",
+        action: Action::OpenTicker,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char('s'), KeyModifiers::CONTROL)],
+        label: "Ctrl + s",
+        description: "Settings menu",
+        action: Action::OpenSettings,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char('['), KeyModifiers::NONE)],
+        label: "[",
+        description: "Back to the previous symbol",
+        action: Action::HistoryBack,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char(']'), KeyModifiers::NONE)],
+        label: "]",
+        description: "Forward again",
+        action: Action::HistoryForward,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::PageUp, KeyModifiers::NONE)],
+        label: "PgUp",
+        description: "The previous trading day's chart",
+        action: Action::PreviousDay,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::PageDown, KeyModifiers::NONE)],
+        label: "PgDn",
+        description: "The next trading day's chart, then live again",
+        action: Action::NextDay,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char('H'), KeyModifiers::NONE)],
+        label: "H",
+        description: "Recent symbols",
+        action: Action::ShowHistory,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char(':'), KeyModifiers::NONE)],
+        label: ":",
+        description: "Command line (see Commands below)",
+        action: Action::OpenCommandLine,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char('?'), KeyModifiers::NONE)],
+        label: "?",
+        description: "Show this help",
+        action: Action::ShowHelp,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Backspace, KeyModifiers::NONE)],
+        label: "Backspace",
+        description: "Back to the grid (with --grid)",
+        action: Action::ShowGrid,
+    },
+    KeyBinding {
+        section: "Navigation",
+        keys: &[(KeyCode::Char('q'), KeyModifiers::NONE), (KeyCode::Esc, KeyModifiers::NONE)],
+        label: "q / Esc",
+        description: "Quit",
+        action: Action::Quit,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('f'), KeyModifiers::NONE)],
+        label: "f",
+        description: "Toggle full-screen chart",
+        action: Action::ToggleFullscreen,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('d'), KeyModifiers::CONTROL)],
+        label: "Ctrl + d",
+        description: "Toggle fetch debug overlay",
+        action: Action::ToggleDebug,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('b'), KeyModifiers::NONE)],
+        label: "b",
+        description: "Measure change from previous close / today's open",
+        action: Action::ToggleBaseline,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('t'), KeyModifiers::NONE)],
+        label: "t",
+        description: "Toggle market index strip",
+        action: Action::ToggleTape,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('S'), KeyModifiers::NONE)],
+        label: "S",
+        description: "Toggle session statistics under the chart",
+        action: Action::ToggleFooter,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('g'), KeyModifiers::NONE)],
+        label: "g",
+        description: "Toggle the day's range gauge under the header",
+        action: Action::ToggleRangeGauge,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('m'), KeyModifiers::NONE)],
+        label: "m",
+        description: "Color the line green/red by direction",
+        action: Action::ToggleDirectionColors,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('p'), KeyModifiers::NONE)],
+        label: "p",
+        description: "Cycle line style: line / points / line+points",
+        action: Action::CycleLineStyle,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('a'), KeyModifiers::NONE)],
+        label: "a",
+        description: "Smooth the line: off / 3 / 5 / 9-bar average",
+        action: Action::CycleSmoothing,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('z'), KeyModifiers::NONE)],
+        label: "z",
+        description: "Y axis: fit the data, or keep the baseline in view",
+        action: Action::ToggleYScale,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('x'), KeyModifiers::NONE)],
+        label: "x",
+        description: "1-day time axis: whole session / session so far / sliding window",
+        action: Action::CycleXAxis,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('L'), KeyModifiers::NONE)],
+        label: "L",
+        description: "Log price axis (price view)",
+        action: Action::ToggleLogScale,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('i'), KeyModifiers::NONE)],
+        label: "i",
+        description: "Next bar size the range allows (1m, 5m, 1h, ...)",
+        action: Action::CycleInterval,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('B'), KeyModifiers::NONE)],
+        label: "B",
+        description: "Show the price in large digits instead of the chart",
+        action: Action::ToggleBillboard,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('y'), KeyModifiers::NONE)],
+        label: "y",
+        description: "Overlay the previous day on the 1d chart",
+        action: Action::TogglePreviousDay,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('O'), KeyModifiers::NONE)],
+        label: "O",
+        description: "Overlay the prices seen at each fetch on the 1d chart",
+        action: Action::ToggleObserved,
+    },
+    KeyBinding {
+        section: "Chart",
+        keys: &[(KeyCode::Char('l'), KeyModifiers::NONE)],
+        label: "l",
+        description: "Price levels marked on this symbol",
+        action: Action::ShowLevels,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('r'), KeyModifiers::NONE)],
+        label: "r",
+        description: "Refresh now",
+        action: Action::Refresh,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('w'), KeyModifiers::NONE)],
+        label: "w",
+        description: "Low-power mode: wake every 10s, coarse countdown and clock",
+        action: Action::ToggleLowPower,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('o'), KeyModifiers::NONE)],
+        label: "o",
+        description: "Open quote page in browser",
+        action: Action::OpenInBrowser,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('c'), KeyModifiers::NONE)],
+        label: "c",
+        description: "Copy quote to clipboard",
+        action: Action::CopyQuote,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('e'), KeyModifiers::NONE)],
+        label: "e",
+        description: "Export chart data to CSV",
+        action: Action::ExportCsv,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('E'), KeyModifiers::NONE)],
+        label: "E",
+        description: "Save the screen as text",
+        action: Action::Snapshot,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('A'), KeyModifiers::NONE)],
+        label: "A",
+        description: "Alerts triggered today",
+        action: Action::ShowAlertLog,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('n'), KeyModifiers::NONE)],
+        label: "n",
+        description: "Latest news headlines",
+        action: Action::ShowNews,
+    },
+    KeyBinding {
+        section: "Data",
+        keys: &[(KeyCode::Char('P'), KeyModifiers::NONE)],
+        label: "P",
+        description: "Portfolio: holdings with live P/L",
+        action: Action::ShowPortfolio,
+    },
+    KeyBinding {
+        section: "Replay",
+        keys: &[(KeyCode::Char(' '), KeyModifiers::NONE)],
+        label: "Space",
+        description: "Pause / resume playback",
+        action: Action::ReplayPause,
+    },
+    KeyBinding {
+        section: "Replay",
+        keys: &[(KeyCode::Left, KeyModifiers::NONE)],
+        label: "Left",
+        description: "Previous snapshot",
+        action: Action::ReplayBack,
+    },
+    KeyBinding {
+        section: "Replay",
+        keys: &[(KeyCode::Right, KeyModifiers::NONE)],
+        label: "Right",
+        description: "Next snapshot",
+        action: Action::ReplayForward,
+    },
+];
+
+/// Finds the action bound to `key`. Bindings without modifiers ignore Shift
+/// (it's already reflected in the character) but not Ctrl or Alt.
+pub fn lookup(key: &KeyEvent) -> Option<Action> {
+    let significant = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+    KEYMAP
+        .iter()
+        .find(|binding| {
+            binding
+                .keys
+                .iter()
+                .any(|(code, modifiers)| *code == key.code && *modifiers == significant)
+        })
+        .map(|binding| binding.action)
+}
+
+/// The key shown for `action`, for hints outside the help popup.
+pub fn label_for(action: Action) -> &'static str {
+    KEYMAP.iter().find(|binding| binding.action == action).map_or("?", |binding| binding.label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn looks_up_plain_and_control_keys() {
+        assert_eq!(lookup(&key(KeyCode::Char('q'), KeyModifiers::NONE)), Some(Action::Quit));
+        assert_eq!(lookup(&key(KeyCode::Char('o'), KeyModifiers::CONTROL)), Some(Action::OpenTicker));
+    }
+
+    #[test]
+    fn shift_is_ignored_but_control_and_alt_are_not() {
+        assert_eq!(lookup(&key(KeyCode::Char('?'), KeyModifiers::SHIFT)), Some(Action::ShowHelp));
+        assert_eq!(lookup(&key(KeyCode::Char('q'), KeyModifiers::CONTROL)), None);
+        assert_eq!(lookup(&key(KeyCode::Char('q'), KeyModifiers::ALT)), None);
+    }
+
+    #[test]
+    fn no_key_is_bound_twice() {
+        let mut seen = Vec::new();
+        for binding in KEYMAP {
+            for key in binding.keys {
+                assert!(!seen.contains(key), "{:?} is bound twice", key);
+                seen.push(*key);
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs, io,
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logging::state_dir;
+
+/// A price marked on a symbol's chart, e.g. a support level or an order's
+/// limit price.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: f64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+}
+
+impl Level {
+    /// Parses `:level` arguments: a positive price, optionally with a `$`,
+    /// then an optional label, e.g. "225.50 buy-zone".
+    pub fn parse(s: &str) -> Result<Level, String> {
+        let s = s.trim();
+        let (price, label) = s.split_once(char::is_whitespace).map_or((s, ""), |(price, label)| (price, label.trim()));
+        match price.trim_start_matches('$').replace(',', "").parse::<f64>() {
+            Ok(price) if price.is_finite() && price > 0.0 => Ok(Level { price, label: label.to_string() }),
+            _ => Err(format!("'{}': expected a price, e.g. 225.50 buy-zone", s)),
+        }
+    }
+}
+
+/// Levels per symbol, kept in `levels.json` in the state directory so they
+/// survive restarts. Every change is written straight away.
+#[derive(Debug, Default)]
+pub struct Levels {
+    by_symbol: BTreeMap<String, Vec<Level>>,
+}
+
+/// `levels.json` in the state directory, next to the alert log.
+pub fn path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("levels.json"))
+}
+
+impl Levels {
+    /// Reads the levels file; a missing one is just empty.
+    pub fn load() -> Result<Levels, Box<dyn Error>> {
+        let Some(path) = path() else { return Ok(Levels::default()) };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Levels::default()),
+            Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+        };
+        let by_symbol = serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Levels { by_symbol })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = path().ok_or("can't locate the levels file: HOME is not set")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&self.by_symbol)?;
+        fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// `symbol`'s levels, highest first.
+    pub fn get(&self, symbol: &str) -> &[Level] {
+        self.by_symbol.get(symbol).map_or(&[], Vec::as_slice)
+    }
+
+    /// Marks `level` on `symbol`, replacing any level at the same price.
+    pub fn add(&mut self, symbol: &str, level: Level) -> Result<(), Box<dyn Error>> {
+        let levels = self.by_symbol.entry(symbol.to_string()).or_default();
+        levels.retain(|existing| existing.price != level.price);
+        levels.push(level);
+        levels.sort_by(|a, b| b.price.total_cmp(&a.price));
+        self.save()
+    }
+
+    /// Removes `symbol`'s level at `index` in `get`'s order.
+    pub fn remove(&mut self, symbol: &str, index: usize) -> Result<(), Box<dyn Error>> {
+        let Some(levels) = self.by_symbol.get_mut(symbol) else { return Ok(()) };
+        if index < levels.len() {
+            levels.remove(index);
+        }
+        if levels.is_empty() {
+            self.by_symbol.remove(symbol);
+        }
+        self.save()
+    }
+}
@@ -0,0 +1,32 @@
+//! The terminal stock viewer's data, settings, state and drawing: the `tmp2`
+//! binary only sets up the terminal and runs the app, and anything else
+//! (tests, headless modes) can use the pieces directly.
+
+pub mod alert;
+pub mod api;
+pub mod app;
+pub mod billboard;
+pub mod cli;
+pub mod clock;
+pub mod command;
+pub mod config;
+pub mod export;
+pub mod fetch;
+pub mod format;
+pub mod grid;
+pub mod headless;
+pub mod history;
+pub mod indicators;
+pub mod keymap;
+pub mod levels;
+pub mod logging;
+pub mod model;
+pub mod notify;
+pub mod observed;
+pub mod portfolio;
+pub mod replay;
+pub mod session;
+pub mod snapshot;
+pub mod sparkline;
+pub mod symbol;
+pub mod theme;
@@ -0,0 +1,45 @@
+use std::{env, error::Error, fs, path::PathBuf};
+
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::EnvFilter;
+
+/// Daily log files kept before the oldest is deleted.
+const MAX_LOG_FILES: usize = 7;
+
+/// `$XDG_STATE_HOME/stock-tui`, falling back to `~/.local/state/stock-tui`.
+pub fn state_dir() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .map(|dir| dir.join("stock-tui"))
+}
+
+/// Starts logging to a daily-rotated file when `--debug` or `RUST_LOG` is
+/// set. Nothing is installed otherwise, so log statements cost next to
+/// nothing. The returned guard flushes pending lines when dropped.
+pub fn init(debug: bool) -> Result<Option<WorkerGuard>, Box<dyn Error>> {
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) if debug => EnvFilter::new("debug"),
+        Err(_) => return Ok(None),
+    };
+    let dir = state_dir().ok_or("can't locate a log directory: HOME is not set")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("stock-tui")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        .init();
+    Ok(Some(guard))
+}
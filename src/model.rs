@@ -0,0 +1,100 @@
+//! What the fetcher reports about a symbol, independent of how it is shown.
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::Series;
+
+/// Time-adjusted relative volume needs this much of the session (about 20
+/// minutes) to have passed.
+pub const MIN_SESSION_ELAPSED: f64 = 0.05;
+
+/// The fetcher's output. Every field is optional so a payload missing some
+/// of them (Yahoo renames or drops a field now and then) still shows what it
+/// has: zeros and empty strings are treated as unknown and left out.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct StockStats {
+    pub symbol: String,
+    pub price: f64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub volume: u64,
+    pub change: f64,
+    pub pct_change: f64,
+    pub currency: String,
+    pub as_of: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<Series>,
+    // Bars without a price that the fetcher discarded
+    pub dropped: u64,
+    // Start of the newest bar, in seconds since the Unix epoch
+    pub last_bar: Option<u64>,
+    // Yahoo's market state: REGULAR, PRE, POST, CLOSED, ...
+    pub market_state: String,
+    // Yahoo's instrument type: EQUITY, ETF, INDEX, FUTURE, ...
+    pub instrument_type: String,
+    // New York's current offset from UTC in seconds, for the header clock
+    pub utc_offset: Option<i32>,
+    // Average daily volume over the last three months
+    pub avg_volume: u64,
+    // Fraction of the regular session that has passed, 0 to 1
+    pub session_elapsed: Option<f64>,
+    // Length of the day's regular session in minutes, and the bars received
+    // within it; intraday intervals only
+    pub session_minutes: Option<u64>,
+    pub regular_bars: Option<u64>,
+    // New York times of the day's high and low; intraday intervals only
+    pub high_at: String,
+    pub low_at: String,
+    // The chart endpoint's latest price, which can differ from the last
+    // bar's close (`price`) in a fast market; regular hours only
+    pub live_price: Option<f64>,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub bid_size: Option<u64>,
+    pub ask_size: Option<u64>,
+    // Every bar lacked a price (halted, or nothing traded yet): the price is
+    // the last known one and there is no chart
+    pub no_trades: bool,
+    // Yahoo has no bars at all for the symbol: most likely a typo
+    pub not_found: bool,
+    // The past day asked for had no trading (a holiday)
+    pub no_trading: bool,
+    // Change, open, high, low and volume cover the trailing 24 hours rather
+    // than the day (crypto, which never closes)
+    pub rolling_24h: bool,
+    #[serde(skip_serializing)]
+    pub image_data: Option<String>,
+    #[serde(skip_serializing)]
+    pub error: Option<String>,
+}
+
+impl StockStats {
+    /// How far the price is below the day's high and above its low, in
+    /// percent; 0 at the extremes themselves.
+    pub fn distance_from_extremes(&self) -> Option<(f64, f64)> {
+        (self.high > 0.0 && self.low > 0.0).then(|| {
+            (
+                ((self.high - self.price) / self.high * 100.0).max(0.0),
+                ((self.price - self.low) / self.low * 100.0).max(0.0),
+            )
+        })
+    }
+
+    /// Today's volume as a multiple of the average day's, and whether it was
+    /// time-adjusted: compared against the share of an average day's volume
+    /// expected by now, assuming volume accrues evenly over the session.
+    /// Early in the session that estimate is too noisy, so the plain ratio
+    /// is used instead.
+    pub fn relative_volume(&self, time_adjusted: bool) -> Option<(f64, bool)> {
+        if self.avg_volume == 0 {
+            return None;
+        }
+        let rvol = self.volume as f64 / self.avg_volume as f64;
+        match self.session_elapsed {
+            Some(elapsed) if time_adjusted && elapsed >= MIN_SESSION_ELAPSED => Some((rvol / elapsed, true)),
+            _ => Some((rvol, false)),
+        }
+    }
+}
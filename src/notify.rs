@@ -0,0 +1,70 @@
+use std::{
+    process::Command,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+/// Sends desktop notifications through the platform's command-line notifier
+/// (`notify-send` on Linux, `osascript` on macOS). Delivery happens on a
+/// background thread; failures are queued for the UI to report instead of
+/// interrupting it.
+pub struct Notifier {
+    pub move_threshold: f64,
+    errors_tx: Sender<String>,
+    errors_rx: Receiver<String>,
+}
+
+impl Notifier {
+    pub fn new(move_threshold: f64) -> Notifier {
+        let (errors_tx, errors_rx) = mpsc::channel();
+        Notifier { move_threshold, errors_tx, errors_rx }
+    }
+
+    pub fn send(&self, summary: &str, body: &str) {
+        let summary = summary.to_string();
+        let body = body.to_string();
+        let errors = self.errors_tx.clone();
+        thread::spawn(move || {
+            if let Err(e) = deliver(&summary, &body) {
+                let _ = errors.send(e);
+            }
+        });
+    }
+
+    /// Returns the next delivery failure, if any.
+    pub fn take_error(&self) -> Option<String> {
+        self.errors_rx.try_recv().ok()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(summary: &str, body: &str) -> Command {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(format!(
+        "display notification \"{}\" with title \"{}\"",
+        escape(body),
+        escape(summary)
+    ));
+    cmd
+}
+
+#[cfg(not(target_os = "macos"))]
+fn notification_command(summary: &str, body: &str) -> Command {
+    let mut cmd = Command::new("notify-send");
+    cmd.arg("--app-name=stock-tui").arg(summary).arg(body);
+    cmd
+}
+
+/// Shows a notification right away, on the calling thread.
+pub fn deliver(summary: &str, body: &str) -> Result<(), String> {
+    let output = notification_command(summary, body)
+        .output()
+        .map_err(|e| format!("notification failed: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("notification failed: {}", stderr.trim()))
+    }
+}
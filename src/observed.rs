@@ -0,0 +1,40 @@
+use std::collections::VecDeque;
+
+/// Most points kept: a 6.5-hour session at one fetch every 10 seconds.
+const MAX_POINTS: usize = 2300;
+
+/// The price each fetch saw, with when it arrived, for one symbol and one
+/// New York trading day. Bars are rebuilt by Yahoo on every fetch, so moves
+/// within a bar that were watched as they happened only survive here.
+/// Switching symbols or a new day starts over.
+#[derive(Default)]
+pub struct ObservedPrices {
+    symbol: String,
+    day: i64,
+    points: VecDeque<(u64, f64)>,
+}
+
+impl ObservedPrices {
+    /// Adds `price` as seen at `at` (seconds since the Unix epoch);
+    /// `utc_offset` is New York's, for telling the days apart.
+    pub fn push(&mut self, symbol: &str, at: u64, utc_offset: i32, price: f64) {
+        let day = (at as i64 + utc_offset as i64).div_euclid(86_400);
+        if symbol != self.symbol || day != self.day {
+            self.symbol = symbol.to_string();
+            self.day = day;
+            self.points.clear();
+        }
+        if self.points.len() == MAX_POINTS {
+            self.points.pop_front();
+        }
+        self.points.push_back((at, price));
+    }
+
+    /// `symbol`'s points, oldest first; empty for any other symbol.
+    pub fn points(&self, symbol: &str) -> Vec<(u64, f64)> {
+        if symbol != self.symbol {
+            return Vec::new();
+        }
+        self.points.iter().copied().collect()
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{api::yahoo::MiniQuote, config::config_dir, symbol::normalize_symbol};
+
+/// A position in the portfolio file.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Holding {
+    pub symbol: String,
+    pub shares: f64,
+    /// Average price paid per share, in the quote's currency
+    pub cost_basis: f64,
+    /// Optional label, e.g. "IRA", shown in its own column
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// `portfolio.toml`: holdings, and symbols watched without a position.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Portfolio {
+    #[serde(rename = "holding")]
+    pub holdings: Vec<Holding>,
+    pub watchlist: Vec<String>,
+}
+
+impl Portfolio {
+    /// `portfolio.toml` next to the config file.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("portfolio.toml"))
+    }
+
+    /// Reads `path` if given (it must exist), otherwise the default location
+    /// if a file is there. Symbols are normalized as if typed.
+    pub fn load(path: Option<&Path>) -> Result<Portfolio, Box<dyn Error>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Portfolio::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Portfolio::default()),
+            },
+        };
+        let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut portfolio: Portfolio = toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        for holding in &mut portfolio.holdings {
+            holding.symbol = normalize_symbol(&holding.symbol).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        for symbol in &mut portfolio.watchlist {
+            *symbol = normalize_symbol(symbol).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        Ok(portfolio)
+    }
+
+    /// Symbols listed one per line, normalized as if typed and each kept
+    /// once, in order. Blank lines and `#` comments are skipped.
+    pub fn parse_watchlist(text: &str) -> Result<Vec<String>, String> {
+        let mut symbols: Vec<String> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let symbol = normalize_symbol(line).map_err(|e| format!("line {}: {}", n + 1, e))?;
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+        Ok(symbols)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holdings.is_empty() && self.watchlist.is_empty()
+    }
+
+    /// Every symbol to quote, each once, holdings first.
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = Vec::new();
+        for symbol in self.holdings.iter().map(|h| &h.symbol).chain(&self.watchlist) {
+            if !symbols.contains(symbol) {
+                symbols.push(symbol.clone());
+            }
+        }
+        symbols
+    }
+
+    /// One row per holding and watched symbol, valued with `quotes`.
+    /// `convert` gives the currency amounts are shown in and the factor
+    /// taking a quote currency's prices into it, or None to leave the
+    /// quote's currency as it is. Weights are shares of their currency's
+    /// total.
+    pub fn rows(
+        &self,
+        quotes: &HashMap<String, MiniQuote>,
+        convert: impl Fn(&str) -> Option<(String, f64)>,
+    ) -> Vec<PortfolioRow> {
+        let mut rows: Vec<PortfolioRow> = self
+            .holdings
+            .iter()
+            .map(|h| (h.symbol.as_str(), Some(h)))
+            .chain(self.watchlist.iter().map(|symbol| (symbol.as_str(), None)))
+            .map(|(symbol, holding)| {
+                let quote = quotes.get(symbol);
+                let quote_currency = quote.map_or("", |q| q.currency.as_str());
+                let (currency, factor) =
+                    convert(quote_currency).unwrap_or_else(|| (quote_currency.to_string(), 1.0));
+                let mut row = PortfolioRow {
+                    symbol: symbol.to_string(),
+                    account: holding.and_then(|h| h.account.clone()),
+                    shares: holding.map(|h| h.shares),
+                    price: quote.map(|q| q.price),
+                    quote_currency: quote_currency.to_string(),
+                    day_pct: quote.map(|q| q.pct_change),
+                    currency,
+                    value: None,
+                    day_change: None,
+                    pl: None,
+                    pl_pct: None,
+                    weight: None,
+                };
+                if let (Some(h), Some(q)) = (holding, quote) {
+                    let value = h.shares * q.price;
+                    let previous = q.price / (1.0 + q.pct_change / 100.0);
+                    row.value = Some(value * factor);
+                    row.day_change = Some(h.shares * (q.price - previous) * factor);
+                    row.pl = Some((value - h.shares * h.cost_basis) * factor);
+                    row.pl_pct = (h.cost_basis > 0.0).then(|| (q.price / h.cost_basis - 1.0) * 100.0);
+                }
+                row
+            })
+            .collect();
+
+        let totals = totals(&rows);
+        for row in &mut rows {
+            if let (Some(value), Some(total)) = (row.value, totals.iter().find(|t| t.currency == row.currency))
+                && total.value > 0.0
+            {
+                row.weight = Some(value / total.value * 100.0);
+            }
+        }
+        rows
+    }
+}
+
+/// A holding or watched symbol as the portfolio table shows it. Amounts are
+/// in `currency`; empty for watched symbols and before the first quote.
+#[derive(Debug, Clone)]
+pub struct PortfolioRow {
+    pub symbol: String,
+    pub account: Option<String>,
+    pub shares: Option<f64>,
+    /// In the quote's own currency
+    pub price: Option<f64>,
+    pub quote_currency: String,
+    pub day_pct: Option<f64>,
+    pub currency: String,
+    pub value: Option<f64>,
+    pub day_change: Option<f64>,
+    pub pl: Option<f64>,
+    pub pl_pct: Option<f64>,
+    /// Percent of its currency's total value
+    pub weight: Option<f64>,
+}
+
+/// The valued holdings in one currency, added up.
+#[derive(Debug, Clone)]
+pub struct Total {
+    pub currency: String,
+    pub value: f64,
+    pub day_change: f64,
+    pub pl: f64,
+    /// Total P/L against the total cost
+    pub pl_pct: Option<f64>,
+}
+
+/// A total per currency the rows are shown in, in order of appearance, so
+/// mixed currencies are never added together.
+pub fn totals(rows: &[PortfolioRow]) -> Vec<Total> {
+    let mut totals: Vec<Total> = Vec::new();
+    for row in rows {
+        let (Some(value), Some(day_change), Some(pl)) = (row.value, row.day_change, row.pl) else { continue };
+        let index = match totals.iter().position(|t| t.currency == row.currency) {
+            Some(index) => index,
+            None => {
+                totals.push(Total { currency: row.currency.clone(), value: 0.0, day_change: 0.0, pl: 0.0, pl_pct: None });
+                totals.len() - 1
+            }
+        };
+        let total = &mut totals[index];
+        total.value += value;
+        total.day_change += day_change;
+        total.pl += pl;
+    }
+    for total in &mut totals {
+        let cost = total.value - total.pl;
+        total.pl_pct = (cost > 0.0).then(|| total.pl / cost * 100.0);
+    }
+    totals
+}
+
+/// What the portfolio table is ordered by, cycled with `s`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortColumn {
+    /// File order
+    #[default]
+    None,
+    Symbol,
+    DayChange,
+    Pl,
+    PlPct,
+    Weight,
+}
+
+impl SortColumn {
+    pub fn next(self) -> SortColumn {
+        match self {
+            SortColumn::None => SortColumn::Symbol,
+            SortColumn::Symbol => SortColumn::DayChange,
+            SortColumn::DayChange => SortColumn::Pl,
+            SortColumn::Pl => SortColumn::PlPct,
+            SortColumn::PlPct => SortColumn::Weight,
+            SortColumn::Weight => SortColumn::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortColumn::None => "file order",
+            SortColumn::Symbol => "symbol",
+            SortColumn::DayChange => "day %",
+            SortColumn::Pl => "P/L",
+            SortColumn::PlPct => "P/L %",
+            SortColumn::Weight => "weight",
+        }
+    }
+
+    /// Orders `rows`: symbols A to Z, numbers largest first, and rows
+    /// without the number last.
+    pub fn sort(self, rows: &mut [PortfolioRow]) {
+        let key = |row: &PortfolioRow| match self {
+            SortColumn::None | SortColumn::Symbol => None,
+            SortColumn::DayChange => row.day_pct,
+            SortColumn::Pl => row.pl,
+            SortColumn::PlPct => row.pl_pct,
+            SortColumn::Weight => row.weight,
+        };
+        match self {
+            SortColumn::None => {}
+            SortColumn::Symbol => rows.sort_by(|a, b| a.symbol.cmp(&b.symbol)),
+            _ => rows.sort_by(|a, b| match (key(a), key(b)) {
+                (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }),
+        }
+    }
+}
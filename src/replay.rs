@@ -0,0 +1,164 @@
+use std::{
+    error::Error,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::model::StockStats;
+
+/// One line of a session file: a fetched quote and when it arrived.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    /// Wall-clock time of the fetch, in milliseconds since the Unix epoch
+    at_ms: u64,
+    stats: StockStats,
+    // The chart travels separately because `StockStats` never serializes it
+    #[serde(default)]
+    chart: Option<String>,
+}
+
+/// `--record`: appends every successful fetch to a JSONL session file.
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Recorder> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Recorder { file })
+    }
+
+    pub fn append(&mut self, stats: &StockStats) -> Result<(), Box<dyn Error>> {
+        let at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let snapshot = Snapshot { at_ms, stats: stats.clone(), chart: stats.image_data.clone() };
+        writeln!(self.file, "{}", serde_json::to_string(&snapshot)?)?;
+        Ok(())
+    }
+}
+
+/// `--replay`: plays a recorded session back at its recorded pace, scaled by
+/// `speed`. Stepping by hand pauses playback.
+pub struct Replay {
+    snapshots: Vec<Snapshot>,
+    // Snapshot on screen; None until the first one is shown
+    position: Option<usize>,
+    speed: f64,
+    paused: bool,
+    next_due: Instant,
+}
+
+impl Replay {
+    pub fn load(path: &Path, speed: f64) -> Result<Replay, Box<dyn Error>> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut snapshots = Vec::new();
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let snapshot = serde_json::from_str(&line)
+                .map_err(|e| format!("{} line {}: {}", path.display(), i + 1, e))?;
+            snapshots.push(snapshot);
+        }
+        if snapshots.is_empty() {
+            return Err(format!("{}: no snapshots recorded", path.display()).into());
+        }
+        Ok(Replay { snapshots, position: None, speed, paused: false, next_due: Instant::now() })
+    }
+
+    /// Returns the next snapshot once its recorded delay has elapsed.
+    pub fn poll(&mut self) -> Option<StockStats> {
+        if self.paused || Instant::now() < self.next_due {
+            return None;
+        }
+        let next = self.position.map_or(0, |i| i + 1);
+        (next < self.snapshots.len()).then(|| self.show(next))
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            self.schedule_next();
+        }
+    }
+
+    /// Moves one snapshot forward or back, pausing playback.
+    pub fn step(&mut self, forward: bool) -> Option<StockStats> {
+        self.paused = true;
+        let index = match (self.position, forward) {
+            (None, _) => 0,
+            (Some(i), true) => i + 1,
+            (Some(i), false) => i.checked_sub(1)?,
+        };
+        (index < self.snapshots.len()).then(|| self.show(index))
+    }
+
+    /// Playback state for the chart border, e.g. "Replay 3/120 10x".
+    pub fn label(&self) -> String {
+        let shown = self.position.map_or(0, |i| i + 1);
+        let state = if self.paused {
+            " paused"
+        } else if shown == self.snapshots.len() {
+            " end"
+        } else {
+            ""
+        };
+        format!("Replay {}/{} {}x{}", shown, self.snapshots.len(), self.speed, state)
+    }
+
+    fn show(&mut self, index: usize) -> StockStats {
+        self.position = Some(index);
+        self.schedule_next();
+        let snapshot = &self.snapshots[index];
+        let mut stats = snapshot.stats.clone();
+        stats.image_data = snapshot.chart.clone();
+        stats
+    }
+
+    fn schedule_next(&mut self) {
+        let Some(current) = self.position else { return };
+        if let Some(next) = self.snapshots.get(current + 1) {
+            let gap_ms = next.at_ms.saturating_sub(self.snapshots[current].at_ms);
+            // Corrupt timestamps can make the gap too long to represent
+            let due = Duration::try_from_secs_f64(gap_ms as f64 / 1000.0 / self.speed)
+                .ok()
+                .and_then(|wait| Instant::now().checked_add(wait));
+            self.next_due = due.unwrap_or_else(|| {
+                warn!(index = current + 1, at_ms = next.at_ms, "snapshot timestamp out of range; playing it now");
+                Instant::now()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(at_ms: u64, price: f64) -> Snapshot {
+        Snapshot { at_ms, stats: StockStats { symbol: "AAPL".to_string(), price, ..StockStats::default() }, chart: None }
+    }
+
+    #[test]
+    fn out_of_range_timestamp_plays_next_instead_of_panicking() {
+        // A gap of u64::MAX ms, slowed down, is far past what an Instant holds
+        let mut replay = Replay {
+            snapshots: vec![snapshot(0, 1.0), snapshot(u64::MAX, 2.0)],
+            position: None,
+            speed: 0.001,
+            paused: false,
+            next_due: Instant::now(),
+        };
+        assert_eq!(replay.poll().map(|s| s.price), Some(1.0));
+        assert_eq!(replay.poll().map(|s| s.price), Some(2.0));
+        assert_eq!(replay.label(), "Replay 2/2 0.001x end");
+    }
+}
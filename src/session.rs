@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use crate::{format::parse_utc, model::StockStats};
+
+/// Minutes of regular trading in a year (252 sessions of 6.5 hours), for
+/// annualizing bar-to-bar volatility.
+const TRADING_MINUTES_PER_YEAR: f64 = 252.0 * 390.0;
+
+/// A summary of the day's session for the footer under the chart. Computed
+/// once per fetch so drawing stays cheap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionStats {
+    /// Volume-weighted average of the bars' closes; None without volume
+    pub vwap: Option<f64>,
+    /// The day's high minus its low, and that as a percent of the low
+    pub range: f64,
+    pub range_pct: f64,
+    /// Standard deviation of bar-to-bar returns in percent, and the same
+    /// scaled to a year of trading; None with fewer than three bars
+    pub volatility: Option<f64>,
+    pub annualized: Option<f64>,
+    /// Regular-session bars received, and how many the session so far
+    /// should have produced
+    pub bars: Option<(u64, u64)>,
+    /// Percent of bars that closed above the bar before; None without two
+    /// adjacent bars. Pairs straddling a missing bar aren't compared
+    pub up_pct: Option<f64>,
+}
+
+impl SessionStats {
+    /// From a quote's chart series, made of bars `bar` long. None when the
+    /// quote has no bars.
+    pub fn compute(stats: &StockStats, bar: Duration) -> Option<SessionStats> {
+        let series = stats.series.as_ref().filter(|series| !series.is_empty())?;
+        let rows = series.len();
+        let prices = &series.price[..rows];
+        let volumes = &series.volume[..rows];
+
+        let total_volume: u64 = volumes.iter().sum();
+        let vwap = (total_volume > 0).then(|| {
+            prices.iter().zip(volumes).map(|(price, volume)| price * *volume as f64).sum::<f64>() / total_volume as f64
+        });
+
+        let (high, low) = if stats.high > 0.0 && stats.low > 0.0 {
+            (stats.high, stats.low)
+        } else {
+            let high = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let low = prices.iter().copied().fold(f64::INFINITY, f64::min);
+            (high, low)
+        };
+        let range = high - low;
+        let range_pct = if low > 0.0 { range / low * 100.0 } else { 0.0 };
+
+        let returns: Vec<f64> = prices.windows(2).filter(|pair| pair[0] > 0.0).map(|pair| pair[1] / pair[0] - 1.0).collect();
+        let volatility = (returns.len() >= 2).then(|| {
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+            variance.sqrt() * 100.0
+        });
+        let bar_minutes = bar.as_secs_f64() / 60.0;
+        let annualized = volatility.map(|v| v * (TRADING_MINUTES_PER_YEAR / bar_minutes).sqrt());
+
+        let bars = match (stats.regular_bars, stats.session_minutes, stats.session_elapsed) {
+            (Some(received), Some(minutes), Some(elapsed)) if minutes > 0 => {
+                Some((received, (elapsed * minutes as f64 / bar_minutes).ceil() as u64))
+            }
+            _ => None,
+        };
+
+        let times: Vec<Option<i64>> = series.utc[..rows].iter().map(|stamp| parse_utc(stamp)).collect();
+        let (mut up, mut compared) = (0, 0);
+        for i in 1..rows {
+            let adjacent = matches!((times[i - 1], times[i]), (Some(a), Some(b)) if b - a == bar.as_secs() as i64);
+            if adjacent && prices[i - 1] > 0.0 {
+                compared += 1;
+                up += usize::from(prices[i] > prices[i - 1]);
+            }
+        }
+        let up_pct = (compared > 0).then(|| up as f64 / compared as f64 * 100.0);
+
+        Some(SessionStats { vwap, range, range_pct, volatility, annualized, bars, up_pct })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Series;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    /// A quote whose series has a bar at each of `minutes` past 14:30 UTC.
+    fn quote(minutes: &[u32], prices: &[f64], volumes: &[u64]) -> StockStats {
+        let series = Series {
+            utc: minutes.iter().map(|m| format!("2026-03-02T{:02}:{:02}:00Z", 14 + (30 + m) / 60, (30 + m) % 60)).collect(),
+            et: minutes.iter().map(|_| String::new()).collect(),
+            price: prices.to_vec(),
+            pct_change: prices.iter().map(|_| 0.0).collect(),
+            volume: volumes.to_vec(),
+        };
+        StockStats { symbol: "AAPL".to_string(), series: Some(series), ..StockStats::default() }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn vwap_weights_closes_by_volume() {
+        let stats = SessionStats::compute(&quote(&[0, 1], &[10.0, 20.0], &[1, 3]), MINUTE).unwrap();
+        assert_eq!(stats.vwap, Some(17.5));
+        let quiet = SessionStats::compute(&quote(&[0, 1], &[10.0, 20.0], &[0, 0]), MINUTE).unwrap();
+        assert_eq!(quiet.vwap, None);
+    }
+
+    #[test]
+    fn range_prefers_the_quoted_high_and_low() {
+        let mut day = quote(&[0, 1, 2], &[101.0, 104.0, 102.0], &[1, 1, 1]);
+        let from_bars = SessionStats::compute(&day, MINUTE).unwrap();
+        assert!(close(from_bars.range, 3.0) && close(from_bars.range_pct, 3.0 / 101.0 * 100.0), "{:?}", from_bars);
+
+        (day.high, day.low) = (110.0, 100.0);
+        let quoted = SessionStats::compute(&day, MINUTE).unwrap();
+        assert_eq!((quoted.range, quoted.range_pct), (10.0, 10.0));
+    }
+
+    #[test]
+    fn volatility_is_the_spread_of_returns() {
+        // Returns of +1%, -1%, +1%: mean 1/3%, sample deviation 2/sqrt(3)%
+        let prices = [100.0, 101.0, 99.99, 100.9899];
+        let stats = SessionStats::compute(&quote(&[0, 1, 2, 3], &prices, &[1; 4]), MINUTE).unwrap();
+        let expected = 2.0 / 3f64.sqrt();
+        assert!(close(stats.volatility.unwrap(), expected), "{:?}", stats);
+        assert!(close(stats.annualized.unwrap(), expected * (252.0f64 * 390.0).sqrt()), "{:?}", stats);
+
+        // Steady growth doesn't vary, and two bars are too few to say
+        let steady = SessionStats::compute(&quote(&[0, 1, 2], &[100.0, 110.0, 121.0], &[1; 3]), MINUTE).unwrap();
+        assert!(close(steady.volatility.unwrap(), 0.0));
+        let short = SessionStats::compute(&quote(&[0, 1], &[100.0, 101.0], &[1; 2]), MINUTE).unwrap();
+        assert_eq!(short.volatility, None);
+    }
+
+    #[test]
+    fn bars_received_against_those_expected_so_far() {
+        let mut day = quote(&[0], &[100.0], &[1]);
+        (day.regular_bars, day.session_minutes, day.session_elapsed) = (Some(180), Some(390), Some(0.5));
+        assert_eq!(SessionStats::compute(&day, MINUTE).unwrap().bars, Some((180, 195)));
+        assert_eq!(SessionStats::compute(&day, MINUTE * 5).unwrap().bars, Some((180, 39)));
+        day.session_minutes = None;
+        assert_eq!(SessionStats::compute(&day, MINUTE).unwrap().bars, None);
+    }
+
+    #[test]
+    fn up_bars_skip_pairs_across_a_gap() {
+        // 14:31 to 14:33 straddles a missing bar, so only the first pair counts
+        let stats = SessionStats::compute(&quote(&[0, 1, 3], &[100.0, 99.0, 101.0], &[1; 3]), MINUTE).unwrap();
+        assert_eq!(stats.up_pct, Some(0.0));
+        let rising = SessionStats::compute(&quote(&[0, 1, 2], &[100.0, 101.0, 100.5], &[1; 3]), MINUTE).unwrap();
+        assert_eq!(rising.up_pct, Some(50.0));
+    }
+
+    #[test]
+    fn alternating_bars_are_an_even_split() {
+        let minutes: Vec<u32> = (0..11).collect();
+        let prices: Vec<f64> = minutes.iter().map(|m| if m % 2 == 0 { 100.0 } else { 101.0 }).collect();
+        let stats = SessionStats::compute(&quote(&minutes, &prices, &[1; 11]), MINUTE).unwrap();
+        assert_eq!(stats.up_pct, Some(50.0));
+
+        // Without 14:33 and 14:34 the 14:32 to 14:35 rise isn't compared,
+        // leaving 3 of 7
+        let (minutes, prices): (Vec<u32>, Vec<f64>) = minutes.iter().zip(&prices).filter(|(m, _)| !matches!(m, 3 | 4)).map(|(m, p)| (*m, *p)).unzip();
+        let gapped = SessionStats::compute(&quote(&minutes, &prices, &[1; 9]), MINUTE).unwrap();
+        assert!(close(gapped.up_pct.unwrap(), 300.0 / 7.0), "{:?}", gapped.up_pct);
+    }
+
+    #[test]
+    fn no_bars_no_stats() {
+        assert_eq!(SessionStats::compute(&quote(&[], &[], &[]), MINUTE), None);
+        assert_eq!(SessionStats::compute(&StockStats::default(), MINUTE), None);
+    }
+}
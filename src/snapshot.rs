@@ -0,0 +1,121 @@
+//! Text copies of the screen, for pasting a chart into a chat or keeping a
+//! daily record.
+
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::format::civil_from_days;
+
+/// Default name for snapshots made with `E`.
+pub const DEFAULT_SNAPSHOT_PATH: &str = "{symbol}-{date}-{time}.txt";
+
+/// Expands `{symbol}`, `{date}` (e.g. 2024-03-14) and `{time}` (e.g. 143207)
+/// in a snapshot path, at `utc_offset` seconds from UTC. Also returns
+/// whether anything was expanded: only templated names may replace an
+/// existing file.
+pub fn expand_path(template: &str, symbol: &str, now: SystemTime, utc_offset: i32) -> (PathBuf, bool) {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64) + utc_offset as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let of_day = secs.rem_euclid(86_400);
+    let expanded = template
+        .replace("{symbol}", symbol)
+        .replace("{date}", &format!("{:04}-{:02}-{:02}", year, month, day))
+        .replace("{time}", &format!("{:02}{:02}{:02}", of_day / 3600, of_day / 60 % 60, of_day % 60));
+    let templated = expanded != template;
+    (PathBuf::from(expanded), templated)
+}
+
+/// The buffer as lines of text, trailing blanks trimmed. With `ansi`, colors
+/// and modifiers are kept as SGR escape sequences, and lines keep their full
+/// width since a blank cell may have a background. A chart drawn with an
+/// image protocol is not in the buffer; only half-block charts come through.
+pub fn render(buffer: &Buffer, ansi: bool) -> String {
+    let area = buffer.area;
+    let mut out = String::new();
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut style = None;
+        // Cells covered by the previous wide character
+        let mut skip = 0;
+        for x in area.left()..area.right() {
+            let cell = &buffer[(x, y)];
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            skip = cell.symbol().width().saturating_sub(1);
+            if ansi {
+                let cell_style = (cell.fg, cell.bg, cell.modifier);
+                if style != Some(cell_style) {
+                    line.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                    style = Some(cell_style);
+                }
+            }
+            line.push_str(cell.symbol());
+        }
+        if ansi {
+            out.push_str(&line);
+            out.push_str("\x1b[0m");
+        } else {
+            out.push_str(line.trim_end());
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The escape sequence that sets exactly these colors and modifiers.
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (flag, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(color_code(fg, false));
+    codes.extend(color_code(bg, true));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let base = if background { 40 } else { 30 };
+    let named = |n: u8| Some((base + n as u16).to_string());
+    let bright = |n: u8| Some((base + 60 + n as u16).to_string());
+    let extended = if background { 48 } else { 38 };
+    match color {
+        Color::Reset => None,
+        Color::Black => named(0),
+        Color::Red => named(1),
+        Color::Green => named(2),
+        Color::Yellow => named(3),
+        Color::Blue => named(4),
+        Color::Magenta => named(5),
+        Color::Cyan => named(6),
+        Color::Gray => named(7),
+        Color::DarkGray => bright(0),
+        Color::LightRed => bright(1),
+        Color::LightGreen => bright(2),
+        Color::LightYellow => bright(3),
+        Color::LightBlue => bright(4),
+        Color::LightMagenta => bright(5),
+        Color::LightCyan => bright(6),
+        Color::White => bright(7),
+        Color::Indexed(n) => Some(format!("{};5;{}", extended, n)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", extended, r, g, b)),
+    }
+}
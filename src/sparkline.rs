@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+
+/// Most prices kept; one is added per successful fetch.
+const MAX_PRICES: usize = 60;
+
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_LEVELS: [char; 8] = ['_', '.', ',', '-', '~', '=', '*', '^'];
+
+/// The last fetched prices of one symbol, oldest first. Switching symbols
+/// starts over.
+#[derive(Default)]
+pub struct RecentPrices {
+    symbol: String,
+    prices: VecDeque<f64>,
+}
+
+impl RecentPrices {
+    pub fn push(&mut self, symbol: &str, price: f64) {
+        if symbol != self.symbol {
+            self.symbol = symbol.to_string();
+            self.prices.clear();
+        }
+        if self.prices.len() == MAX_PRICES {
+            self.prices.pop_front();
+        }
+        self.prices.push_back(price);
+    }
+
+    /// One block per price, scaled between the kept prices' own min and max,
+    /// e.g. "▁▂▄▃▅▇█". None until `symbol` has two prices to compare.
+    pub fn sparkline(&self, symbol: &str, ascii: bool) -> Option<String> {
+        if symbol != self.symbol || self.prices.len() < 2 {
+            return None;
+        }
+        let levels = if ascii { ASCII_LEVELS } else { LEVELS };
+        let min = self.prices.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self.prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let top = (levels.len() - 1) as f64;
+        Some(
+            self.prices
+                .iter()
+                .map(|price| {
+                    let level = if max > min { ((price - min) / (max - min) * top).round() } else { top / 2.0 };
+                    levels[level as usize]
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,70 @@
+/// Yahoo exchange suffixes, which keep their dot (`VOD.L`, `SHOP.TO`). Any
+/// other short dotted suffix is taken to be a share class.
+const EXCHANGE_SUFFIXES: &[&str] = &[
+    "AS", "AX", "BA", "BK", "BO", "BR", "CN", "CO", "DE", "F", "HE", "HK", "IR", "JK", "JO", "KL", "KQ", "KS",
+    "L", "LS", "MC", "MI", "MX", "NE", "NS", "NZ", "OL", "PA", "SA", "SG", "SI", "SS", "ST", "SW", "SZ", "T",
+    "TA", "TO", "TW", "TWO", "V", "VI", "WA",
+];
+
+/// Turns a typed symbol into the form Yahoo expects: trimmed, upper case, and
+/// with US share classes written with a dash (`brk.b` -> `BRK-B`). Exchange
+/// suffixes (`.L`, `.TO`), crypto pairs (`BTC-USD`), indices (`^GSPC`) and
+/// futures or currencies (`ES=F`, `EURUSD=X`) keep their punctuation.
+pub fn normalize_symbol(raw: &str) -> Result<String, String> {
+    let symbol = raw.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("empty symbol".to_string());
+    }
+    if let Some(c) = symbol.chars().find(|c| !(c.is_ascii_alphanumeric() || "^.-=&".contains(*c))) {
+        let what = if c.is_whitespace() { "a space".to_string() } else { format!("'{}'", c) };
+        return Err(format!("'{}' is not a valid symbol: it contains {}", raw.trim(), what));
+    }
+    match symbol.rsplit_once('.') {
+        Some((base, class))
+            if !base.is_empty()
+                && base.chars().all(|c| c.is_ascii_alphabetic())
+                && (1..=2).contains(&class.len())
+                && class.chars().all(|c| c.is_ascii_alphabetic())
+                && !EXCHANGE_SUFFIXES.contains(&class) =>
+        {
+            Ok(format!("{}-{}", base, class))
+        }
+        _ => Ok(symbol),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_each_form() {
+        let cases = [
+            ("aapl", "AAPL"),
+            ("  msft \n", "MSFT"),
+            ("brk.b", "BRK-B"),
+            ("BF.B", "BF-B"),
+            ("BRK-B", "BRK-B"),
+            ("vod.l", "VOD.L"),
+            ("shop.to", "SHOP.TO"),
+            ("7203.t", "7203.T"),
+            ("2330.tw", "2330.TW"),
+            ("btc-usd", "BTC-USD"),
+            ("eth-eur", "ETH-EUR"),
+            ("^gspc", "^GSPC"),
+            ("es=f", "ES=F"),
+            ("eurusd=x", "EURUSD=X"),
+            ("M&M.NS", "M&M.NS"),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(normalize_symbol(raw).as_deref(), Ok(expected), "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_and_stray_characters() {
+        assert_eq!(normalize_symbol("  ").unwrap_err(), "empty symbol");
+        assert_eq!(normalize_symbol("BRK B").unwrap_err(), "'BRK B' is not a valid symbol: it contains a space");
+        assert_eq!(normalize_symbol("AAPL!").unwrap_err(), "'AAPL!' is not a valid symbol: it contains '!'");
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Hex colors handed to the matplotlib renderer in `fetch_stock.py`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ChartPalette {
+    pub up: String,
+    pub down: String,
+    pub line: String,
+    pub baseline: String,
+    pub grid: String,
+    pub text: String,
+}
+
+/// Percent moves at which a change stops looking faint and starts looking
+/// bold. A move of exactly `faint_below` is normal, as is one of exactly
+/// `bold_above`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeGrades {
+    pub faint_below: f64,
+    pub bold_above: f64,
+}
+
+impl Default for ChangeGrades {
+    fn default() -> Self {
+        ChangeGrades { faint_below: 0.5, bold_above: 2.0 }
+    }
+}
+
+impl ChangeGrades {
+    pub fn validate(self) -> Result<ChangeGrades, String> {
+        if !(self.faint_below >= 0.0 && self.faint_below <= self.bold_above) {
+            return Err(format!(
+                "change_grades: need 0 <= faint_below <= bold_above, got {} and {}",
+                self.faint_below, self.bold_above
+            ));
+        }
+        Ok(self)
+    }
+}
+
+/// One of the colors that tell symbols apart in the grid and the watchlist.
+struct AccentColor {
+    name: &'static str,
+    /// On terminals with 256 colors or more
+    indexed: u8,
+    /// The same color for the rendered chart
+    hex: &'static str,
+    /// On 16-color terminals, where the bright half is also bold so the
+    /// palette stays distinct where bright colors are drawn the same
+    basic: Color,
+    modifier: Modifier,
+}
+
+/// Accent colors, none of them the green or red of up and down.
+const ACCENT_COLORS: [AccentColor; 8] = [
+    AccentColor { name: "blue", indexed: 33, hex: "#0087ff", basic: Color::Blue, modifier: Modifier::empty() },
+    AccentColor { name: "orange", indexed: 214, hex: "#ffaf00", basic: Color::Yellow, modifier: Modifier::empty() },
+    AccentColor { name: "purple", indexed: 135, hex: "#af5fff", basic: Color::Magenta, modifier: Modifier::empty() },
+    AccentColor { name: "cyan", indexed: 44, hex: "#00d7d7", basic: Color::Cyan, modifier: Modifier::empty() },
+    AccentColor { name: "pink", indexed: 205, hex: "#ff5faf", basic: Color::LightMagenta, modifier: Modifier::BOLD },
+    AccentColor { name: "yellow", indexed: 228, hex: "#ffff87", basic: Color::LightYellow, modifier: Modifier::BOLD },
+    AccentColor { name: "tan", indexed: 137, hex: "#af875f", basic: Color::LightBlue, modifier: Modifier::BOLD },
+    AccentColor { name: "white", indexed: 252, hex: "#d0d0d0", basic: Color::White, modifier: Modifier::BOLD },
+];
+
+/// A symbol's accent: the style of its label and row marker, and its chart
+/// line's color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accent {
+    pub style: Style,
+    pub hex: String,
+}
+
+/// Picks each symbol's accent: the one set for it in the config, or one
+/// hashed from the symbol, so it's the same every run. Off (plain style,
+/// the theme's line color) until built with `Accents::new`, as for the
+/// mono theme.
+#[derive(Clone, Debug, Default)]
+pub struct Accents {
+    enabled: bool,
+    /// 256 colors or more
+    extended: bool,
+    overrides: HashMap<String, Accent>,
+}
+
+impl Accents {
+    /// `overrides` maps symbols to a palette name ("blue", "orange",
+    /// "purple", "cyan", "pink", "yellow", "tan", "white") or "#rrggbb".
+    pub fn new(overrides: &HashMap<String, String>, extended: bool) -> Result<Accents, String> {
+        let mut accents = Accents { enabled: true, extended, overrides: HashMap::new() };
+        for (symbol, color) in overrides {
+            let accent = match ACCENT_COLORS.iter().find(|accent| accent.name.eq_ignore_ascii_case(color)) {
+                Some(named) => accents.palette_accent(named),
+                None => {
+                    let (r, g, b) = parse_hex(color).ok_or_else(|| {
+                        let names: Vec<&str> = ACCENT_COLORS.iter().map(|accent| accent.name).collect();
+                        format!("accents.{}: '{}' is not one of {} or a #rrggbb color", symbol, color, names.join(", "))
+                    })?;
+                    Accent { style: Style::default().fg(Color::Rgb(r, g, b)), hex: color.clone() }
+                }
+            };
+            accents.overrides.insert(symbol.to_uppercase(), accent);
+        }
+        Ok(accents)
+    }
+
+    fn palette_accent(&self, color: &AccentColor) -> Accent {
+        let style = if self.extended {
+            Style::default().fg(Color::Indexed(color.indexed))
+        } else {
+            Style::default().fg(color.basic).add_modifier(color.modifier)
+        };
+        Accent { style, hex: color.hex.to_string() }
+    }
+
+    /// Accents for symbols shown together, in order; `line` is the chart
+    /// line color when accents are off. A symbol whose hashed color is
+    /// already taken gets the next free one, so up to eight differ.
+    pub fn for_symbols(&self, symbols: &[String], line: &str) -> Vec<Accent> {
+        if !self.enabled {
+            return symbols.iter().map(|_| Accent { style: Style::default(), hex: line.to_string() }).collect();
+        }
+        let mut taken = [false; ACCENT_COLORS.len()];
+        symbols
+            .iter()
+            .map(|symbol| {
+                if let Some(accent) = self.overrides.get(&symbol.to_uppercase()) {
+                    return accent.clone();
+                }
+                // FNV-1a, which unlike std's hasher is the same in every build
+                let hash = symbol.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                    (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+                });
+                let hashed = (hash % ACCENT_COLORS.len() as u64) as usize;
+                let index = (0..ACCENT_COLORS.len())
+                    .map(|step| (hashed + step) % ACCENT_COLORS.len())
+                    .find(|&index| !taken[index])
+                    .unwrap_or(hashed);
+                taken[index] = true;
+                self.palette_accent(&ACCENT_COLORS[index])
+            })
+            .collect()
+    }
+}
+
+fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    let digits = color.strip_prefix('#').filter(|digits| digits.len() == 6 && digits.is_ascii())?;
+    let channel = |at: usize| u8::from_str_radix(&digits[at..at + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Colors used by the TUI and the rendered chart.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub up: Color,
+    pub down: Color,
+    pub border: Color,
+    pub axis: Color,
+    pub dim: Color,
+    pub accent: Color,
+    pub warn: Color,
+    /// Unusual activity, such as heavy relative volume
+    pub hot: Color,
+    pub chart: ChartPalette,
+    /// How strongly a change is colored by its size
+    pub grades: ChangeGrades,
+    /// Per-symbol colors in the grid and the watchlist
+    pub accents: Accents,
+}
+
+pub const THEME_NAMES: [&str; 3] = ["default", "colorblind", "mono"];
+
+impl Theme {
+    pub fn by_name(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default_theme()),
+            "colorblind" => Some(Theme::colorblind()),
+            "mono" => Some(Theme::mono()),
+            _ => None,
+        }
+    }
+
+    fn default_theme() -> Theme {
+        Theme {
+            up: Color::Green,
+            down: Color::Red,
+            border: Color::Reset,
+            axis: Color::Reset,
+            dim: Color::DarkGray,
+            accent: Color::Yellow,
+            warn: Color::Yellow,
+            hot: Color::Rgb(255, 140, 0),
+            grades: ChangeGrades::default(),
+            accents: Accents::default(),
+            chart: ChartPalette {
+                up: "#98c379".to_string(),
+                down: "#e06c75".to_string(),
+                line: "#4674d7".to_string(),
+                baseline: "#ABB2BF".to_string(),
+                grid: "#43454c".to_string(),
+                text: "#ABB2BF".to_string(),
+            },
+        }
+    }
+
+    /// Blue/orange instead of green/red, distinguishable with deuteranopia.
+    fn colorblind() -> Theme {
+        Theme {
+            up: Color::Rgb(0, 114, 178),
+            down: Color::Rgb(230, 159, 0),
+            // Orange already means "down" here
+            hot: Color::Rgb(204, 121, 167),
+            chart: ChartPalette {
+                up: "#0072b2".to_string(),
+                down: "#e69f00".to_string(),
+                line: "#56b4e9".to_string(),
+                ..Theme::default_theme().chart
+            },
+            ..Theme::default_theme()
+        }
+    }
+
+    /// No hues at all; direction is carried by the ▲/▼ icons and modifiers.
+    fn mono() -> Theme {
+        Theme {
+            up: Color::Reset,
+            down: Color::Reset,
+            border: Color::Reset,
+            axis: Color::Reset,
+            dim: Color::Reset,
+            accent: Color::Reset,
+            warn: Color::Reset,
+            hot: Color::Reset,
+            grades: ChangeGrades::default(),
+            accents: Accents::default(),
+            chart: ChartPalette {
+                up: "#d0d0d0".to_string(),
+                down: "#707070".to_string(),
+                line: "#d0d0d0".to_string(),
+                baseline: "#909090".to_string(),
+                grid: "#404040".to_string(),
+                text: "#b0b0b0".to_string(),
+            },
+        }
+    }
+
+    pub fn change_color(&self, change: f64) -> Color {
+        if change >= 0.0 { self.up } else { self.down }
+    }
+
+    /// Bold, direction-colored style for price changes.
+    pub fn change_style(&self, change: f64) -> Style {
+        let style = Style::default().fg(self.change_color(change)).add_modifier(Modifier::BOLD);
+        if change < 0.0 && self.down == Color::Reset {
+            style.add_modifier(Modifier::UNDERLINED)
+        } else {
+            style
+        }
+    }
+
+    pub fn dim_style(&self) -> Style {
+        if self.dim == Color::Reset {
+            Style::default().add_modifier(Modifier::DIM)
+        } else {
+            Style::default().fg(self.dim)
+        }
+    }
+
+    /// Style for data that may be out of date.
+    pub fn warn_style(&self) -> Style {
+        if self.warn == Color::Reset {
+            Style::default().add_modifier(Modifier::ITALIC)
+        } else {
+            Style::default().fg(self.warn)
+        }
+    }
+
+    pub fn hot_style(&self) -> Style {
+        if self.hot == Color::Reset {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(self.hot)
+        }
+    }
+
+    pub fn border_style(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+}
+
+/// Style for a percent change graded by its size: the direction's color,
+/// dimmed under `faint_below`, plain up to `bold_above`, bold past it.
+pub fn color_for_change(pct: f64, theme: &Theme) -> Style {
+    let style = Style::default().fg(theme.change_color(pct));
+    let style = if pct < 0.0 && theme.down == Color::Reset { style.add_modifier(Modifier::UNDERLINED) } else { style };
+    let size = pct.abs();
+    if size < theme.grades.faint_below {
+        style.add_modifier(Modifier::DIM)
+    } else if size > theme.grades.bold_above {
+        style.add_modifier(Modifier::BOLD)
+    } else {
+        style
+    }
+}
+
+pub fn change_arrow(change: f64, ascii: bool) -> &'static str {
+    match (change >= 0.0, ascii) {
+        (true, false) => "▲",
+        (false, false) => "▼",
+        (true, true) => "+",
+        (false, true) => "-",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grade(pct: f64, theme: &Theme) -> Modifier {
+        color_for_change(pct, theme).add_modifier
+    }
+
+    #[test]
+    fn thresholds_are_normal_on_the_dot() {
+        let theme = Theme::by_name("default").unwrap();
+        assert_eq!(grade(0.49, &theme), Modifier::DIM);
+        assert_eq!(grade(0.5, &theme), Modifier::empty());
+        assert_eq!(grade(2.0, &theme), Modifier::empty());
+        assert_eq!(grade(2.01, &theme), Modifier::BOLD);
+        assert_eq!(grade(0.0, &theme), Modifier::DIM);
+    }
+
+    #[test]
+    fn falls_grade_like_rises_in_their_own_color() {
+        let theme = Theme::by_name("default").unwrap();
+        assert_eq!(grade(-0.5, &theme), Modifier::empty());
+        assert_eq!(grade(-2.0, &theme), Modifier::empty());
+        assert_eq!(grade(-2.01, &theme), Modifier::BOLD);
+        assert_eq!(color_for_change(-2.01, &theme).fg, Some(theme.down));
+        assert_eq!(color_for_change(2.01, &theme).fg, Some(theme.up));
+    }
+
+    #[test]
+    fn configured_thresholds_move_the_buckets() {
+        let mut theme = Theme::by_name("default").unwrap();
+        theme.grades = ChangeGrades { faint_below: 1.0, bold_above: 1.0 }.validate().unwrap();
+        assert_eq!(grade(0.99, &theme), Modifier::DIM);
+        assert_eq!(grade(1.0, &theme), Modifier::empty());
+        assert_eq!(grade(1.01, &theme), Modifier::BOLD);
+    }
+
+    #[test]
+    fn crossed_thresholds_are_rejected() {
+        assert!(ChangeGrades { faint_below: 2.0, bold_above: 0.5 }.validate().is_err());
+        assert!(ChangeGrades { faint_below: -0.1, bold_above: 0.5 }.validate().is_err());
+        assert!(ChangeGrades { faint_below: f64::NAN, bold_above: 0.5 }.validate().is_err());
+    }
+}
@@ -0,0 +1,68 @@
+//! `--once` against a stand-in for the fetcher script that answers slowly,
+//! to check the symbols are fetched side by side rather than in turn.
+
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// How long the stand-in takes to answer each symbol.
+const LATENCY: Duration = Duration::from_millis(800);
+
+/// A directory holding a `python3` that sleeps, then prints a quote for the
+/// symbol it was given, or fails for `BAD`.
+fn slow_python() -> PathBuf {
+    let dir = env::temp_dir().join(format!("tmp2-fetch-all-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("python3");
+    fs::write(
+        &script,
+        format!(
+            r#"#!/bin/sh
+sleep {}
+if [ "$2" = BAD ]; then echo "ValueError: no such symbol" >&2; exit 1; fi
+echo '{{"symbol":"'"$2"'","price":100,"open":99,"high":101,"low":98,"volume":1000,"change":1,"pct_change":1.0,"currency":"USD","as_of":"14:31 ET","utc_offset":-14400,"instrument_type":"EQUITY","market_state":"REGULAR"}}'
+"#,
+            LATENCY.as_secs_f64()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    dir
+}
+
+#[test]
+fn eight_symbols_take_about_one_fetch() {
+    let dir = slow_python();
+    let symbols = ["AAPL", "MSFT", "NVDA", "AMZN", "GOOG", "META", "TSLA", "BAD"];
+    let path = format!("{}:{}", dir.display(), env::var("PATH").unwrap_or_default());
+
+    let started = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_tmp2"))
+        .arg("--once")
+        .args(symbols)
+        .env("PATH", path)
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .output()
+        .unwrap();
+    let elapsed = started.elapsed();
+    fs::remove_dir_all(&dir).ok();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // One request at a time would take eight latencies, four at a time two
+    assert!(elapsed < LATENCY * 2, "took {:?}", elapsed);
+    for symbol in &symbols[..7] {
+        assert!(stdout.lines().any(|line| line.starts_with(&format!("{} ", symbol))), "{} missing from {:?}", symbol, stdout);
+    }
+    assert!(stderr.contains("BAD: the fetcher failed: ValueError: no such symbol"), "{:?}", stderr);
+    assert!(stderr.contains("1 of 8 symbols failed"), "{:?}", stderr);
+    assert!(!output.status.success());
+}
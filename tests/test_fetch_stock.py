@@ -0,0 +1,321 @@
+"""Checks of fetch_stock.py's helpers. Run from the repository root with
+`python3 -m unittest discover tests`. The script's plotting and data
+libraries are stood in for when missing, so the helpers using none of them
+are still checked; those working on bars need pandas or numpy and are
+skipped without them."""
+
+import importlib
+import math
+import os
+import sys
+import time
+import unittest
+from unittest import mock
+
+sys.path.insert(0, os.path.join(os.path.dirname(__file__), ".."))
+try:
+    import pandas as pd
+except ImportError:
+    pd = None
+try:
+    import numpy as np
+except ImportError:
+    np = None
+for name in ["yfinance", "matplotlib", "matplotlib.pyplot", "matplotlib.dates",
+             "matplotlib.ticker", "matplotlib.lines", "pandas", "numpy"]:
+    try:
+        importlib.import_module(name)
+    except ImportError:
+        sys.modules[name] = mock.MagicMock()
+
+import fetch_stock
+from indicators import ema, heikin_ashi, macd
+
+NAN = math.nan
+
+# New York's regular session, as exchange_hours gives it for US stocks
+US = fetch_stock.US_HOURS
+
+
+def minute_bars(opens):
+    # One bar per (HH:MM, open) on Monday 2 March 2026, naive New York time
+    index = pd.DatetimeIndex([f"2026-03-02 {at}" for at, _ in opens])
+    return pd.DataFrame({"Open": [price for _, price in opens]}, index=index)
+
+
+class DayRangeTest(unittest.TestCase):
+    # A pre-market bar, then the session's four: the second sent no high
+    # and the third a low of 0, as Yahoo does for some international symbols
+    HIGHS = [212.0, 201.5, NAN, 204.25, 203.0]
+    LOWS = [198.0, 199.0, 200.5, 0.0, 201.75]
+    REGULAR = [False, True, True, True, True]
+
+    def test_bars_missing_a_high_or_low_are_left_out(self):
+        high, low = fetch_stock.day_range(self.HIGHS, self.LOWS, self.REGULAR)
+        self.assertEqual(high, (204.25, 3))
+        self.assertEqual(low, (199.0, 1))
+
+    def test_pre_market_counts_without_extended_hours(self):
+        high, low = fetch_stock.day_range(self.HIGHS, self.LOWS, None)
+        self.assertEqual(high, (212.0, 0))
+        self.assertEqual(low, (198.0, 0))
+
+    def test_pre_market_counts_before_the_open(self):
+        high, low = fetch_stock.day_range([212.0, NAN], [198.0, 0.0], [False, False])
+        self.assertEqual(high, (212.0, 0))
+        self.assertEqual(low, (198.0, 0))
+
+    def test_nothing_reported_is_unknown(self):
+        self.assertEqual(fetch_stock.day_range([NAN, 0.0], [0.0, NAN], None), (None, None))
+
+
+class RollingWindowTest(unittest.TestCase):
+    # BTC-USD's 15-minute closes from 09:00 UTC on 1 March 2026 (epoch
+    # 1772355600) to 10:00 the next day: 25 hours, so the window starts an
+    # hour in
+    STAMPS = [1772355600 + i * 900 for i in range(101)]
+    CLOSES = [95000.0 + 25.0 * i for i in range(101)]
+
+    def test_trailing_24_hours_start_after_the_cutoff(self):
+        # The cutoff, 10:00 on the 1st, is a bar: its close is the reference
+        # and the window starts with the bar after it
+        first, reference = fetch_stock.rolling_window(self.STAMPS, self.CLOSES)
+        self.assertEqual((first, reference), (5, 95100.0))
+        self.assertEqual(len(self.STAMPS) - first, 96)
+
+    def test_cutoff_between_bars_takes_the_close_before_it(self):
+        stamps = self.STAMPS[:-1] + [self.STAMPS[-1] + 420]
+        first, reference = fetch_stock.rolling_window(stamps, self.CLOSES)
+        self.assertEqual((first, reference), (5, 95100.0))
+
+    def test_missing_bars_before_the_cutoff_fall_back_further(self):
+        stamps = self.STAMPS[:3] + self.STAMPS[6:]
+        closes = self.CLOSES[:3] + self.CLOSES[6:]
+        first, reference = fetch_stock.rolling_window(stamps, closes)
+        self.assertEqual((first, reference), (3, 95050.0))
+
+    def test_less_than_a_day_of_history_keeps_the_day(self):
+        self.assertIsNone(fetch_stock.rolling_window(self.STAMPS[10:], self.CLOSES[10:]))
+
+
+class LineGradeTest(unittest.TestCase):
+    GRADES = fetch_stock.DEFAULT_CHANGE_GRADES
+
+    def test_thresholds_are_normal_on_the_dot(self):
+        self.assertEqual(fetch_stock.line_grade(0.49, self.GRADES), fetch_stock.FAINT_LINE)
+        self.assertEqual(fetch_stock.line_grade(0.5, self.GRADES), (1.0, 1.0))
+        self.assertEqual(fetch_stock.line_grade(-2.0, self.GRADES), (1.0, 1.0))
+        self.assertEqual(fetch_stock.line_grade(-2.01, self.GRADES), fetch_stock.BOLD_LINE)
+
+    def test_configured_thresholds_move_the_buckets(self):
+        grades = {"faint_below": 1.0, "bold_above": 1.0}
+        self.assertEqual(fetch_stock.line_grade(0.99, grades), fetch_stock.FAINT_LINE)
+        self.assertEqual(fetch_stock.line_grade(1.0, grades), (1.0, 1.0))
+        self.assertEqual(fetch_stock.line_grade(1.01, grades), fetch_stock.BOLD_LINE)
+
+
+class NiceTicksTest(unittest.TestCase):
+    def test_price_span_takes_whole_dollars(self):
+        self.assertEqual(fetch_stock.nice_ticks(228.3, 233.9, 6), [229, 230, 231, 232, 233])
+
+    def test_quiet_percent_day_steps_by_a_tenth(self):
+        self.assertEqual(fetch_stock.nice_ticks(-0.35, 0.42, 8), [-0.3, -0.2, -0.1, 0, 0.1, 0.2, 0.3, 0.4])
+
+    def test_percent_never_steps_below_a_tenth(self):
+        self.assertEqual(fetch_stock.nice_ticks(-0.04, 0.05, 10, percent=True), [0])
+
+    def test_short_axis_gets_fewer_labels(self):
+        self.assertEqual(fetch_stock.nice_ticks(-1.8, 2.3, 5, percent=True), [-1, 0, 1, 2])
+        self.assertEqual(fetch_stock.nice_ticks(-1.8, 2.3, 0, percent=True), [0, 2])
+
+    def test_sub_penny_prices(self):
+        self.assertEqual(fetch_stock.nice_ticks(0.000201, 0.000219, 4), [0.000205, 0.00021, 0.000215])
+
+    def test_flat_line_is_one_tick(self):
+        self.assertEqual(fetch_stock.nice_ticks(231.5, 231.5, 6), [231.5])
+
+    def test_unknown_range_has_no_ticks(self):
+        self.assertEqual(fetch_stock.nice_ticks(NAN, 231.5, 6), [])
+
+
+class FakeTicker:
+    # Stands in for yfinance's Ticker, answering with fixed history metadata
+    def __init__(self, meta):
+        self.meta = meta
+
+    def get_history_metadata(self):
+        if self.meta is None:
+            raise KeyError("no metadata")
+        return self.meta
+
+
+class ExchangeHoursTest(unittest.TestCase):
+    def test_index_keeps_its_exchange_hours(self):
+        gspc = FakeTicker({"exchangeTimezoneName": "America/New_York", "instrumentType": "INDEX"})
+        self.assertEqual(fetch_stock.exchange_hours(gspc, "INDEX"), US)
+        ftse = FakeTicker({"exchangeTimezoneName": "Europe/London", "instrumentType": "INDEX"})
+        self.assertEqual(fetch_stock.exchange_hours(ftse, "INDEX")[0], "Europe/London")
+
+    def test_without_metadata_futures_trade_around_the_clock(self):
+        around_the_clock = ("America/New_York", fetch_stock.time(), fetch_stock.time())
+        self.assertEqual(fetch_stock.exchange_hours(FakeTicker(None), "FUTURE"), around_the_clock)
+        self.assertEqual(fetch_stock.exchange_hours(FakeTicker(None), "INDEX"), US)
+
+    @unittest.skipUnless(pd, "needs pandas")
+    def test_futures_take_yahoos_trading_period(self):
+        # ES=F on 2 March 2026: 18:00 the evening before to 17:00 New York time
+        es = FakeTicker({
+            "exchangeTimezoneName": "America/New_York",
+            "instrumentType": "FUTURE",
+            "currentTradingPeriod": {"regular": {"start": 1772406000, "end": 1772488800}},
+        })
+        self.assertEqual(fetch_stock.exchange_hours(es, "FUTURE"),
+                         ("America/New_York", fetch_stock.time(18, 0), fetch_stock.time(17, 0)))
+
+@unittest.skipUnless(pd, "needs pandas")
+class SessionOpenTest(unittest.TestCase):
+    # Yahoo's regularMarketOpen isn't used: the open always comes from the
+    # bars, as it would for a symbol whose meta open is 0
+    DAY = [("08:00", 101.0), ("09:15", 102.0), ("09:30", 103.0), ("09:31", 104.5), ("15:59", 99.0)]
+
+    def test_first_regular_bar_not_the_latest(self):
+        self.assertEqual(fetch_stock.session_open(minute_bars(self.DAY[2:]), False, US), 103.0)
+
+    def test_regular_bar_wins_over_pre_market(self):
+        self.assertEqual(fetch_stock.session_open(minute_bars(self.DAY), True, US), 103.0)
+
+    def test_pre_market_before_the_session_starts(self):
+        self.assertEqual(fetch_stock.session_open(minute_bars(self.DAY[:2]), True, US), 101.0)
+
+    def test_no_bars_no_open(self):
+        self.assertEqual(fetch_stock.session_open(minute_bars([]), True, US), 0)
+
+
+@unittest.skipUnless(pd, "needs pandas")
+class DstSessionTest(unittest.TestCase):
+    # 2026: US clocks go forward on 8 March, UK clocks on 29 March
+    LONDON = ("Europe/London", *fetch_stock.EXCHANGE_HOURS["Europe/London"])
+
+    def mask(self, day, times, hours):
+        index = pd.DatetimeIndex([f"{day} {t}" for t in times])
+        return list(fetch_stock.session_mask(index, hours))
+
+    def test_london_session_in_new_york_time_while_dst_differs(self):
+        # 08:00-16:30 GMT is 04:00-12:30 in New York, already on EDT
+        self.assertEqual(self.mask("2026-03-16", ["03:59", "04:00", "12:29", "12:30"], self.LONDON),
+                         [False, True, True, False])
+
+    def test_london_session_in_new_york_time_either_side(self):
+        for day in ["2026-03-02", "2026-03-30"]:
+            self.assertEqual(self.mask(day, ["02:59", "03:00", "11:29", "11:30"], self.LONDON),
+                             [False, True, True, False], day)
+
+    def test_new_york_session_after_spring_forward(self):
+        self.assertEqual(self.mask("2026-03-09", ["09:29", "09:30", "15:59", "16:00"], US),
+                         [False, True, True, False])
+
+    def test_repeated_hour_is_squeezed_not_dropped(self):
+        # 00:30, 01:00 and 01:30 EDT, then 01:00 and 01:30 again in EST
+        utc = pd.date_range("2026-11-01 04:30", periods=5, freq="30min", tz="UTC")
+        naive = fetch_stock.naive_times(utc.tz_convert("America/New_York"))
+        self.assertEqual([t.strftime("%H:%M") for t in naive], ["00:15", "00:30", "00:45", "01:00", "01:30"])
+
+
+@unittest.skipUnless(pd, "needs pandas")
+class HeikinAshiTest(unittest.TestCase):
+    def bars(self, rows):
+        return pd.DataFrame(rows, columns=["Open", "High", "Low", "Close"])
+
+    def test_known_sequence(self):
+        ha = heikin_ashi(self.bars([(10, 12, 9, 11), (11, 13, 10, 12), (12, 12.5, 10.5, 11)]))
+        self.assertEqual(list(ha["Close"]), [10.5, 11.5, 11.5])
+        # The first bar opens at its own midpoint, the rest at the previous
+        # HA bar's
+        self.assertEqual(list(ha["Open"]), [10.5, 10.5, 11.0])
+        self.assertEqual(list(ha["High"]), [12, 13, 12.5])
+        self.assertEqual(list(ha["Low"]), [9, 10, 10.5])
+
+    def test_missing_bar_restarts_the_chain(self):
+        ha = heikin_ashi(self.bars([(10, 12, 9, 11), (NAN, NAN, NAN, NAN), (12, 12.5, 10.5, 11)]))
+        self.assertTrue(math.isnan(ha["Open"][1]))
+        self.assertEqual(ha["Open"][2], 11.5)
+
+
+@unittest.skipUnless(pd, "needs pandas")
+class MacdTest(unittest.TestCase):
+    def test_ema_is_seeded_with_the_first_value(self):
+        # Span 3 weighs each new value by a half
+        self.assertEqual(list(ema(pd.Series([1.0, 2.0, 3.0]), 3)), [1.0, 1.5, 2.25])
+
+    def test_crossovers_of_a_rise_then_a_plateau(self):
+        # Flat at 100, up a dollar a bar to 140, then flat again: MACD
+        # crosses above its signal on the first rising bar, and back below
+        # two bars into the plateau
+        closes = pd.Series([100.0] * 40 + [100.0 + i for i in range(1, 41)] + [140.0] * 40)
+        _, _, histogram = macd.macd_lines(closes)
+        above = list(histogram > 0)
+        crossings = [i for i in range(1, len(above)) if above[i] != above[i - 1]]
+        self.assertEqual(crossings, [40, 82])
+        self.assertAlmostEqual(histogram[40], 0.0638177, places=6)
+
+
+@unittest.skipUnless(np, "needs numpy")
+class MinmaxDownsampleTest(unittest.TestCase):
+    # A wavy day of 1,000 bars with a spike up at bar 333 and one down at
+    # 777, thinned ten bars to a bucket
+    BARS = 1000
+    BUCKET = 10
+
+    def day(self):
+        y = 100 + np.sin(np.arange(self.BARS) / 7.0) + np.arange(self.BARS) * 0.01
+        y[333] += 50
+        y[777] -= 50
+        return np.arange(self.BARS), y
+
+    def test_each_buckets_low_and_high_survive(self):
+        x, y = self.day()
+        kept_x, kept_y = fetch_stock.minmax_downsample(x, y, self.BUCKET)
+        kept = set(kept_x.tolist())
+        for start in range(0, self.BARS, self.BUCKET):
+            chunk = y[start:start + self.BUCKET]
+            self.assertIn(start + int(np.argmin(chunk)), kept)
+            self.assertIn(start + int(np.argmax(chunk)), kept)
+        self.assertEqual(kept_y.max(), y[333])
+        self.assertEqual(kept_y.min(), y[777])
+        self.assertLessEqual(len(kept_x), 2 * self.BARS // self.BUCKET + 2)
+
+    def test_time_order_and_endpoints_are_kept(self):
+        x, y = self.day()
+        kept_x, kept_y = fetch_stock.minmax_downsample(x, y, self.BUCKET)
+        self.assertTrue((np.diff(kept_x) > 0).all())
+        self.assertEqual((kept_x[0], kept_x[-1]), (0, self.BARS - 1))
+        self.assertEqual((kept_y[0], kept_y[-1]), (y[0], y[-1]))
+        self.assertTrue((kept_y == y[kept_x]).all())
+
+    def test_short_runs_come_back_unchanged(self):
+        x, y = self.day()
+        run = (x[:2 * self.BUCKET], y[:2 * self.BUCKET])
+        kept_x, kept_y = fetch_stock.minmax_downsample(*run, self.BUCKET)
+        self.assertIs(kept_x, run[0])
+        self.assertIs(kept_y, run[1])
+        self.assertIs(fetch_stock.minmax_downsample(x, y, 1)[0], x)
+
+    def test_10k_bars_thin_in_milliseconds(self):
+        # A week of minute bars and then some, on a chart about 800 pixels
+        # wide: the bucket fetch_and_plot would pick
+        bars = 10000
+        x = np.arange(bars)
+        y = 100 + np.cumsum(np.sin(x / 13.0) * 0.1)
+        bucket = bars // 800
+        timings = []
+        for _ in range(5):
+            start = time.perf_counter()
+            kept_x, _ = fetch_stock.minmax_downsample(x, y, bucket)
+            timings.append(time.perf_counter() - start)
+        print(f"\nminmax_downsample: {bars} bars to {len(kept_x)} in {min(timings) * 1000:.1f} ms")
+        self.assertLessEqual(len(kept_x), 2 * (bars // bucket + 1) + 2)
+        self.assertLess(min(timings), 0.25)
+
+
+if __name__ == "__main__":
+    unittest.main()